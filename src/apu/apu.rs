@@ -1,14 +1,13 @@
-use std::time::Duration;
-
 use smol::channel::Sender;
 
 use crate::{audio::synth::SynthCommand, bus::Bus};
 
 use super::{
   channel::APUChannel,
+  emulated_time,
   timing::{APUTimerInstant, CycleCountRange},
   APUFrameCounterRegister, APUNoiseChannel, APUPulseChannel, APUSequencerMode, APUState,
-  APUStatusRegister, APUSynthChannel, APUTriangleChannel, NTSC_CPU_FREQUENCY,
+  APUStatusRegister, APUSynthChannel, APUTriangleChannel, AudioClock,
 };
 
 #[derive(Debug, Clone)]
@@ -21,6 +20,8 @@ pub struct APU {
   pub status: APUStatusRegister,
   pub frame_counter: APUFrameCounterRegister,
   prev_state: Option<APUState>,
+  // what the recorder last heard, tracked separately since it can start at any point
+  recording_prev_state: Option<APUState>,
   prev_cpu_cycle_count: u64,
 }
 
@@ -40,13 +41,18 @@ impl APU {
       status: 0.into(),
       frame_counter: 0.into(),
       prev_state: None,
+      recording_prev_state: None,
       prev_cpu_cycle_count: 0,
     }
   }
 
   pub fn tick(
     apu: &mut APU,
-    apu_sender: &Sender<SynthCommand<APUSynthChannel>>,
+    // `None` while muted
+    apu_sender: Option<&Sender<SynthCommand<APUSynthChannel>>>,
+    audio_clock: &AudioClock,
+    // gets the same commands timed in emulated time while recording
+    recording_sender: Option<&Sender<SynthCommand<APUSynthChannel>>>,
     cpu_cycle_count: u64,
   ) -> bool {
     let mut irq_set = false;
@@ -75,16 +81,35 @@ impl APU {
       apu.noise.tick(&instant);
 
       let new_state = APUState::capture(apu);
-      let time_since_start = Duration::from_secs_f32(cpu_cycle_count as f32 / NTSC_CPU_FREQUENCY);
-
-      let commands = if let Some(prev_state) = &apu.prev_state {
-        prev_state.diff_commands(&new_state, time_since_start)
-      } else {
-        new_state.commands(time_since_start)
-      };
-      for command in commands {
-        apu_sender.send_blocking(command).unwrap();
+      if let Some(apu_sender) = apu_sender {
+        let time_since_start = audio_clock.time(cpu_cycle_count);
+
+        let commands = if let Some(prev_state) = &apu.prev_state {
+          prev_state.diff_commands(&new_state, time_since_start)
+        } else {
+          new_state.commands(time_since_start)
+        };
+        for command in commands {
+          apu_sender.send_blocking(command).unwrap();
+        }
+      }
+
+      match recording_sender {
+        Some(recording_sender) => {
+          let time = emulated_time(cpu_cycle_count);
+          let commands = if let Some(prev_state) = &apu.recording_prev_state {
+            prev_state.diff_commands(&new_state, time)
+          } else {
+            new_state.commands(time)
+          };
+          for command in commands {
+            recording_sender.send_blocking(command).unwrap();
+          }
+          apu.recording_prev_state = Some(new_state.clone());
+        }
+        None => apu.recording_prev_state = None,
       }
+
       apu.prev_state = Some(new_state);
     }
 
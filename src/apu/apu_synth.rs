@@ -1,4 +1,8 @@
-use crate::audio::{audio_channel::AudioChannel, stream_setup::StreamSpawner, synth::Synth};
+use crate::audio::{
+  audio_channel::AudioChannel,
+  stream_setup::StreamSpawner,
+  synth::{OfflineSynth, PlaybackPosition, Synth},
+};
 
 use super::{APUNoiseOscillator, APUPulseOscillator, APUTriangleOscillator};
 
@@ -44,9 +48,18 @@ impl APUSynth {
         ]
         .into_iter()
         .collect(),
+        playback_position: PlaybackPosition::default(),
       },
     }
   }
+
+  pub fn playback_position(&self) -> PlaybackPosition {
+    self.synth.playback_position.clone()
+  }
+
+  pub fn offline_synth(&self, sample_rate: u32) -> OfflineSynth<APUSynthChannel> {
+    OfflineSynth::new(&self.synth, sample_rate)
+  }
 }
 
 impl StreamSpawner for APUSynth {
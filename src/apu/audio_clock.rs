@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use crate::audio::synth::PlaybackPosition;
+
+use super::NTSC_CPU_FREQUENCY;
+
+// how far ahead of the synth's playback position commands should be scheduled
+const TARGET_LATENCY: Duration = Duration::from_millis(50);
+
+/// How long `cpu_cycle_count` cycles take on a real NES.
+pub fn emulated_time(cpu_cycle_count: u64) -> Duration {
+  Duration::from_secs_f64(cpu_cycle_count as f64 / f64::from(NTSC_CPU_FREQUENCY))
+}
+
+/// Converts CPU cycle counts into the synth's playback time. At 1x this is just emulated time, but
+/// slow motion stretches it out and fast-forward squeezes it, so sound keeps up with the picture.
+#[derive(Debug, Clone)]
+pub struct AudioClock {
+  playback_position: PlaybackPosition,
+  // emulated seconds per played second, or `None` to play everything as soon as it arrives
+  speed: Option<f64>,
+  base_cycle_count: u64,
+  base_time: Duration,
+}
+
+impl Default for AudioClock {
+  fn default() -> Self {
+    Self::new(PlaybackPosition::default())
+  }
+}
+
+impl AudioClock {
+  pub fn new(playback_position: PlaybackPosition) -> Self {
+    Self {
+      playback_position,
+      speed: Some(1.0),
+      base_cycle_count: 0,
+      base_time: TARGET_LATENCY,
+    }
+  }
+
+  pub fn time(&self, cpu_cycle_count: u64) -> Duration {
+    let Some(speed) = self.speed else {
+      return self.base_time;
+    };
+
+    let cycles = cpu_cycle_count.saturating_sub(self.base_cycle_count);
+    self.base_time + Duration::from_secs_f64(cycles as f64 / f64::from(NTSC_CPU_FREQUENCY) / speed)
+  }
+
+  /// How far the audio scheduled so far is from the target latency ahead of playback, relative to
+  /// that latency: 0 when on target, positive when emulation is running ahead of the speakers.
+  pub fn fill_error(&self, cpu_cycle_count: u64) -> f64 {
+    let buffered =
+      self.time(cpu_cycle_count).as_secs_f64() - self.playback_position.get().as_secs_f64();
+    (buffered - TARGET_LATENCY.as_secs_f64()) / TARGET_LATENCY.as_secs_f64()
+  }
+
+  /// Restarts the clock from the current playback position, e.g. after a speed change, pause or
+  /// rewind, so commands neither pile up in the synth's queue nor all play at once.
+  pub fn resync(&mut self, cpu_cycle_count: u64, speed: Option<f64>) {
+    self.base_time = self.playback_position.get() + TARGET_LATENCY;
+    self.base_cycle_count = cpu_cycle_count;
+    self.speed = speed;
+  }
+}
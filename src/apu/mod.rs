@@ -1,5 +1,6 @@
 mod apu;
 mod apu_synth;
+mod audio_clock;
 mod channel;
 mod channel_state;
 mod envelope;
@@ -15,6 +16,7 @@ mod triangle;
 
 pub use apu::*;
 pub use apu_synth::*;
+pub use audio_clock::*;
 pub use channel_state::*;
 pub use length_counter::*;
 pub use noise::*;
@@ -27,6 +29,7 @@ pub use triangle::*;
 mod tests {
   use std::io::BufReader;
 
+  use super::{AudioClock, NTSC_CPU_FREQUENCY};
   use crate::{
     nes::{INESRom, NES},
     ppu::Pixbuf,
@@ -79,4 +82,21 @@ mod tests {
       );
     }
   }
+
+  #[test]
+  fn test_audio_clock_speed() {
+    let mut clock = AudioClock::default();
+    let one_second = NTSC_CPU_FREQUENCY as u64;
+    assert_eq!((clock.time(one_second) - clock.time(0)).as_millis(), 1000);
+
+    clock.resync(one_second, Some(4.0));
+    assert_eq!(
+      (clock.time(2 * one_second) - clock.time(one_second)).as_millis(),
+      250
+    );
+
+    // unlimited speed plays everything as soon as it arrives
+    clock.resync(one_second, None);
+    assert_eq!(clock.time(one_second), clock.time(10 * one_second));
+  }
 }
@@ -4,7 +4,7 @@ use std::{
   fmt::Debug,
   hash::Hash,
   sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     Arc,
   },
   time::Duration,
@@ -35,8 +35,24 @@ impl<ChannelIdentifier: Clone + Eq + PartialEq + Hash + Debug + Send>
   }
 }
 
+/// How far the output stream has played, shared with the emulator so it can pace itself by how
+/// much scheduled audio is left.
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackPosition(Arc<AtomicU64>);
+
+impl PlaybackPosition {
+  pub fn get(&self) -> Duration {
+    Duration::from_nanos(self.0.load(Ordering::Relaxed))
+  }
+
+  fn set(&self, position: Duration) {
+    self.0.store(position.as_nanos() as u64, Ordering::Relaxed);
+  }
+}
+
 pub struct Synth<ChannelIdentifier: Clone + Eq + PartialEq + Hash + Debug + Send> {
   pub channels: HashMap<ChannelIdentifier, Box<dyn AudioChannel>>,
+  pub playback_position: PlaybackPosition,
 }
 
 impl<ChannelIdentifier: Clone + Eq + PartialEq + Hash + Debug + Send + 'static> StreamSpawner
@@ -63,6 +79,7 @@ impl<ChannelIdentifier: Clone + Eq + PartialEq + Hash + Debug + Send + 'static>
       .map(|(id, channel)| (id.clone(), dyn_clone::clone_box(channel)))
       .collect::<HashMap<_, _>>();
     let config = config.clone();
+    let playback_position = self.playback_position.clone();
 
     let (sender, receiver) = smol::channel::unbounded::<SynthCommand<ChannelIdentifier>>();
 
@@ -118,6 +135,7 @@ impl<ChannelIdentifier: Clone + Eq + PartialEq + Hash + Debug + Send + 'static>
             });
 
             if let Some(playback_time_since_start) = playback_time_since_start {
+              playback_position.set(playback_time_since_start);
               loop {
                 let command = command_queue.pop_front();
 
@@ -173,6 +191,59 @@ impl<ChannelIdentifier: Clone + Eq + PartialEq + Hash + Debug + Send + 'static>
   }
 }
 
+/// Renders the same sound as the output stream, but into a buffer on demand, so it can follow
+/// emulated time instead of the sound card's clock.
+pub struct OfflineSynth<ChannelIdentifier: Clone + Eq + PartialEq + Hash + Debug + Send> {
+  channels: HashMap<ChannelIdentifier, Box<dyn AudioChannel>>,
+  sample_rate: u32,
+  samples_rendered: u64,
+}
+
+impl<ChannelIdentifier: Clone + Eq + PartialEq + Hash + Debug + Send>
+  OfflineSynth<ChannelIdentifier>
+{
+  pub fn new(synth: &Synth<ChannelIdentifier>, sample_rate: u32) -> Self {
+    Self {
+      channels: synth.channels.clone(),
+      sample_rate,
+      samples_rendered: 0,
+    }
+  }
+
+  pub fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  /// Appends mono samples to `output` until `time` since the synth started.
+  pub fn render_until(&mut self, time: Duration, output: &mut Vec<i16>) {
+    let target = (time.as_secs_f64() * f64::from(self.sample_rate)) as u64;
+    if target <= self.samples_rendered {
+      return;
+    }
+
+    let start = output.len();
+    output.resize(start + (target - self.samples_rendered) as usize, 0);
+    process_frame(
+      &mut output[start..],
+      self.channels.values_mut().collect(),
+      1,
+      self.sample_rate as f32,
+      Duration::from_secs_f64(self.samples_rendered as f64 / f64::from(self.sample_rate)),
+    );
+    self.samples_rendered = target;
+  }
+
+  pub fn handle_command(&mut self, command: SynthCommand<ChannelIdentifier>) {
+    match command {
+      SynthCommand::ChannelCommand(index, command, _) => {
+        if let Some(channel) = self.channels.get_mut(&index) {
+          channel.handle_command(command);
+        }
+      }
+    }
+  }
+}
+
 fn process_frame<SampleType>(
   output: &mut [SampleType],
   mut channels: Vec<&mut Box<dyn AudioChannel>>,
@@ -1,4 +1,6 @@
-use crate::{bus::Bus, cpu::CPUBusTrait, ppu::PPUCPUBusTrait};
+use crate::{
+  bus::Bus, cheats::CheatEngine, cpu::CPUBusTrait, debugger::CodeDataLogger, ppu::PPUCPUBusTrait,
+};
 
 pub enum InterceptorResult<T> {
   Intercepted(T),
@@ -17,17 +19,29 @@ pub trait BusInterceptor<AddrType: Clone>: Send + Sync {
   fn intercept_read_side_effects(&mut self, _addr: AddrType) -> InterceptorResult<()> {
     InterceptorResult::NotIntercepted
   }
+
+  // lets the interceptor override the final result of a read, whether it was intercepted or not
+  fn patch_read_value(&self, _addr: AddrType, value: Option<u8>) -> Option<u8> {
+    value
+  }
+
+  // called for every read that reaches the bus (but not readonly peeks), e.g. for code/data logging
+  fn observe_read(&mut self, _addr: AddrType) {}
 }
 
 impl<AddrType: Clone, I: BusInterceptor<AddrType> + ?Sized> Bus<AddrType> for I {
   fn try_read_readonly(&self, addr: AddrType) -> Option<u8> {
-    match self.intercept_read_readonly(addr.clone()) {
+    let value = match self.intercept_read_readonly(addr.clone()) {
       InterceptorResult::Intercepted(value) => value,
-      InterceptorResult::NotIntercepted => self.get_inner().try_read_readonly(addr),
-    }
+      InterceptorResult::NotIntercepted => self.get_inner().try_read_readonly(addr.clone()),
+    };
+
+    self.patch_read_value(addr, value)
   }
 
   fn read_side_effects(&mut self, addr: AddrType) {
+    self.observe_read(addr.clone());
+
     match self.intercept_read_side_effects(addr.clone()) {
       InterceptorResult::Intercepted(_) => {}
       InterceptorResult::NotIntercepted => self.get_inner_mut().read_side_effects(addr),
@@ -49,10 +63,18 @@ impl<BusType: CPUBusTrait + 'static, I: BusInterceptor<u16, BusType = BusType>>
 
   fn tick_apu(
     &mut self,
-    sender: &<crate::apu::APUSynth as crate::audio::stream_setup::StreamSpawner>::OutputType,
+    sender: Option<
+      &<crate::apu::APUSynth as crate::audio::stream_setup::StreamSpawner>::OutputType,
+    >,
+    audio_clock: &crate::apu::AudioClock,
+    recording_sender: Option<
+      &<crate::apu::APUSynth as crate::audio::stream_setup::StreamSpawner>::OutputType,
+    >,
     cpu_cycle_count: u64,
   ) -> bool {
-    self.get_inner_mut().tick_apu(sender, cpu_cycle_count)
+    self
+      .get_inner_mut()
+      .tick_apu(sender, audio_clock, recording_sender, cpu_cycle_count)
   }
 
   fn set_controller_button_state(
@@ -66,6 +88,34 @@ impl<BusType: CPUBusTrait + 'static, I: BusInterceptor<u16, BusType = BusType>>
       .set_controller_button_state(controller_index, button, pressed)
   }
 
+  fn work_ram(&self) -> &[u8] {
+    self.get_inner().work_ram()
+  }
+
+  fn work_ram_mut(&mut self) -> &mut [u8] {
+    self.get_inner_mut().work_ram_mut()
+  }
+
+  fn take_input_polls(&mut self) -> u32 {
+    self.get_inner_mut().take_input_polls()
+  }
+
+  fn cheats(&self) -> &CheatEngine {
+    self.get_inner().cheats()
+  }
+
+  fn cheats_mut(&mut self) -> &mut CheatEngine {
+    self.get_inner_mut().cheats_mut()
+  }
+
+  fn code_data_logger(&self) -> &CodeDataLogger {
+    self.get_inner().code_data_logger()
+  }
+
+  fn code_data_logger_mut(&mut self) -> &mut CodeDataLogger {
+    self.get_inner_mut().code_data_logger_mut()
+  }
+
   fn ppu_cpu_bus<'a>(&'a self) -> &'a (dyn PPUCPUBusTrait + 'a) {
     self.get_inner().ppu_cpu_bus()
   }
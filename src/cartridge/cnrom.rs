@@ -1,6 +1,6 @@
 use crate::{
   cpu::CPUBus,
-  nes::INESRom,
+  nes::{INESRom, SharedBuffer, SharedMemory},
   ppu::{PPUCPUBus, PPUMemory},
 };
 
@@ -11,10 +11,21 @@ use super::{
 
 #[derive(Debug, Clone)]
 pub struct CNROMCPUBusInterceptor {
-  prg_rom: [u8; 32 * 1024],
+  prg_rom: SharedMemory<Vec<u8>>,
   bus: CPUBus<CNROMPPUMemoryInterceptor>,
 }
 
+impl CNROMCPUBusInterceptor {
+  fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    if addr < 0x8000 {
+      return None;
+    }
+
+    // 16KiB ROMs are mirrored into $C000-$FFFF
+    usize::from(addr - 0x8000).checked_rem(self.prg_rom.len())
+  }
+}
+
 impl BusInterceptor<u16> for CNROMCPUBusInterceptor {
   type BusType = CPUBus<CNROMPPUMemoryInterceptor>;
 
@@ -30,7 +41,7 @@ impl BusInterceptor<u16> for CNROMCPUBusInterceptor {
     if addr < 0x8000 {
       InterceptorResult::NotIntercepted
     } else {
-      InterceptorResult::Intercepted(Some(self.prg_rom[usize::from(addr - 0x8000)]))
+      InterceptorResult::Intercepted(self.prg_rom_offset(addr).map(|offset| self.prg_rom[offset]))
     }
   }
 
@@ -42,15 +53,35 @@ impl BusInterceptor<u16> for CNROMCPUBusInterceptor {
       InterceptorResult::Intercepted(())
     }
   }
+
+  fn patch_read_value(&self, addr: u16, value: Option<u8>) -> Option<u8> {
+    self.bus.cheats.patch_read(addr, value)
+  }
+
+  fn observe_read(&mut self, addr: u16) {
+    if !self.bus.code_data_logger.is_enabled() {
+      return;
+    }
+
+    if let Some(prg_rom_offset) = self.prg_rom_offset(addr) {
+      self.bus.code_data_logger.log_prg_read(prg_rom_offset, addr);
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
 pub struct CNROMPPUMemoryInterceptor {
   bank_select: u8,
-  chr_rom: [u8; 4 * 8 * 1024],
+  chr_rom: SharedMemory<[u8; 4 * 8 * 1024]>,
   bus: PPUMemory,
 }
 
+impl CNROMPPUMemoryInterceptor {
+  fn chr_mem_offset(&self, addr: u16) -> Option<usize> {
+    (addr < 0x2000).then(|| (self.bank_select as usize * 8 * 1024) + usize::from(addr))
+  }
+}
+
 impl BusInterceptor<u16> for CNROMPPUMemoryInterceptor {
   type BusType = PPUMemory;
 
@@ -63,22 +94,29 @@ impl BusInterceptor<u16> for CNROMPPUMemoryInterceptor {
   }
 
   fn intercept_read_readonly(&self, addr: u16) -> InterceptorResult<Option<u8>> {
-    if addr < 0x2000 {
-      InterceptorResult::Intercepted(Some(
-        self.chr_rom[(self.bank_select as usize * 8 * 1024) + usize::from(addr)],
-      ))
-    } else {
-      InterceptorResult::NotIntercepted
+    match self.chr_mem_offset(addr) {
+      Some(offset) => InterceptorResult::Intercepted(Some(self.chr_rom[offset])),
+      None => InterceptorResult::NotIntercepted,
     }
   }
 
   fn intercept_write(&mut self, addr: u16, value: u8) -> InterceptorResult<()> {
-    if addr < 0x2000 {
-      let addr = (self.bank_select as usize * 8 * 1024) + usize::from(addr);
-      self.chr_rom[addr] = value;
-      InterceptorResult::Intercepted(())
-    } else {
-      InterceptorResult::NotIntercepted
+    match self.chr_mem_offset(addr) {
+      Some(offset) => {
+        self.chr_rom[offset] = value;
+        InterceptorResult::Intercepted(())
+      }
+      None => InterceptorResult::NotIntercepted,
+    }
+  }
+
+  fn observe_read(&mut self, addr: u16) {
+    if !self.bus.chr_data_logger.is_enabled() {
+      return;
+    }
+
+    if let Some(chr_mem_offset) = self.chr_mem_offset(addr) {
+      self.bus.chr_data_logger.log_chr_read(chr_mem_offset);
     }
   }
 }
@@ -94,13 +132,6 @@ impl Mapper for CNROM {
   type PPUMemoryInterceptor = CNROMPPUMemoryInterceptor;
 
   fn from_ines_rom(rom: INESRom) -> Self {
-    let mut prg_rom: [u8; 32 * 1024] = [0; 32 * 1024];
-    if !rom.prg_data.is_empty() {
-      for chunk in prg_rom.chunks_exact_mut(rom.prg_data.len()) {
-        chunk.copy_from_slice(&rom.prg_data);
-      }
-    }
-
     let mut chr_rom: [u8; 4 * 8 * 1024] = [0; 4 * 8 * 1024];
     if !rom.chr_data.is_empty() {
       for chunk in chr_rom.chunks_exact_mut(rom.chr_data.len()) {
@@ -110,12 +141,12 @@ impl Mapper for CNROM {
 
     let ppu_memory = CNROMPPUMemoryInterceptor {
       bank_select: 0,
-      chr_rom,
+      chr_rom: SharedMemory::new(chr_rom),
       bus: PPUMemory::new(rom.initial_mirroring()),
     };
 
     let cpu_bus = CNROMCPUBusInterceptor {
-      prg_rom,
+      prg_rom: SharedMemory::new(rom.prg_data),
       bus: CPUBus::new(PPUCPUBus::new(Box::new(ppu_memory))),
     };
 
@@ -129,4 +160,30 @@ impl Mapper for CNROM {
   fn cpu_bus_mut(&mut self) -> &mut Self::CPUBusInterceptor {
     &mut self.cpu_bus
   }
+
+  fn prg_rom(&self) -> &[u8] {
+    &self.cpu_bus.prg_rom[..]
+  }
+
+  fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    self.cpu_bus.prg_rom_offset(addr)
+  }
+
+  fn prg_rom_mut(&mut self) -> &mut [u8] {
+    &mut self.cpu_bus.prg_rom[..]
+  }
+
+  fn chr_mem(&self) -> &[u8] {
+    &self.ppu_memory().chr_rom[..]
+  }
+
+  fn chr_mem_mut(&mut self) -> &mut [u8] {
+    &mut self.ppu_memory_mut().chr_rom[..]
+  }
+
+  fn visit_shared_buffers(&mut self, visit: &mut dyn FnMut(&mut dyn SharedBuffer)) {
+    visit(&mut self.cpu_bus.prg_rom);
+    visit(&mut self.ppu_memory_mut().chr_rom);
+    self.cpu_bus.bus.visit_shared_buffers(visit);
+  }
 }
@@ -2,6 +2,7 @@ use bitfield_struct::bitfield;
 
 use crate::{
   cpu::CPUBus,
+  nes::{SharedBuffer, SharedMemory},
   ppu::{PPUCPUBus, PPUMemory},
 };
 
@@ -120,70 +121,83 @@ impl MMC1ShiftRegister {
 
 #[derive(Debug, Clone)]
 pub struct MMC1CPUBusInterceptor {
-  pub prg_rom: Vec<u8>,
+  pub prg_rom: SharedMemory<Vec<u8>>,
   pub control: MMC1ControlRegister,
   pub prg_bank_select: u8,
   pub prg_ram_bank_select: u8,
-  pub prg_ram: [u8; 32 * 1024],
+  pub prg_ram: SharedMemory<[u8; 32 * 1024]>,
   pub shift_register: MMC1ShiftRegister,
   bus: CPUBus<MMC1PPUMemoryInterceptor>,
 }
 
-impl BusInterceptor<u16> for MMC1CPUBusInterceptor {
-  type BusType = CPUBus<MMC1PPUMemoryInterceptor>;
-
-  fn get_inner(&self) -> &CPUBus<MMC1PPUMemoryInterceptor> {
-    &self.bus
-  }
-
-  fn get_inner_mut(&mut self) -> &mut CPUBus<MMC1PPUMemoryInterceptor> {
-    &mut self.bus
-  }
-
-  fn intercept_read_readonly(&self, addr: u16) -> InterceptorResult<Option<u8>> {
-    if addr < 0x6000 {
-      InterceptorResult::NotIntercepted
-    } else if addr < 0x8000 {
-      let offset = (addr - 0x6000) as usize;
+impl MMC1CPUBusInterceptor {
+  fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    if addr < 0x8000 {
+      return None;
+    }
 
-      let prg_ram_addr = (0x2000 * (self.prg_ram_bank_select as usize)) + offset;
-      InterceptorResult::Intercepted(Some(self.prg_ram[prg_ram_addr % self.prg_ram.len()]))
-    } else if addr < 0xc000 {
+    let prg_addr = if addr < 0xc000 {
       let offset = (addr - 0x8000) as usize;
 
-      let prg_addr = match self.control.prg_rom_bank_mode() {
+      match self.control.prg_rom_bank_mode() {
         MMC1PRGROMBankMode::Full32KB1 | MMC1PRGROMBankMode::Full32KB2 => {
           (0x8000 * (self.prg_bank_select as usize)) + offset
         }
         MMC1PRGROMBankMode::FixedLow => offset,
         MMC1PRGROMBankMode::FixedHigh => (0x4000 * (self.prg_bank_select as usize)) + offset,
-      };
-
-      InterceptorResult::Intercepted(Some(self.prg_rom[prg_addr % self.prg_rom.len()]))
+      }
     } else {
       let offset = (addr - 0xc000) as usize;
 
-      let prg_addr = match self.control.prg_rom_bank_mode() {
+      match self.control.prg_rom_bank_mode() {
         MMC1PRGROMBankMode::Full32KB1 | MMC1PRGROMBankMode::Full32KB2 => {
           (0x8000 * (self.prg_bank_select as usize)) + offset + 0x2000
         }
         MMC1PRGROMBankMode::FixedLow => (0x4000 * (self.prg_bank_select as usize)) + offset,
         MMC1PRGROMBankMode::FixedHigh => (self.prg_rom.len() - 0x4000) + offset,
-      };
+      }
+    };
 
-      InterceptorResult::Intercepted(Some(self.prg_rom[prg_addr % self.prg_rom.len()]))
+    Some(prg_addr % self.prg_rom.len())
+  }
+
+  fn prg_ram_offset(&self, addr: u16) -> Option<usize> {
+    if !(0x6000..0x8000).contains(&addr) {
+      return None;
     }
+
+    let offset = (addr - 0x6000) as usize;
+    let prg_ram_addr = (0x2000 * (self.prg_ram_bank_select as usize)) + offset;
+    Some(prg_ram_addr % self.prg_ram.len())
   }
+}
 
-  fn intercept_write(&mut self, addr: u16, value: u8) -> InterceptorResult<()> {
+impl BusInterceptor<u16> for MMC1CPUBusInterceptor {
+  type BusType = CPUBus<MMC1PPUMemoryInterceptor>;
+
+  fn get_inner(&self) -> &CPUBus<MMC1PPUMemoryInterceptor> {
+    &self.bus
+  }
+
+  fn get_inner_mut(&mut self) -> &mut CPUBus<MMC1PPUMemoryInterceptor> {
+    &mut self.bus
+  }
+
+  fn intercept_read_readonly(&self, addr: u16) -> InterceptorResult<Option<u8>> {
     if addr < 0x6000 {
       InterceptorResult::NotIntercepted
-    } else if addr < 0x8000 {
-      let offset = (addr - 0x6000) as usize;
+    } else if let Some(offset) = self.prg_ram_offset(addr) {
+      InterceptorResult::Intercepted(Some(self.prg_ram[offset]))
+    } else {
+      InterceptorResult::Intercepted(self.prg_rom_offset(addr).map(|offset| self.prg_rom[offset]))
+    }
+  }
 
-      let prg_ram_addr = (0x2000 * (self.prg_ram_bank_select as usize)) + offset;
-      let prg_ram_size = self.prg_ram.len();
-      self.prg_ram[prg_ram_addr % prg_ram_size] = value;
+  fn intercept_write(&mut self, addr: u16, value: u8) -> InterceptorResult<()> {
+    if addr < 0x6000 {
+      InterceptorResult::NotIntercepted
+    } else if let Some(offset) = self.prg_ram_offset(addr) {
+      self.prg_ram[offset] = value;
       InterceptorResult::Intercepted(())
     } else {
       if value & (1 << 7) > 0 {
@@ -221,6 +235,20 @@ impl BusInterceptor<u16> for MMC1CPUBusInterceptor {
       InterceptorResult::Intercepted(())
     }
   }
+
+  fn patch_read_value(&self, addr: u16, value: Option<u8>) -> Option<u8> {
+    self.bus.cheats.patch_read(addr, value)
+  }
+
+  fn observe_read(&mut self, addr: u16) {
+    if !self.bus.code_data_logger.is_enabled() {
+      return;
+    }
+
+    if let Some(prg_rom_offset) = self.prg_rom_offset(addr) {
+      self.bus.code_data_logger.log_prg_read(prg_rom_offset, addr);
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -229,77 +257,73 @@ pub struct MMC1PPUMemoryInterceptor {
   pub control: MMC1ControlRegister,
   pub chr_low_bank_select: u8,
   pub chr_high_bank_select: u8,
-  pub chr_mem: Vec<u8>,
+  pub chr_mem: SharedMemory<Vec<u8>>,
 }
 
-impl BusInterceptor<u16> for MMC1PPUMemoryInterceptor {
-  type BusType = PPUMemory;
-
-  fn get_inner(&self) -> &PPUMemory {
-    &self.bus
-  }
-
-  fn get_inner_mut(&mut self) -> &mut PPUMemory {
-    &mut self.bus
-  }
+impl MMC1PPUMemoryInterceptor {
+  fn chr_mem_offset(&self, addr: u16) -> Option<usize> {
+    if addr >= 0x2000 {
+      return None;
+    }
 
-  fn intercept_read_readonly(&self, addr: u16) -> InterceptorResult<Option<u8>> {
-    if addr < 0x1000 {
+    let chr_addr = if addr < 0x1000 {
       let offset = addr as usize;
 
-      let chr_addr = match self.control.chr_rom_bank_mode() {
+      match self.control.chr_rom_bank_mode() {
         MMC1CHRROMBankMode::Full8KB => (self.chr_low_bank_select as usize * 8 * 1024) + offset,
         MMC1CHRROMBankMode::Split4KB => (self.chr_low_bank_select as usize * 4 * 1024) + offset,
-      };
-
-      InterceptorResult::Intercepted(Some(self.chr_mem[chr_addr % self.chr_mem.len()]))
-    } else if addr < 0x2000 {
+      }
+    } else {
       let offset = (addr - 0x1000) as usize;
 
-      let chr_addr = match self.control.chr_rom_bank_mode() {
+      match self.control.chr_rom_bank_mode() {
         MMC1CHRROMBankMode::Full8KB => {
           // high bank select is ignored in 8kb mode
           (self.chr_low_bank_select as usize * 8 * 1024) + offset + 0x1000
         }
         MMC1CHRROMBankMode::Split4KB => (self.chr_high_bank_select as usize * 4 * 1024) + offset,
-      };
+      }
+    };
 
-      InterceptorResult::Intercepted(Some(self.chr_mem[chr_addr % self.chr_mem.len()]))
-    } else {
-      InterceptorResult::NotIntercepted
-    }
+    Some(chr_addr % self.chr_mem.len())
   }
+}
 
-  fn intercept_write(&mut self, addr: u16, value: u8) -> InterceptorResult<()> {
-    if addr < 0x1000 {
-      let offset = addr as usize;
+impl BusInterceptor<u16> for MMC1PPUMemoryInterceptor {
+  type BusType = PPUMemory;
 
-      let chr_addr = match self.control.chr_rom_bank_mode() {
-        MMC1CHRROMBankMode::Full8KB => (self.chr_low_bank_select as usize * 8 * 1024) + offset,
-        MMC1CHRROMBankMode::Split4KB => (self.chr_low_bank_select as usize * 4 * 1024) + offset,
-      };
+  fn get_inner(&self) -> &PPUMemory {
+    &self.bus
+  }
 
-      let chr_mem_size = self.chr_mem.len();
-      self.chr_mem[chr_addr % chr_mem_size] = value;
+  fn get_inner_mut(&mut self) -> &mut PPUMemory {
+    &mut self.bus
+  }
 
-      InterceptorResult::Intercepted(())
-    } else if addr < 0x2000 {
-      let offset = (addr - 0x1000) as usize;
+  fn intercept_read_readonly(&self, addr: u16) -> InterceptorResult<Option<u8>> {
+    match self.chr_mem_offset(addr) {
+      Some(offset) => InterceptorResult::Intercepted(Some(self.chr_mem[offset])),
+      None => InterceptorResult::NotIntercepted,
+    }
+  }
 
-      let chr_addr = match self.control.chr_rom_bank_mode() {
-        MMC1CHRROMBankMode::Full8KB => {
-          // high bank select is ignored in 8kb mode
-          (self.chr_low_bank_select as usize * 8 * 1024) + offset + 0x1000
-        }
-        MMC1CHRROMBankMode::Split4KB => (self.chr_high_bank_select as usize * 4 * 1024) + offset,
-      };
+  fn intercept_write(&mut self, addr: u16, value: u8) -> InterceptorResult<()> {
+    match self.chr_mem_offset(addr) {
+      Some(offset) => {
+        self.chr_mem[offset] = value;
+        InterceptorResult::Intercepted(())
+      }
+      None => InterceptorResult::NotIntercepted,
+    }
+  }
 
-      let chr_mem_size = self.chr_mem.len();
-      self.chr_mem[chr_addr % chr_mem_size] = value;
+  fn observe_read(&mut self, addr: u16) {
+    if !self.bus.chr_data_logger.is_enabled() {
+      return;
+    }
 
-      InterceptorResult::Intercepted(())
-    } else {
-      InterceptorResult::NotIntercepted
+    if let Some(chr_mem_offset) = self.chr_mem_offset(addr) {
+      self.bus.chr_data_logger.log_chr_read(chr_mem_offset);
     }
   }
 }
@@ -327,17 +351,17 @@ impl Mapper for MMC1 {
       bus: PPUMemory::new(rom.initial_mirroring()),
       chr_high_bank_select: 0,
       chr_low_bank_select: 0,
-      chr_mem: chr_data,
+      chr_mem: SharedMemory::new(chr_data),
       control: MMC1ControlRegister(0).with_prg_rom_bank_mode(MMC1PRGROMBankMode::FixedHigh),
     };
 
     let cpu_bus = MMC1CPUBusInterceptor {
       bus: CPUBus::new(PPUCPUBus::new(Box::new(ppu_memory_interceptor))),
       control: MMC1ControlRegister(0).with_prg_rom_bank_mode(MMC1PRGROMBankMode::FixedHigh),
-      prg_rom: rom.prg_data,
+      prg_rom: SharedMemory::new(rom.prg_data),
       prg_bank_select: 0,
       prg_ram_bank_select: 0,
-      prg_ram: [0; 32 * 1024],
+      prg_ram: SharedMemory::new([0; 32 * 1024]),
       shift_register: MMC1ShiftRegister::new(),
     };
 
@@ -351,4 +375,43 @@ impl Mapper for MMC1 {
   fn cpu_bus_mut(&mut self) -> &mut Self::CPUBusInterceptor {
     &mut self.cpu_bus
   }
+
+  fn prg_rom(&self) -> &[u8] {
+    &self.cpu_bus.prg_rom[..]
+  }
+
+  fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    self.cpu_bus.prg_rom_offset(addr)
+  }
+
+  fn prg_rom_mut(&mut self) -> &mut [u8] {
+    &mut self.cpu_bus.prg_rom[..]
+  }
+
+  fn chr_mem(&self) -> &[u8] {
+    &self.ppu_memory().chr_mem[..]
+  }
+
+  fn chr_mem_mut(&mut self) -> &mut [u8] {
+    &mut self.ppu_memory_mut().chr_mem[..]
+  }
+
+  fn prg_ram(&self) -> Option<&[u8]> {
+    Some(&self.cpu_bus.prg_ram[..])
+  }
+
+  fn prg_ram_mut(&mut self) -> Option<&mut [u8]> {
+    Some(&mut self.cpu_bus.prg_ram[..])
+  }
+
+  fn prg_ram_offset(&self, addr: u16) -> Option<usize> {
+    self.cpu_bus.prg_ram_offset(addr)
+  }
+
+  fn visit_shared_buffers(&mut self, visit: &mut dyn FnMut(&mut dyn SharedBuffer)) {
+    visit(&mut self.cpu_bus.prg_rom);
+    visit(&mut self.cpu_bus.prg_ram);
+    visit(&mut self.ppu_memory_mut().chr_mem);
+    self.cpu_bus.bus.visit_shared_buffers(visit);
+  }
 }
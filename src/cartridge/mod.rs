@@ -3,10 +3,10 @@ use dyn_clone::DynClone;
 use self::{bus_interceptor::BusInterceptor, cnrom::CNROM, mmc1::MMC1, nrom::NROM, uxrom::UxROM};
 use crate::{
   cpu::{CPUBus, CPUBusTrait},
-  nes::INESRom,
+  nes::{INESRom, SharedBuffer},
   ppu::{PPUCPUBusTrait, PPUMemory, PPUMemoryTrait},
 };
-use std::fmt::Debug;
+use std::{fmt::Debug, mem};
 
 pub mod bus_interceptor;
 mod cnrom;
@@ -35,6 +35,29 @@ pub trait Mapper: Debug + DynClone {
   fn cpu_bus(&self) -> &Self::CPUBusInterceptor;
   fn cpu_bus_mut(&mut self) -> &mut Self::CPUBusInterceptor;
 
+  fn prg_rom(&self) -> &[u8];
+  fn prg_rom_mut(&mut self) -> &mut [u8];
+  /// Offset into `prg_rom` currently mapped at a CPU address, if any.
+  fn prg_rom_offset(&self, addr: u16) -> Option<usize>;
+  fn chr_mem(&self) -> &[u8];
+  fn chr_mem_mut(&mut self) -> &mut [u8];
+
+  fn prg_ram(&self) -> Option<&[u8]> {
+    None
+  }
+
+  fn prg_ram_mut(&mut self) -> Option<&mut [u8]> {
+    None
+  }
+
+  /// Offset into `prg_ram` currently mapped at a CPU address, if any.
+  fn prg_ram_offset(&self, _addr: u16) -> Option<usize> {
+    None
+  }
+
+  /// Calls `visit` on every RAM and ROM buffer, always in the same order.
+  fn visit_shared_buffers(&mut self, visit: &mut dyn FnMut(&mut dyn SharedBuffer));
+
   fn ppu_memory(&self) -> &Self::PPUMemoryInterceptor {
     self.cpu_bus().get_inner().ppu_cpu_bus.ppu_memory.as_ref()
   }
@@ -54,6 +77,7 @@ macro_rules! memoizing_bus_getters {
   () => {};
 }
 
+#[derive(Clone)]
 pub enum Cartridge {
   NROM(Box<NROM>),
   MMC1(Box<MMC1>),
@@ -92,6 +116,152 @@ impl Cartridge {
     }
   }
 
+  pub fn prg_rom(&self) -> &[u8] {
+    match self {
+      Cartridge::NROM(mapper) => mapper.prg_rom(),
+      Cartridge::MMC1(mapper) => mapper.prg_rom(),
+      Cartridge::UxROM(mapper) => mapper.prg_rom(),
+      Cartridge::CNROM(mapper) => mapper.prg_rom(),
+    }
+  }
+
+  pub fn prg_rom_mut(&mut self) -> &mut [u8] {
+    match self {
+      Cartridge::NROM(mapper) => mapper.prg_rom_mut(),
+      Cartridge::MMC1(mapper) => mapper.prg_rom_mut(),
+      Cartridge::UxROM(mapper) => mapper.prg_rom_mut(),
+      Cartridge::CNROM(mapper) => mapper.prg_rom_mut(),
+    }
+  }
+
+  pub fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    match self {
+      Cartridge::NROM(mapper) => mapper.prg_rom_offset(addr),
+      Cartridge::MMC1(mapper) => mapper.prg_rom_offset(addr),
+      Cartridge::UxROM(mapper) => mapper.prg_rom_offset(addr),
+      Cartridge::CNROM(mapper) => mapper.prg_rom_offset(addr),
+    }
+  }
+
+  pub fn chr_mem(&self) -> &[u8] {
+    match self {
+      Cartridge::NROM(mapper) => mapper.chr_mem(),
+      Cartridge::MMC1(mapper) => mapper.chr_mem(),
+      Cartridge::UxROM(mapper) => mapper.chr_mem(),
+      Cartridge::CNROM(mapper) => mapper.chr_mem(),
+    }
+  }
+
+  pub fn chr_mem_mut(&mut self) -> &mut [u8] {
+    match self {
+      Cartridge::NROM(mapper) => mapper.chr_mem_mut(),
+      Cartridge::MMC1(mapper) => mapper.chr_mem_mut(),
+      Cartridge::UxROM(mapper) => mapper.chr_mem_mut(),
+      Cartridge::CNROM(mapper) => mapper.chr_mem_mut(),
+    }
+  }
+
+  pub fn prg_ram(&self) -> Option<&[u8]> {
+    match self {
+      Cartridge::NROM(mapper) => mapper.prg_ram(),
+      Cartridge::MMC1(mapper) => mapper.prg_ram(),
+      Cartridge::UxROM(mapper) => mapper.prg_ram(),
+      Cartridge::CNROM(mapper) => mapper.prg_ram(),
+    }
+  }
+
+  pub fn prg_ram_mut(&mut self) -> Option<&mut [u8]> {
+    match self {
+      Cartridge::NROM(mapper) => mapper.prg_ram_mut(),
+      Cartridge::MMC1(mapper) => mapper.prg_ram_mut(),
+      Cartridge::UxROM(mapper) => mapper.prg_ram_mut(),
+      Cartridge::CNROM(mapper) => mapper.prg_ram_mut(),
+    }
+  }
+
+  pub fn prg_ram_offset(&self, addr: u16) -> Option<usize> {
+    match self {
+      Cartridge::NROM(mapper) => mapper.prg_ram_offset(addr),
+      Cartridge::MMC1(mapper) => mapper.prg_ram_offset(addr),
+      Cartridge::UxROM(mapper) => mapper.prg_ram_offset(addr),
+      Cartridge::CNROM(mapper) => mapper.prg_ram_offset(addr),
+    }
+  }
+
+  /// Writes the RAM or ROM behind a CPU address directly, without the side effects a write through
+  /// the bus can have, like switching banks. Does nothing for I/O registers.
+  pub fn poke_cpu(&mut self, addr: u16, value: u8) {
+    let byte = if addr < 0x2000 {
+      self
+        .cpu_bus_mut()
+        .work_ram_mut()
+        .get_mut(usize::from(addr) % 0x800)
+    } else if let Some(offset) = self.prg_ram_offset(addr) {
+      self
+        .prg_ram_mut()
+        .and_then(|prg_ram| prg_ram.get_mut(offset))
+    } else if let Some(offset) = self.prg_rom_offset(addr) {
+      self.prg_rom_mut().get_mut(offset)
+    } else {
+      None
+    };
+
+    if let Some(byte) = byte {
+      *byte = value;
+    }
+  }
+
+  pub fn visit_shared_buffers(&mut self, visit: &mut dyn FnMut(&mut dyn SharedBuffer)) {
+    match self {
+      Cartridge::NROM(mapper) => mapper.visit_shared_buffers(visit),
+      Cartridge::MMC1(mapper) => mapper.visit_shared_buffers(visit),
+      Cartridge::UxROM(mapper) => mapper.visit_shared_buffers(visit),
+      Cartridge::CNROM(mapper) => mapper.visit_shared_buffers(visit),
+    }
+  }
+
+  /// Memory taken by the mapper's state, not counting the buffers it shares.
+  pub fn state_size(&self) -> usize {
+    match self {
+      Cartridge::NROM(mapper) => {
+        mem::size_of_val(&**mapper) + mem::size_of_val(mapper.ppu_memory())
+      }
+      Cartridge::MMC1(mapper) => {
+        mem::size_of_val(&**mapper) + mem::size_of_val(mapper.ppu_memory())
+      }
+      Cartridge::UxROM(mapper) => {
+        mem::size_of_val(&**mapper) + mem::size_of_val(mapper.ppu_memory())
+      }
+      Cartridge::CNROM(mapper) => {
+        mem::size_of_val(&**mapper) + mem::size_of_val(mapper.ppu_memory())
+      }
+    }
+  }
+
+  pub fn set_code_data_logging(&mut self, enabled: bool) {
+    let prg_rom_len = self.prg_rom().len();
+    let chr_mem_len = self.chr_mem().len();
+
+    self
+      .cpu_bus_mut()
+      .code_data_logger_mut()
+      .set_enabled(enabled, prg_rom_len);
+    self
+      .ppu_cpu_bus_mut()
+      .ppu_memory_mut()
+      .chr_data_logger_mut()
+      .set_enabled(enabled, chr_mem_len);
+  }
+
+  pub fn clear_code_data_log(&mut self) {
+    self.cpu_bus_mut().code_data_logger_mut().clear();
+    self
+      .ppu_cpu_bus_mut()
+      .ppu_memory_mut()
+      .chr_data_logger_mut()
+      .clear();
+  }
+
   pub fn ppu_cpu_bus(&self) -> &dyn PPUCPUBusTrait {
     self.cpu_bus().ppu_cpu_bus()
   }
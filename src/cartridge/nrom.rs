@@ -1,6 +1,6 @@
 use crate::{
   cpu::CPUBus,
-  nes::INESRom,
+  nes::{INESRom, SharedBuffer, SharedMemory},
   ppu::{PPUCPUBus, PPUMemory},
 };
 
@@ -11,11 +11,28 @@ use super::{
 
 #[derive(Debug, Clone)]
 pub struct NROMCPUBusInterceptor {
-  prg_ram: [u8; 8 * 1024],
-  prg_rom: [u8; 32 * 1024],
+  prg_ram: SharedMemory<[u8; 8 * 1024]>,
+  prg_rom: SharedMemory<Vec<u8>>,
   bus: CPUBus<NROMPPUMemoryInterceptor>,
 }
 
+impl NROMCPUBusInterceptor {
+  fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    if addr < 0x8000 {
+      return None;
+    }
+
+    // 16KiB ROMs are mirrored into $C000-$FFFF
+    usize::from(addr - 0x8000).checked_rem(self.prg_rom.len())
+  }
+
+  fn prg_ram_offset(&self, addr: u16) -> Option<usize> {
+    (0x6000..0x8000)
+      .contains(&addr)
+      .then(|| usize::from(addr) % (8 * 1024))
+  }
+}
+
 impl BusInterceptor<u16> for NROMCPUBusInterceptor {
   type BusType = CPUBus<NROMPPUMemoryInterceptor>;
 
@@ -30,32 +47,52 @@ impl BusInterceptor<u16> for NROMCPUBusInterceptor {
   fn intercept_read_readonly(&self, addr: u16) -> InterceptorResult<Option<u8>> {
     if addr < 0x6000 {
       InterceptorResult::NotIntercepted
-    } else if addr < 0x8000 {
-      InterceptorResult::Intercepted(Some(self.prg_ram[usize::from(addr) % (8 * 1024)]))
+    } else if let Some(offset) = self.prg_ram_offset(addr) {
+      InterceptorResult::Intercepted(Some(self.prg_ram[offset]))
     } else {
-      InterceptorResult::Intercepted(Some(self.prg_rom[usize::from(addr - 0x8000)]))
+      InterceptorResult::Intercepted(self.prg_rom_offset(addr).map(|offset| self.prg_rom[offset]))
     }
   }
 
   fn intercept_write(&mut self, addr: u16, value: u8) -> InterceptorResult<()> {
     if addr < 0x6000 {
       InterceptorResult::NotIntercepted
-    } else if addr < 0x8000 {
-      self.prg_ram[usize::from(addr) % (8 * 1024)] = value;
+    } else if let Some(offset) = self.prg_ram_offset(addr) {
+      self.prg_ram[offset] = value;
       InterceptorResult::Intercepted(())
     } else {
       // can't write to rom
       InterceptorResult::Intercepted(())
     }
   }
+
+  fn patch_read_value(&self, addr: u16, value: Option<u8>) -> Option<u8> {
+    self.bus.cheats.patch_read(addr, value)
+  }
+
+  fn observe_read(&mut self, addr: u16) {
+    if !self.bus.code_data_logger.is_enabled() {
+      return;
+    }
+
+    if let Some(prg_rom_offset) = self.prg_rom_offset(addr) {
+      self.bus.code_data_logger.log_prg_read(prg_rom_offset, addr);
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
 pub struct NROMPPUMemoryInterceptor {
-  chr_rom: [u8; 8 * 1024],
+  chr_rom: SharedMemory<[u8; 8 * 1024]>,
   bus: PPUMemory,
 }
 
+impl NROMPPUMemoryInterceptor {
+  fn chr_mem_offset(&self, addr: u16) -> Option<usize> {
+    (addr < 0x2000).then(|| usize::from(addr))
+  }
+}
+
 impl BusInterceptor<u16> for NROMPPUMemoryInterceptor {
   type BusType = PPUMemory;
 
@@ -68,10 +105,9 @@ impl BusInterceptor<u16> for NROMPPUMemoryInterceptor {
   }
 
   fn intercept_read_readonly(&self, addr: u16) -> InterceptorResult<Option<u8>> {
-    if addr < 0x2000 {
-      InterceptorResult::Intercepted(Some(self.chr_rom[usize::from(addr)]))
-    } else {
-      InterceptorResult::NotIntercepted
+    match self.chr_mem_offset(addr) {
+      Some(offset) => InterceptorResult::Intercepted(Some(self.chr_rom[offset])),
+      None => InterceptorResult::NotIntercepted,
     }
   }
 
@@ -83,6 +119,16 @@ impl BusInterceptor<u16> for NROMPPUMemoryInterceptor {
       InterceptorResult::NotIntercepted
     }
   }
+
+  fn observe_read(&mut self, addr: u16) {
+    if !self.bus.chr_data_logger.is_enabled() {
+      return;
+    }
+
+    if let Some(chr_mem_offset) = self.chr_mem_offset(addr) {
+      self.bus.chr_data_logger.log_chr_read(chr_mem_offset);
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -96,13 +142,6 @@ impl Mapper for NROM {
   type PPUMemoryInterceptor = NROMPPUMemoryInterceptor;
 
   fn from_ines_rom(rom: INESRom) -> Self {
-    let mut prg_rom: [u8; 32 * 1024] = [0; 32 * 1024];
-    if !rom.prg_data.is_empty() {
-      for chunk in prg_rom.chunks_exact_mut(rom.prg_data.len()) {
-        chunk.copy_from_slice(&rom.prg_data);
-      }
-    }
-
     let mut chr_rom: [u8; 8 * 1024] = [0; 8 * 1024];
     if !rom.chr_data.is_empty() {
       for chunk in chr_rom.chunks_exact_mut(rom.chr_data.len()) {
@@ -111,13 +150,13 @@ impl Mapper for NROM {
     }
 
     let ppu_memory = NROMPPUMemoryInterceptor {
-      chr_rom,
+      chr_rom: SharedMemory::new(chr_rom),
       bus: PPUMemory::new(rom.initial_mirroring()),
     };
 
     let cpu_bus = NROMCPUBusInterceptor {
-      prg_ram: [0; 8 * 1024],
-      prg_rom,
+      prg_ram: SharedMemory::new([0; 8 * 1024]),
+      prg_rom: SharedMemory::new(rom.prg_data),
       bus: CPUBus::new(PPUCPUBus::new(Box::new(ppu_memory))),
     };
 
@@ -131,4 +170,43 @@ impl Mapper for NROM {
   fn cpu_bus_mut(&mut self) -> &mut Self::CPUBusInterceptor {
     &mut self.cpu_bus
   }
+
+  fn prg_rom(&self) -> &[u8] {
+    &self.cpu_bus.prg_rom[..]
+  }
+
+  fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    self.cpu_bus.prg_rom_offset(addr)
+  }
+
+  fn prg_rom_mut(&mut self) -> &mut [u8] {
+    &mut self.cpu_bus.prg_rom[..]
+  }
+
+  fn chr_mem(&self) -> &[u8] {
+    &self.ppu_memory().chr_rom[..]
+  }
+
+  fn chr_mem_mut(&mut self) -> &mut [u8] {
+    &mut self.ppu_memory_mut().chr_rom[..]
+  }
+
+  fn prg_ram(&self) -> Option<&[u8]> {
+    Some(&self.cpu_bus.prg_ram[..])
+  }
+
+  fn prg_ram_mut(&mut self) -> Option<&mut [u8]> {
+    Some(&mut self.cpu_bus.prg_ram[..])
+  }
+
+  fn prg_ram_offset(&self, addr: u16) -> Option<usize> {
+    self.cpu_bus.prg_ram_offset(addr)
+  }
+
+  fn visit_shared_buffers(&mut self, visit: &mut dyn FnMut(&mut dyn SharedBuffer)) {
+    visit(&mut self.cpu_bus.prg_ram);
+    visit(&mut self.cpu_bus.prg_rom);
+    visit(&mut self.ppu_memory_mut().chr_rom);
+    self.cpu_bus.bus.visit_shared_buffers(visit);
+  }
 }
@@ -1,5 +1,6 @@
 use crate::{
   cpu::CPUBus,
+  nes::{SharedBuffer, SharedMemory},
   ppu::{PPUCPUBus, PPUMemory},
 };
 
@@ -10,11 +11,25 @@ use super::{
 
 #[derive(Debug, Clone)]
 pub struct UxROMCPUBusInterceptor {
-  prg_rom: Vec<u8>,
+  prg_rom: SharedMemory<Vec<u8>>,
   bank_select: u8,
   bus: CPUBus<UxROMPPUMemoryInterceptor>,
 }
 
+impl UxROMCPUBusInterceptor {
+  fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    if addr < 0x8000 {
+      None
+    } else if addr < 0xc000 {
+      Some(
+        ((0x4000 * (self.bank_select as usize)) + usize::from(addr - 0x8000)) % self.prg_rom.len(),
+      )
+    } else {
+      Some(self.prg_rom.len() - 0x4000 + usize::from(addr - 0xc000))
+    }
+  }
+}
+
 impl BusInterceptor<u16> for UxROMCPUBusInterceptor {
   type BusType = CPUBus<UxROMPPUMemoryInterceptor>;
 
@@ -27,22 +42,9 @@ impl BusInterceptor<u16> for UxROMCPUBusInterceptor {
   }
 
   fn intercept_read_readonly(&self, addr: u16) -> InterceptorResult<Option<u8>> {
-    if addr < 0x8000 {
-      InterceptorResult::NotIntercepted
-    } else if addr < 0xc000 {
-      InterceptorResult::Intercepted(Some(
-        *self
-          .prg_rom
-          .get(
-            ((0x4000 * (self.bank_select as usize)) + usize::from(addr - 0x8000))
-              % self.prg_rom.len(),
-          )
-          .unwrap(),
-      ))
-    } else {
-      InterceptorResult::Intercepted(Some(
-        self.prg_rom[self.prg_rom.len() - 0x4000 + usize::from(addr - 0xc000)],
-      ))
+    match self.prg_rom_offset(addr) {
+      Some(offset) => InterceptorResult::Intercepted(Some(self.prg_rom[offset])),
+      None => InterceptorResult::NotIntercepted,
     }
   }
 
@@ -54,14 +56,34 @@ impl BusInterceptor<u16> for UxROMCPUBusInterceptor {
       InterceptorResult::Intercepted(())
     }
   }
+
+  fn patch_read_value(&self, addr: u16, value: Option<u8>) -> Option<u8> {
+    self.bus.cheats.patch_read(addr, value)
+  }
+
+  fn observe_read(&mut self, addr: u16) {
+    if !self.bus.code_data_logger.is_enabled() {
+      return;
+    }
+
+    if let Some(prg_rom_offset) = self.prg_rom_offset(addr) {
+      self.bus.code_data_logger.log_prg_read(prg_rom_offset, addr);
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
 pub struct UxROMPPUMemoryInterceptor {
-  chr_rom: [u8; 8 * 1024],
+  chr_rom: SharedMemory<[u8; 8 * 1024]>,
   bus: PPUMemory,
 }
 
+impl UxROMPPUMemoryInterceptor {
+  fn chr_mem_offset(&self, addr: u16) -> Option<usize> {
+    (addr < 0x2000).then(|| usize::from(addr))
+  }
+}
+
 impl BusInterceptor<u16> for UxROMPPUMemoryInterceptor {
   type BusType = PPUMemory;
 
@@ -74,10 +96,9 @@ impl BusInterceptor<u16> for UxROMPPUMemoryInterceptor {
   }
 
   fn intercept_read_readonly(&self, addr: u16) -> InterceptorResult<Option<u8>> {
-    if addr < 0x2000 {
-      InterceptorResult::Intercepted(Some(self.chr_rom[usize::from(addr)]))
-    } else {
-      InterceptorResult::NotIntercepted
+    match self.chr_mem_offset(addr) {
+      Some(offset) => InterceptorResult::Intercepted(Some(self.chr_rom[offset])),
+      None => InterceptorResult::NotIntercepted,
     }
   }
 
@@ -89,6 +110,16 @@ impl BusInterceptor<u16> for UxROMPPUMemoryInterceptor {
       InterceptorResult::NotIntercepted
     }
   }
+
+  fn observe_read(&mut self, addr: u16) {
+    if !self.bus.chr_data_logger.is_enabled() {
+      return;
+    }
+
+    if let Some(chr_mem_offset) = self.chr_mem_offset(addr) {
+      self.bus.chr_data_logger.log_chr_read(chr_mem_offset);
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -113,11 +144,11 @@ impl Mapper for UxROM {
 
     let ppu_memory_interceptor = UxROMPPUMemoryInterceptor {
       bus: PPUMemory::new(rom.initial_mirroring()),
-      chr_rom,
+      chr_rom: SharedMemory::new(chr_rom),
     };
     let cpu_bus_interceptor = UxROMCPUBusInterceptor {
       bus: CPUBus::new(PPUCPUBus::new(Box::new(ppu_memory_interceptor))),
-      prg_rom: rom.prg_data,
+      prg_rom: SharedMemory::new(rom.prg_data),
       bank_select: 0,
     };
 
@@ -133,4 +164,30 @@ impl Mapper for UxROM {
   fn cpu_bus_mut(&mut self) -> &mut UxROMCPUBusInterceptor {
     &mut self.cpu_bus_interceptor
   }
+
+  fn prg_rom(&self) -> &[u8] {
+    &self.cpu_bus_interceptor.prg_rom[..]
+  }
+
+  fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    self.cpu_bus_interceptor.prg_rom_offset(addr)
+  }
+
+  fn prg_rom_mut(&mut self) -> &mut [u8] {
+    &mut self.cpu_bus_interceptor.prg_rom[..]
+  }
+
+  fn chr_mem(&self) -> &[u8] {
+    &self.ppu_memory().chr_rom[..]
+  }
+
+  fn chr_mem_mut(&mut self) -> &mut [u8] {
+    &mut self.ppu_memory_mut().chr_rom[..]
+  }
+
+  fn visit_shared_buffers(&mut self, visit: &mut dyn FnMut(&mut dyn SharedBuffer)) {
+    visit(&mut self.cpu_bus_interceptor.prg_rom);
+    visit(&mut self.ppu_memory_mut().chr_rom);
+    self.cpu_bus_interceptor.bus.visit_shared_buffers(visit);
+  }
 }
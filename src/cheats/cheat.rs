@@ -0,0 +1,40 @@
+use super::{GameGenieCode, ProActionReplayCode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatCode {
+  GameGenie(GameGenieCode),
+  ProActionReplay(ProActionReplayCode),
+}
+
+impl CheatCode {
+  pub fn decode(code: &str) -> Result<Self, anyhow::Error> {
+    if let Ok(game_genie_code) = GameGenieCode::decode(code) {
+      return Ok(Self::GameGenie(game_genie_code));
+    }
+
+    ProActionReplayCode::decode(code)
+      .map(Self::ProActionReplay)
+      .map_err(|_| anyhow::Error::msg(format!("Unrecognized cheat code '{}'", code)))
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cheat {
+  pub code: CheatCode,
+  pub code_text: String,
+  pub description: String,
+  pub enabled: bool,
+}
+
+impl Cheat {
+  pub fn new(code_text: &str, description: &str) -> Result<Self, anyhow::Error> {
+    let code_text = code_text.trim().to_ascii_uppercase();
+
+    Ok(Self {
+      code: CheatCode::decode(&code_text)?,
+      code_text,
+      description: description.trim().to_owned(),
+      enabled: true,
+    })
+  }
+}
@@ -0,0 +1,40 @@
+use super::{Cheat, CheatCode, GameGenieCode, ProActionReplayCode};
+
+#[derive(Debug, Clone, Default)]
+pub struct CheatEngine {
+  game_genie_codes: Vec<GameGenieCode>,
+  frozen_ram: Vec<ProActionReplayCode>,
+}
+
+impl CheatEngine {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn set_cheats(&mut self, cheats: &[Cheat]) {
+    self.game_genie_codes.clear();
+    self.frozen_ram.clear();
+
+    for cheat in cheats.iter().filter(|cheat| cheat.enabled) {
+      match cheat.code {
+        CheatCode::GameGenie(code) => self.game_genie_codes.push(code),
+        CheatCode::ProActionReplay(code) => self.frozen_ram.push(code),
+      }
+    }
+  }
+
+  pub fn patch_read(&self, addr: u16, value: Option<u8>) -> Option<u8> {
+    if addr < 0x8000 {
+      return value;
+    }
+
+    self
+      .game_genie_codes
+      .iter()
+      .fold(value, |value, code| code.patch(addr, value))
+  }
+
+  pub fn frozen_ram(&self) -> &[ProActionReplayCode] {
+    &self.frozen_ram
+  }
+}
@@ -0,0 +1,66 @@
+use std::{fs, path::Path};
+
+use super::Cheat;
+
+// Cheat lists are plain text, one cheat per line:
+//
+//   [x] SXIOPO Infinite lives
+//   [ ] 0075:09 Start with 9 lives
+//
+// Blank lines and lines starting with # are ignored.
+
+pub fn parse_cheat_list(input: &str) -> Result<Vec<Cheat>, anyhow::Error> {
+  input
+    .lines()
+    .enumerate()
+    .map(|(line_index, line)| (line_index, line.trim()))
+    .filter(|(_line_index, line)| !line.is_empty() && !line.starts_with('#'))
+    .map(|(line_index, line)| {
+      parse_cheat_line(line)
+        .map_err(|err| anyhow::Error::msg(format!("Line {}: {}", line_index + 1, err)))
+    })
+    .collect()
+}
+
+fn parse_cheat_line(line: &str) -> Result<Cheat, anyhow::Error> {
+  let (enabled, rest) = if let Some(rest) = line.strip_prefix("[x]") {
+    (true, rest)
+  } else if let Some(rest) = line.strip_prefix("[ ]") {
+    (false, rest)
+  } else {
+    return Err(anyhow::Error::msg(
+      "Expected cheat to start with [x] or [ ]",
+    ));
+  };
+
+  let rest = rest.trim_start();
+  let (code_text, description) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+  let mut cheat = Cheat::new(code_text, description)?;
+  cheat.enabled = enabled;
+  Ok(cheat)
+}
+
+pub fn format_cheat_list(cheats: &[Cheat]) -> String {
+  cheats
+    .iter()
+    .map(|cheat| {
+      let line = format!(
+        "[{}] {} {}",
+        if cheat.enabled { "x" } else { " " },
+        cheat.code_text,
+        cheat.description
+      );
+      format!("{}\n", line.trim_end())
+    })
+    .collect()
+}
+
+pub fn load_cheat_list(path: &Path) -> Result<Vec<Cheat>, anyhow::Error> {
+  parse_cheat_list(&fs::read_to_string(path)?)
+}
+
+pub fn save_cheat_list(path: &Path, cheats: &[Cheat]) -> Result<(), anyhow::Error> {
+  fs::write(path, format_cheat_list(cheats))?;
+  Ok(())
+}
@@ -0,0 +1,71 @@
+const GAME_GENIE_LETTERS: [char; 16] = [
+  'A', 'P', 'Z', 'L', 'G', 'I', 'T', 'Y', 'E', 'O', 'X', 'U', 'K', 'S', 'V', 'N',
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameGenieCode {
+  pub addr: u16,
+  pub value: u8,
+  pub compare: Option<u8>,
+}
+
+impl GameGenieCode {
+  pub fn decode(code: &str) -> Result<Self, anyhow::Error> {
+    let nybbles = code
+      .chars()
+      .map(|c| {
+        GAME_GENIE_LETTERS
+          .iter()
+          .position(|letter| *letter == c.to_ascii_uppercase())
+          .map(|index| index as u16)
+          .ok_or_else(|| anyhow::Error::msg(format!("Invalid Game Genie letter '{}'", c)))
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let n = nybbles.as_slice();
+    if n.len() != 6 && n.len() != 8 {
+      return Err(anyhow::Error::msg(format!(
+        "Game Genie codes must be 6 or 8 letters long, got {}",
+        n.len()
+      )));
+    }
+
+    let addr = 0x8000
+      | ((n[3] & 7) << 12)
+      | ((n[5] & 7) << 8)
+      | ((n[4] & 8) << 8)
+      | ((n[2] & 7) << 4)
+      | ((n[1] & 8) << 4)
+      | (n[4] & 7)
+      | (n[3] & 8);
+
+    // the high bit of the value comes from the last letter, which differs between 6 and 8 letter
+    // codes
+    let value = ((n[1] & 7) << 4) | ((n[0] & 8) << 4) | (n[0] & 7) | (n[n.len() - 1] & 8);
+
+    let compare = if n.len() == 8 {
+      Some(((n[7] & 7) << 4) | ((n[6] & 8) << 4) | (n[6] & 7) | (n[5] & 8))
+    } else {
+      None
+    };
+
+    Ok(Self {
+      addr,
+      value: value as u8,
+      compare: compare.map(|compare| compare as u8),
+    })
+  }
+
+  pub fn patch(&self, addr: u16, value: Option<u8>) -> Option<u8> {
+    if addr != self.addr {
+      return value;
+    }
+
+    match self.compare {
+      // 8-letter codes only apply when the ROM byte currently mapped in matches, which is what
+      // keeps them from corrupting other banks switched into the same address
+      Some(compare) if value != Some(compare) => value,
+      _ => Some(self.value),
+    }
+  }
+}
@@ -0,0 +1,100 @@
+mod cheat;
+mod cheat_engine;
+mod cheat_list;
+mod game_genie;
+mod pro_action_replay;
+
+pub use cheat::*;
+pub use cheat_engine::*;
+pub use cheat_list::*;
+pub use game_genie::*;
+pub use pro_action_replay::*;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_decode_6_letter_game_genie_code() {
+    let code = GameGenieCode::decode("SXIOPO").unwrap();
+
+    assert_eq!(code.addr, 0x91d9);
+    assert_eq!(code.value, 0xad);
+    assert_eq!(code.compare, None);
+    assert_eq!(GameGenieCode::decode("gossip").unwrap().addr, 0xd1dd);
+  }
+
+  #[test]
+  fn test_decode_8_letter_game_genie_code() {
+    let code = GameGenieCode::decode("ZEXPYGLA").unwrap();
+
+    assert_eq!(code.addr, 0x94a7);
+    assert_eq!(code.value, 0x02);
+    assert_eq!(code.compare, Some(0x03));
+  }
+
+  #[test]
+  fn test_invalid_game_genie_codes() {
+    assert!(GameGenieCode::decode("SXIOP").is_err());
+    assert!(GameGenieCode::decode("SXIOPB").is_err());
+  }
+
+  #[test]
+  fn test_decode_pro_action_replay_codes() {
+    assert_eq!(
+      ProActionReplayCode::decode("00075A09").unwrap(),
+      ProActionReplayCode {
+        addr: 0x075a,
+        value: 0x09
+      }
+    );
+    assert_eq!(
+      ProActionReplayCode::decode("6010:FF").unwrap(),
+      ProActionReplayCode {
+        addr: 0x6010,
+        value: 0xff
+      }
+    );
+    assert!(ProActionReplayCode::decode("8000:01").is_err());
+    assert!(ProActionReplayCode::decode("2000:80").is_err());
+    assert!(ProActionReplayCode::decode("00401402").is_err());
+  }
+
+  #[test]
+  fn test_game_genie_compare() {
+    let mut engine = CheatEngine::new();
+    engine.set_cheats(&[Cheat::new("ZEXPYGLA", "").unwrap()]);
+
+    assert_eq!(engine.patch_read(0x94a7, Some(0x03)), Some(0x02));
+    // a different bank is switched in, so the compare value doesn't match
+    assert_eq!(engine.patch_read(0x94a7, Some(0x10)), Some(0x10));
+    assert_eq!(engine.patch_read(0x94a8, Some(0x03)), Some(0x03));
+  }
+
+  #[test]
+  fn test_disabled_cheats_are_ignored() {
+    let mut cheat = Cheat::new("SXIOPO", "Infinite lives").unwrap();
+    cheat.enabled = false;
+
+    let mut engine = CheatEngine::new();
+    engine.set_cheats(&[cheat, Cheat::new("0075:09", "").unwrap()]);
+
+    assert_eq!(engine.patch_read(0x91d9, Some(0xce)), Some(0xce));
+    assert_eq!(engine.frozen_ram().len(), 1);
+  }
+
+  #[test]
+  fn test_cheat_list_round_trip() {
+    let input = "# lives\n[x] SXIOPO Infinite lives\n\n[ ] 0075:09\n";
+    let cheats = parse_cheat_list(input).unwrap();
+
+    assert_eq!(cheats.len(), 2);
+    assert!(cheats[0].enabled);
+    assert_eq!(cheats[0].description, "Infinite lives");
+    assert!(!cheats[1].enabled);
+    assert_eq!(
+      format_cheat_list(&cheats),
+      "[x] SXIOPO Infinite lives\n[ ] 0075:09\n"
+    );
+  }
+}
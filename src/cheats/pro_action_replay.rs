@@ -0,0 +1,38 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProActionReplayCode {
+  pub addr: u16,
+  pub value: u8,
+}
+
+impl ProActionReplayCode {
+  /// Accepts either 8 hex digit Pro Action Replay codes (`00AAAAVV`, where the first byte is
+  /// ignored) or raw `AAAA:VV` address/value pairs.
+  pub fn decode(code: &str) -> Result<Self, anyhow::Error> {
+    let (addr, value) = match code.split_once(':') {
+      Some((addr, value)) => (addr, value),
+      None => {
+        if code.len() != 8 || !code.is_ascii() {
+          return Err(anyhow::Error::msg(format!(
+            "Pro Action Replay codes must be 8 hex digits long, got '{}'",
+            code
+          )));
+        }
+
+        (&code[2..6], &code[6..8])
+      }
+    };
+
+    let addr = u16::from_str_radix(addr, 16)?;
+    let value = u8::from_str_radix(value, 16)?;
+
+    // anything else is a register or ROM, which freezing would write to every frame
+    if addr >= 0x2000 && !(0x6000..0x8000).contains(&addr) {
+      return Err(anyhow::Error::msg(format!(
+        "RAM codes must target RAM ($0000-$1FFF) or PRG RAM ($6000-$7FFF), got ${:04X}",
+        addr
+      )));
+    }
+
+    Ok(Self { addr, value })
+  }
+}
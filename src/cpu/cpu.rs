@@ -3,6 +3,7 @@ use std::fmt::Debug;
 use bitfield_struct::bitfield;
 
 use super::{CPUBusTrait, ExecutedInstruction, Instruction, Operand};
+use crate::debugger::CPUAccessKind;
 
 #[bitfield(u8)]
 pub struct CPUStatusRegister {
@@ -16,6 +17,13 @@ pub struct CPUStatusRegister {
   pub negative_flag: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum CPUInterrupt {
+  NMI,
+  IRQ,
+}
+
 #[derive(Debug, Clone)]
 #[allow(clippy::upper_case_acronyms)]
 pub struct CPU {
@@ -54,6 +62,17 @@ impl CPU {
     }
   }
 
+  /// The interrupt the next call to `tick` will service, if any. Mirrors the checks in `tick`.
+  pub fn pending_interrupt(&self) -> Option<CPUInterrupt> {
+    if self.nmi_set {
+      Some(CPUInterrupt::NMI)
+    } else if self.wait_cycles == 0 && self.irq_set && !self.p.interrupt_disable() {
+      Some(CPUInterrupt::IRQ)
+    } else {
+      None
+    }
+  }
+
   pub fn set_operand(op: &Operand, value: u8, cpu_bus: &mut dyn CPUBusTrait, cpu: &mut CPU) {
     match op {
       Operand::Accumulator => cpu.a = value,
@@ -125,7 +144,11 @@ impl CPU {
     cpu.wait_cycles = 7;
   }
 
-  pub fn tick(&mut self, cpu_bus: &mut dyn CPUBusTrait) -> Option<ExecutedInstruction> {
+  pub fn tick(
+    &mut self,
+    cpu_bus: &mut dyn CPUBusTrait,
+    disassemble: bool,
+  ) -> Option<ExecutedInstruction> {
     if self.nmi_set {
       CPU::push_stack(
         u8::try_from((self.pc & 0xff00) >> 8).unwrap(),
@@ -178,9 +201,13 @@ impl CPU {
 
     self.p.set_unused(true);
 
+    cpu_bus.code_data_logger_mut().begin_opcode_fetch();
     let (instruction, opcode) = Instruction::load_instruction(cpu_bus, self);
+    cpu_bus
+      .code_data_logger_mut()
+      .set_access_kind(CPUAccessKind::Data);
     self.wait_cycles = instruction.base_cycles() - 1;
-    let disassembled_instruction = instruction.disassemble(cpu_bus, self);
+    let disassembled_instruction = disassemble.then(|| instruction.disassemble(cpu_bus, self));
     CPU::execute_instruction(&instruction, true, cpu_bus, self);
 
     Some(ExecutedInstruction {
@@ -441,6 +468,10 @@ impl CPU {
 
       Instruction::JMP(addr) => {
         CPU::set_pc(addr, cpu_bus, cpu);
+
+        if let Operand::Indirect(_) = addr {
+          cpu_bus.code_data_logger_mut().indirect_jump_taken();
+        }
       }
 
       Instruction::JSR(addr) => {
@@ -1,9 +1,11 @@
 use crate::{
-  apu::{APUSynth, APU},
+  apu::{APUSynth, AudioClock, APU},
   audio::stream_setup::StreamSpawner,
   bus::Bus,
   cartridge::bus_interceptor::BusInterceptor,
-  nes::{Controller, ControllerButton, DMA},
+  cheats::CheatEngine,
+  debugger::CodeDataLogger,
+  nes::{Controller, ControllerButton, SharedBuffer, SharedMemory, DMA},
   ppu::{PPUCPUBus, PPUCPUBusTrait, PPUMemory, PPUMemoryTrait, PPURegister},
 };
 
@@ -11,7 +13,9 @@ pub trait CPUBusTrait: Bus<u16> {
   fn maybe_tick_dma(&mut self, ppu_cycle_count: u64) -> bool;
   fn tick_apu(
     &mut self,
-    sender: &<APUSynth as StreamSpawner>::OutputType,
+    sender: Option<&<APUSynth as StreamSpawner>::OutputType>,
+    audio_clock: &AudioClock,
+    recording_sender: Option<&<APUSynth as StreamSpawner>::OutputType>,
     cpu_cycle_count: u64,
   ) -> bool;
   fn set_controller_button_state(
@@ -21,29 +25,59 @@ pub trait CPUBusTrait: Bus<u16> {
     pressed: bool,
   );
 
+  fn work_ram(&self) -> &[u8];
+  fn work_ram_mut(&mut self) -> &mut [u8];
+  /// Controller reads following a strobe since this was last called, resetting the count and the
+  /// strobe.
+  fn take_input_polls(&mut self) -> u32;
+
+  fn cheats(&self) -> &CheatEngine;
+  fn cheats_mut(&mut self) -> &mut CheatEngine;
+  fn code_data_logger(&self) -> &CodeDataLogger;
+  fn code_data_logger_mut(&mut self) -> &mut CodeDataLogger;
+
   fn ppu_cpu_bus<'a>(&'a self) -> &'a (dyn PPUCPUBusTrait + 'a);
   fn ppu_cpu_bus_mut<'a>(&'a mut self) -> &'a mut (dyn PPUCPUBusTrait + 'a);
 }
 
 #[derive(Debug, Clone)]
 pub struct CPUBus<I: BusInterceptor<u16, BusType = PPUMemory> + PPUMemoryTrait> {
-  pub work_ram: [u8; 2048],
+  pub work_ram: SharedMemory<[u8; 2048]>,
   pub controllers: [Controller; 2],
+  input_polls: u32,
+  // whether the controllers have been strobed this frame, without which reading them isn't a poll
+  input_strobed: bool,
   pub ppu_cpu_bus: Box<PPUCPUBus<I>>,
   pub dma: DMA,
   pub apu: APU,
+  pub cheats: CheatEngine,
+  pub code_data_logger: CodeDataLogger,
 }
 
 impl<I: BusInterceptor<u16, BusType = PPUMemory> + Clone + PPUMemoryTrait> CPUBus<I> {
   pub fn new(ppu_cpu_bus: PPUCPUBus<I>) -> Self {
     Self {
-      work_ram: [0; 2048],
+      work_ram: SharedMemory::new([0; 2048]),
       controllers: [Controller::new(), Controller::new()],
+      input_polls: 0,
+      input_strobed: false,
       ppu_cpu_bus: Box::new(ppu_cpu_bus),
       dma: DMA::new(),
       apu: APU::new(),
+      cheats: CheatEngine::new(),
+      code_data_logger: CodeDataLogger::new(),
     }
   }
+
+  /// Visits work RAM and the PPU's buffers, but not the mapper's own.
+  pub fn visit_shared_buffers(&mut self, visit: &mut dyn FnMut(&mut dyn SharedBuffer)) {
+    visit(&mut self.work_ram);
+    self
+      .ppu_cpu_bus
+      .ppu_memory
+      .get_inner_mut()
+      .visit_shared_buffers(visit);
+  }
 }
 
 impl<I: BusInterceptor<u16, BusType = PPUMemory> + Clone + PPUMemoryTrait> CPUBusTrait
@@ -72,10 +106,18 @@ impl<I: BusInterceptor<u16, BusType = PPUMemory> + Clone + PPUMemoryTrait> CPUBu
 
   fn tick_apu(
     &mut self,
-    sender: &<APUSynth as StreamSpawner>::OutputType,
+    sender: Option<&<APUSynth as StreamSpawner>::OutputType>,
+    audio_clock: &AudioClock,
+    recording_sender: Option<&<APUSynth as StreamSpawner>::OutputType>,
     cpu_cycle_count: u64,
   ) -> bool {
-    APU::tick(&mut self.apu, sender, cpu_cycle_count)
+    APU::tick(
+      &mut self.apu,
+      sender,
+      audio_clock,
+      recording_sender,
+      cpu_cycle_count,
+    )
   }
 
   fn set_controller_button_state(
@@ -87,6 +129,35 @@ impl<I: BusInterceptor<u16, BusType = PPUMemory> + Clone + PPUMemoryTrait> CPUBu
     self.controllers[controller_index].set_button_state(button, pressed)
   }
 
+  fn work_ram(&self) -> &[u8] {
+    &self.work_ram[..]
+  }
+
+  fn work_ram_mut(&mut self) -> &mut [u8] {
+    &mut self.work_ram[..]
+  }
+
+  fn take_input_polls(&mut self) -> u32 {
+    self.input_strobed = false;
+    std::mem::take(&mut self.input_polls)
+  }
+
+  fn cheats(&self) -> &CheatEngine {
+    &self.cheats
+  }
+
+  fn cheats_mut(&mut self) -> &mut CheatEngine {
+    &mut self.cheats
+  }
+
+  fn code_data_logger(&self) -> &CodeDataLogger {
+    &self.code_data_logger
+  }
+
+  fn code_data_logger_mut(&mut self) -> &mut CodeDataLogger {
+    &mut self.code_data_logger
+  }
+
   fn ppu_cpu_bus(&self) -> &dyn PPUCPUBusTrait {
     self.ppu_cpu_bus.as_ref()
   }
@@ -131,7 +202,10 @@ impl<I: BusInterceptor<u16, BusType = PPUMemory> + Clone + PPUMemoryTrait> Bus<u
       self.apu.read_side_effects(addr)
     } else if addr < 0x4018 {
       let controller = &mut self.controllers[addr as usize - 0x4016];
-      controller.read_side_effects(())
+      controller.read_side_effects(());
+      if self.input_strobed {
+        self.input_polls += 1;
+      }
     } else if addr < 0x4020 {
       // TODO: CPU test mode
     }
@@ -154,6 +228,7 @@ impl<I: BusInterceptor<u16, BusType = PPUMemory> + Clone + PPUMemoryTrait> Bus<u
     } else if addr == 0x4016 {
       self.controllers[0].poll();
       self.controllers[1].poll();
+      self.input_strobed = true;
     } else if addr == 0x4017 {
       self.apu.write(addr, value);
     } else if addr < 0x4020 {
@@ -8,7 +8,8 @@ use super::{CPUBusTrait, Instruction, Operand, CPU};
 pub struct ExecutedInstruction {
   pub instruction: Instruction,
   pub opcode: u8,
-  pub disassembled_instruction: DisassembledInstruction,
+  /// Only filled in when the caller asked `CPU::tick` to disassemble.
+  pub disassembled_instruction: Option<DisassembledInstruction>,
 }
 
 #[derive(Clone, Debug)]
@@ -65,7 +66,11 @@ impl ExecutedInstruction {
       } else {
         " "
       },
-      self.disassembled_instruction.to_string(),
+      self
+        .disassembled_instruction
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_default(),
       prev_state.cpu.a,
       prev_state.cpu.x,
       prev_state.cpu.y,
@@ -97,7 +102,11 @@ impl ExecutedInstruction {
       } else {
         " "
       },
-      self.disassembled_instruction.to_string(),
+      self
+        .disassembled_instruction
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_default(),
       prev_state.cpu.a,
       prev_state.cpu.x,
       prev_state.cpu.y,
@@ -123,6 +132,19 @@ impl ExecutedInstruction {
 pub struct DisassembledInstruction {
   instruction: Instruction,
   operand: Option<DisassembledOperand>,
+  operand_label: Option<String>,
+}
+
+impl DisassembledInstruction {
+  /// Names the operand's address with a symbol, so it displays as `LDA player_x` rather than
+  /// `LDA $0300`.
+  pub fn resolve_label(&mut self, label: impl FnOnce(u16) -> Option<String>) {
+    self.operand_label = self
+      .operand
+      .as_ref()
+      .and_then(DisassembledOperand::symbol_addr)
+      .and_then(label);
+  }
 }
 
 impl Display for DisassembledInstruction {
@@ -133,7 +155,10 @@ impl Display for DisassembledInstruction {
     };
 
     match &self.operand {
-      Some(op) => f.write_fmt(format_args!("{} {}", instruction_name, op)),
+      Some(op) => {
+        write!(f, "{} ", instruction_name)?;
+        op.fmt_with_label(f, self.operand_label.as_deref())
+      }
       None => f.write_str(instruction_name),
     }
   }
@@ -151,10 +176,12 @@ impl Instruction {
       Some(op) => DisassembledInstruction {
         instruction: self.clone(),
         operand: Some(op.disassemble(cpu_bus, cpu, eval)),
+        operand_label: None,
       },
       None => DisassembledInstruction {
         instruction: self.clone(),
         operand: None,
+        operand_label: None,
       },
     }
   }
@@ -216,21 +243,31 @@ pub enum DisassembledOperand {
 
 impl Display for DisassembledOperand {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    self.fmt_with_label(f, None)
+  }
+}
+
+impl DisassembledOperand {
+  /// Formats the operand, writing `label` in place of the address it names.
+  fn fmt_with_label(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+    label: Option<&str>,
+  ) -> std::fmt::Result {
+    let addr = |addr: String| label.map(str::to_owned).unwrap_or(addr);
     let operand_formatted = match self {
       Self::Accumulator => "A".to_owned(),
       Self::Immediate { value } => format!("#${:02X}", value),
-      Self::Absolute { addr, .. } => format!("${:04X}", addr),
-      Self::AbsoluteX { base_addr, .. } => format!("${:04X},X", base_addr),
-      Self::AbsoluteY { base_addr, .. } => format!("${:04X},Y", base_addr),
-      Self::ZeroPage { zp_addr, .. } => format!("${:02X}", zp_addr),
-      Self::ZeroPageX { zp_addr, .. } => format!("${:02X},X", zp_addr),
-      Self::ZeroPageY { zp_addr, .. } => format!("${:02X},Y", zp_addr),
-      Self::Indirect { addr, .. } => format!("(${:04X})", addr),
-      Self::IndirectX { zp_addr, .. } => format!("(${:02X},X)", zp_addr),
-      Self::IndirectY { zp_addr, .. } => format!("(${:02X}),Y", zp_addr),
-      Self::Relative { offset, pc } => {
-        format!("${:04X}", *pc as i32 + *offset as i32)
-      }
+      Self::Absolute { addr: abs_addr, .. } => addr(format!("${:04X}", abs_addr)),
+      Self::AbsoluteX { base_addr, .. } => format!("{},X", addr(format!("${:04X}", base_addr))),
+      Self::AbsoluteY { base_addr, .. } => format!("{},Y", addr(format!("${:04X}", base_addr))),
+      Self::ZeroPage { zp_addr, .. } => addr(format!("${:02X}", zp_addr)),
+      Self::ZeroPageX { zp_addr, .. } => format!("{},X", addr(format!("${:02X}", zp_addr))),
+      Self::ZeroPageY { zp_addr, .. } => format!("{},Y", addr(format!("${:02X}", zp_addr))),
+      Self::Indirect { addr: ind_addr, .. } => format!("({})", addr(format!("${:04X}", ind_addr))),
+      Self::IndirectX { zp_addr, .. } => format!("({},X)", addr(format!("${:02X}", zp_addr))),
+      Self::IndirectY { zp_addr, .. } => format!("({}),Y", addr(format!("${:02X}", zp_addr))),
+      Self::Relative { offset, pc } => addr(format!("${:04X}", *pc as i32 + *offset as i32)),
     };
 
     match self {
@@ -288,9 +325,81 @@ impl Display for DisassembledOperand {
       _ => f.write_str(&operand_formatted),
     }
   }
+
+  /// The address a symbol could name: any directly addressed location, including zero page
+  /// pointers and branch targets.
+  pub fn symbol_addr(&self) -> Option<u16> {
+    match self {
+      Self::ZeroPage { zp_addr, .. }
+      | Self::ZeroPageX { zp_addr, .. }
+      | Self::ZeroPageY { zp_addr, .. }
+      | Self::IndirectX { zp_addr, .. }
+      | Self::IndirectY { zp_addr, .. } => Some(u16::from(*zp_addr)),
+      _ => self.target_addr(),
+    }
+  }
+  /// The address the operand refers to, for operands that name one directly (including branch
+  /// targets).
+  pub fn target_addr(&self) -> Option<u16> {
+    match self {
+      Self::Absolute { addr, .. } | Self::Indirect { addr, .. } => Some(*addr),
+      Self::AbsoluteX { base_addr, .. } | Self::AbsoluteY { base_addr, .. } => Some(*base_addr),
+      Self::Relative { offset, pc } => Some(pc.wrapping_add_signed(i16::from(*offset))),
+      _ => None,
+    }
+  }
 }
 
 impl Operand {
+  /// Disassembles without evaluating against machine state, for static disassembly. `next_pc` is
+  /// the address of the following instruction.
+  pub fn disassemble_static(&self, next_pc: u16) -> DisassembledOperand {
+    match self {
+      Operand::Accumulator => DisassembledOperand::Accumulator,
+      Operand::Immediate(value) => DisassembledOperand::Immediate { value: *value },
+      Operand::Absolute(addr) => DisassembledOperand::Absolute {
+        addr: *addr,
+        result: None,
+      },
+      Operand::AbsoluteX(base_addr) => DisassembledOperand::AbsoluteX {
+        base_addr: *base_addr,
+        result: None,
+      },
+      Operand::AbsoluteY(base_addr) => DisassembledOperand::AbsoluteY {
+        base_addr: *base_addr,
+        result: None,
+      },
+      Operand::ZeroPage(zp_addr) => DisassembledOperand::ZeroPage {
+        zp_addr: *zp_addr,
+        result: None,
+      },
+      Operand::ZeroPageX(zp_addr) => DisassembledOperand::ZeroPageX {
+        zp_addr: *zp_addr,
+        result: None,
+      },
+      Operand::ZeroPageY(zp_addr) => DisassembledOperand::ZeroPageY {
+        zp_addr: *zp_addr,
+        result: None,
+      },
+      Operand::Indirect(addr) => DisassembledOperand::Indirect {
+        addr: *addr,
+        result_addr: None,
+      },
+      Operand::IndirectX(zp_addr) => DisassembledOperand::IndirectX {
+        zp_addr: *zp_addr,
+        result: None,
+      },
+      Operand::IndirectY(zp_addr) => DisassembledOperand::IndirectY {
+        zp_addr: *zp_addr,
+        result: None,
+      },
+      Operand::Relative(offset) => DisassembledOperand::Relative {
+        offset: *offset,
+        pc: next_pc,
+      },
+    }
+  }
+
   fn disassemble(&self, cpu_bus: &dyn CPUBusTrait, cpu: &CPU, eval: bool) -> DisassembledOperand {
     match self {
       Operand::Accumulator => DisassembledOperand::Accumulator,
@@ -1,6 +1,7 @@
 use strum::IntoStaticStr;
 
 use super::{CPUBusTrait, Operand, CPU};
+use crate::debugger::CPUAccessKind;
 
 #[derive(Debug, IntoStaticStr, Clone)]
 #[allow(clippy::upper_case_acronyms)]
@@ -494,480 +495,485 @@ impl Instruction {
     byte
   }
 
-  fn load_addr(cpu_bus: &mut dyn CPUBusTrait, cpu: &mut CPU) -> u16 {
-    let low = Instruction::load_byte(cpu_bus, cpu);
-    let high = Instruction::load_byte(cpu_bus, cpu);
+  fn next_addr(next_byte: &mut impl FnMut() -> u8) -> u16 {
+    let low = next_byte();
+    let high = next_byte();
 
     (u16::from(high) << 8) + u16::from(low)
   }
 
-  fn load_offset(cpu_bus: &mut dyn CPUBusTrait, cpu: &mut CPU) -> i8 {
-    let byte = Instruction::load_byte(cpu_bus, cpu);
-    byte as i8
-  }
-
   pub fn load_instruction(cpu_bus: &mut dyn CPUBusTrait, cpu: &mut CPU) -> (Instruction, u8) {
     let opcode = Instruction::load_byte(cpu_bus, cpu);
+    cpu_bus
+      .code_data_logger_mut()
+      .set_access_kind(CPUAccessKind::Operand);
+
+    let instruction = Instruction::decode(opcode, || Instruction::load_byte(cpu_bus, cpu))
+      .unwrap_or_else(|| panic!("Unknown opcode {:#04x}", opcode));
+
+    (instruction, opcode)
+  }
 
+  /// Decodes the instruction for `opcode`, pulling its operand bytes from `next_byte`. Returns
+  /// `None` for opcodes the CPU doesn't implement.
+  pub fn decode(opcode: u8, mut next_byte: impl FnMut() -> u8) -> Option<Instruction> {
     let instruction = match opcode {
       0x00 => Instruction::BRK,
-      0x01 => Instruction::ORA(Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu))),
+      0x01 => Instruction::ORA(Operand::IndirectX(next_byte())),
       0x03 => {
-        let op = Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectX(next_byte());
         Instruction::Illegal(Box::new(Instruction::SLO(op.clone())), Some(op))
       }
       0x04 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::ZeroPage(next_byte())),
       ),
-      0x05 => Instruction::ORA(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0x06 => Instruction::ASL(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+      0x05 => Instruction::ORA(Operand::ZeroPage(next_byte())),
+      0x06 => Instruction::ASL(Operand::ZeroPage(next_byte())),
       0x07 => {
-        let op = Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPage(next_byte());
         Instruction::Illegal(Box::new(Instruction::SLO(op.clone())), Some(op))
       }
       0x08 => Instruction::PHP,
-      0x09 => Instruction::ORA(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
+      0x09 => Instruction::ORA(Operand::Immediate(next_byte())),
       0x0a => Instruction::ASL(Operand::Accumulator),
       0x0c => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
+        Some(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
       ),
-      0x0d => Instruction::ORA(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0x0e => Instruction::ASL(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
+      0x0d => Instruction::ORA(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0x0e => Instruction::ASL(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
       0x0f => {
-        let op = Operand::Absolute(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::Absolute(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::SLO(op.clone())), Some(op))
       }
 
-      0x10 => Instruction::BPL(Operand::Relative(Instruction::load_offset(cpu_bus, cpu))),
-      0x11 => Instruction::ORA(Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu))),
+      0x10 => Instruction::BPL(Operand::Relative(next_byte() as i8)),
+      0x11 => Instruction::ORA(Operand::IndirectY(next_byte())),
       0x13 => {
-        let op = Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectY(next_byte());
         Instruction::Illegal(Box::new(Instruction::SLO(op.clone())), Some(op))
       }
       0x14 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::ZeroPageX(next_byte())),
       ),
-      0x15 => Instruction::ORA(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0x16 => Instruction::ASL(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+      0x15 => Instruction::ORA(Operand::ZeroPageX(next_byte())),
+      0x16 => Instruction::ASL(Operand::ZeroPageX(next_byte())),
       0x17 => {
-        let op = Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPageX(next_byte());
         Instruction::Illegal(Box::new(Instruction::SLO(op.clone())), Some(op))
       }
       0x18 => Instruction::CLC,
-      0x19 => Instruction::ORA(Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu))),
+      0x19 => Instruction::ORA(Operand::AbsoluteY(Instruction::next_addr(&mut next_byte))),
       0x1a => Instruction::Illegal(Box::new(Instruction::NOP), None),
       0x1b => {
-        let op = Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteY(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::SLO(op.clone())), Some(op))
       }
       0x1c => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+        Some(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       ),
-      0x1d => Instruction::ORA(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
-      0x1e => Instruction::ASL(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+      0x1d => Instruction::ORA(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
+      0x1e => Instruction::ASL(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       0x1f => {
-        let op = Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteX(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::SLO(op.clone())), Some(op))
       }
 
-      0x20 => Instruction::JSR(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0x21 => Instruction::AND(Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu))),
+      0x20 => Instruction::JSR(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0x21 => Instruction::AND(Operand::IndirectX(next_byte())),
       0x23 => {
-        let op = Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectX(next_byte());
         Instruction::Illegal(Box::new(Instruction::RLA(op.clone())), Some(op))
       }
-      0x24 => Instruction::BIT(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0x25 => Instruction::AND(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0x26 => Instruction::ROL(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+      0x24 => Instruction::BIT(Operand::ZeroPage(next_byte())),
+      0x25 => Instruction::AND(Operand::ZeroPage(next_byte())),
+      0x26 => Instruction::ROL(Operand::ZeroPage(next_byte())),
       0x27 => {
-        let op = Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPage(next_byte());
         Instruction::Illegal(Box::new(Instruction::RLA(op.clone())), Some(op))
       }
       0x28 => Instruction::PLP,
-      0x29 => Instruction::AND(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
+      0x29 => Instruction::AND(Operand::Immediate(next_byte())),
       0x2a => Instruction::ROL(Operand::Accumulator),
-      0x2c => Instruction::BIT(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0x2d => Instruction::AND(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0x2e => Instruction::ROL(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
+      0x2c => Instruction::BIT(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0x2d => Instruction::AND(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0x2e => Instruction::ROL(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
       0x2f => {
-        let op = Operand::Absolute(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::Absolute(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::RLA(op.clone())), Some(op))
       }
 
-      0x30 => Instruction::BMI(Operand::Relative(Instruction::load_offset(cpu_bus, cpu))),
-      0x31 => Instruction::AND(Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu))),
+      0x30 => Instruction::BMI(Operand::Relative(next_byte() as i8)),
+      0x31 => Instruction::AND(Operand::IndirectY(next_byte())),
       0x33 => {
-        let op = Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectY(next_byte());
         Instruction::Illegal(Box::new(Instruction::RLA(op.clone())), Some(op))
       }
       0x34 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::ZeroPageX(next_byte())),
       ),
-      0x35 => Instruction::AND(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0x36 => Instruction::ROL(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+      0x35 => Instruction::AND(Operand::ZeroPageX(next_byte())),
+      0x36 => Instruction::ROL(Operand::ZeroPageX(next_byte())),
       0x37 => {
-        let op = Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPageX(next_byte());
         Instruction::Illegal(Box::new(Instruction::RLA(op.clone())), Some(op))
       }
       0x38 => Instruction::SEC,
-      0x39 => Instruction::AND(Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu))),
+      0x39 => Instruction::AND(Operand::AbsoluteY(Instruction::next_addr(&mut next_byte))),
       0x3a => Instruction::Illegal(Box::new(Instruction::NOP), None),
       0x3b => {
-        let op = Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteY(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::RLA(op.clone())), Some(op))
       }
       0x3c => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+        Some(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       ),
-      0x3d => Instruction::AND(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
-      0x3e => Instruction::ROL(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+      0x3d => Instruction::AND(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
+      0x3e => Instruction::ROL(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       0x3f => {
-        let op = Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteX(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::RLA(op.clone())), Some(op))
       }
 
       0x40 => Instruction::RTI,
-      0x41 => Instruction::EOR(Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu))),
+      0x41 => Instruction::EOR(Operand::IndirectX(next_byte())),
       0x43 => {
-        let op = Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectX(next_byte());
         Instruction::Illegal(Box::new(Instruction::SRE(op.clone())), Some(op))
       }
       0x44 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::ZeroPage(next_byte())),
       ),
-      0x45 => Instruction::EOR(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0x46 => Instruction::LSR(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+      0x45 => Instruction::EOR(Operand::ZeroPage(next_byte())),
+      0x46 => Instruction::LSR(Operand::ZeroPage(next_byte())),
       0x47 => {
-        let op = Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPage(next_byte());
         Instruction::Illegal(Box::new(Instruction::SRE(op.clone())), Some(op))
       }
       0x48 => Instruction::PHA,
-      0x49 => Instruction::EOR(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
+      0x49 => Instruction::EOR(Operand::Immediate(next_byte())),
       0x4a => Instruction::LSR(Operand::Accumulator),
-      0x4c => Instruction::JMP(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0x4d => Instruction::EOR(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0x4e => Instruction::LSR(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
+      0x4c => Instruction::JMP(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0x4d => Instruction::EOR(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0x4e => Instruction::LSR(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
       0x4f => {
-        let op = Operand::Absolute(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::Absolute(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::SRE(op.clone())), Some(op))
       }
 
-      0x50 => Instruction::BVC(Operand::Relative(Instruction::load_offset(cpu_bus, cpu))),
-      0x51 => Instruction::EOR(Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu))),
+      0x50 => Instruction::BVC(Operand::Relative(next_byte() as i8)),
+      0x51 => Instruction::EOR(Operand::IndirectY(next_byte())),
       0x53 => {
-        let op = Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectY(next_byte());
         Instruction::Illegal(Box::new(Instruction::SRE(op.clone())), Some(op))
       }
       0x54 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::ZeroPageX(next_byte())),
       ),
-      0x55 => Instruction::EOR(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0x56 => Instruction::LSR(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+      0x55 => Instruction::EOR(Operand::ZeroPageX(next_byte())),
+      0x56 => Instruction::LSR(Operand::ZeroPageX(next_byte())),
       0x57 => {
-        let op = Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPageX(next_byte());
         Instruction::Illegal(Box::new(Instruction::SRE(op.clone())), Some(op))
       }
       0x58 => Instruction::CLI,
-      0x59 => Instruction::EOR(Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu))),
+      0x59 => Instruction::EOR(Operand::AbsoluteY(Instruction::next_addr(&mut next_byte))),
       0x5a => Instruction::Illegal(Box::new(Instruction::NOP), None),
       0x5b => {
-        let op = Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteY(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::SRE(op.clone())), Some(op))
       }
       0x5c => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+        Some(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       ),
-      0x5d => Instruction::EOR(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
-      0x5e => Instruction::LSR(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+      0x5d => Instruction::EOR(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
+      0x5e => Instruction::LSR(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       0x5f => {
-        let op = Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteX(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::SRE(op.clone())), Some(op))
       }
 
       0x60 => Instruction::RTS,
-      0x61 => Instruction::ADC(Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu))),
+      0x61 => Instruction::ADC(Operand::IndirectX(next_byte())),
       0x63 => {
-        let op = Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectX(next_byte());
         Instruction::Illegal(Box::new(Instruction::RRA(op.clone())), Some(op))
       }
       0x64 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::ZeroPage(next_byte())),
       ),
-      0x65 => Instruction::ADC(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0x66 => Instruction::ROR(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+      0x65 => Instruction::ADC(Operand::ZeroPage(next_byte())),
+      0x66 => Instruction::ROR(Operand::ZeroPage(next_byte())),
       0x67 => {
-        let op = Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPage(next_byte());
         Instruction::Illegal(Box::new(Instruction::RRA(op.clone())), Some(op))
       }
       0x68 => Instruction::PLA,
-      0x69 => Instruction::ADC(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
+      0x69 => Instruction::ADC(Operand::Immediate(next_byte())),
       0x6a => Instruction::ROR(Operand::Accumulator),
-      0x6c => Instruction::JMP(Operand::Indirect(Instruction::load_addr(cpu_bus, cpu))),
-      0x6d => Instruction::ADC(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0x6e => Instruction::ROR(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
+      0x6c => Instruction::JMP(Operand::Indirect(Instruction::next_addr(&mut next_byte))),
+      0x6d => Instruction::ADC(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0x6e => Instruction::ROR(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
       0x6f => {
-        let op = Operand::Absolute(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::Absolute(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::RRA(op.clone())), Some(op))
       }
 
-      0x70 => Instruction::BVS(Operand::Relative(Instruction::load_offset(cpu_bus, cpu))),
-      0x71 => Instruction::ADC(Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu))),
+      0x70 => Instruction::BVS(Operand::Relative(next_byte() as i8)),
+      0x71 => Instruction::ADC(Operand::IndirectY(next_byte())),
       0x73 => {
-        let op = Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectY(next_byte());
         Instruction::Illegal(Box::new(Instruction::RRA(op.clone())), Some(op))
       }
       0x74 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::ZeroPageX(next_byte())),
       ),
-      0x75 => Instruction::ADC(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0x76 => Instruction::ROR(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+      0x75 => Instruction::ADC(Operand::ZeroPageX(next_byte())),
+      0x76 => Instruction::ROR(Operand::ZeroPageX(next_byte())),
       0x77 => {
-        let op = Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPageX(next_byte());
         Instruction::Illegal(Box::new(Instruction::RRA(op.clone())), Some(op))
       }
       0x78 => Instruction::SEI,
-      0x79 => Instruction::ADC(Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu))),
+      0x79 => Instruction::ADC(Operand::AbsoluteY(Instruction::next_addr(&mut next_byte))),
       0x7a => Instruction::Illegal(Box::new(Instruction::NOP), None),
       0x7b => {
-        let op = Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteY(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::RRA(op.clone())), Some(op))
       }
       0x7c => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+        Some(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       ),
-      0x7d => Instruction::ADC(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
-      0x7e => Instruction::ROR(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+      0x7d => Instruction::ADC(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
+      0x7e => Instruction::ROR(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       0x7f => {
-        let op = Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteX(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::RRA(op.clone())), Some(op))
       }
 
       0x80 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::Immediate(next_byte())),
       ),
-      0x81 => Instruction::STA(Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu))),
+      0x81 => Instruction::STA(Operand::IndirectX(next_byte())),
       0x83 => {
-        let op = Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectX(next_byte());
         Instruction::Illegal(Box::new(Instruction::SAX(op.clone())), Some(op))
       }
-      0x84 => Instruction::STY(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0x85 => Instruction::STA(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0x86 => Instruction::STX(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+      0x84 => Instruction::STY(Operand::ZeroPage(next_byte())),
+      0x85 => Instruction::STA(Operand::ZeroPage(next_byte())),
+      0x86 => Instruction::STX(Operand::ZeroPage(next_byte())),
       0x87 => {
-        let op = Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPage(next_byte());
         Instruction::Illegal(Box::new(Instruction::SAX(op.clone())), Some(op))
       }
       0x88 => Instruction::DEY,
       0x8a => Instruction::TXA,
-      0x8c => Instruction::STY(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0x8d => Instruction::STA(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0x8e => Instruction::STX(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
+      0x8c => Instruction::STY(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0x8d => Instruction::STA(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0x8e => Instruction::STX(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
       0x8f => {
-        let op = Operand::Absolute(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::Absolute(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::SAX(op.clone())), Some(op))
       }
 
-      0x90 => Instruction::BCC(Operand::Relative(Instruction::load_offset(cpu_bus, cpu))),
-      0x91 => Instruction::STA(Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu))),
-      0x94 => Instruction::STY(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0x95 => Instruction::STA(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0x96 => Instruction::STX(Operand::ZeroPageY(Instruction::load_byte(cpu_bus, cpu))),
+      0x90 => Instruction::BCC(Operand::Relative(next_byte() as i8)),
+      0x91 => Instruction::STA(Operand::IndirectY(next_byte())),
+      0x94 => Instruction::STY(Operand::ZeroPageX(next_byte())),
+      0x95 => Instruction::STA(Operand::ZeroPageX(next_byte())),
+      0x96 => Instruction::STX(Operand::ZeroPageY(next_byte())),
       0x97 => {
-        let op = Operand::ZeroPageY(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPageY(next_byte());
         Instruction::Illegal(Box::new(Instruction::SAX(op.clone())), Some(op))
       }
       0x98 => Instruction::TYA,
-      0x99 => Instruction::STA(Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu))),
+      0x99 => Instruction::STA(Operand::AbsoluteY(Instruction::next_addr(&mut next_byte))),
       0x9a => Instruction::TXS,
-      0x9d => Instruction::STA(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+      0x9d => Instruction::STA(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
 
-      0xa0 => Instruction::LDY(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
-      0xa1 => Instruction::LDA(Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu))),
-      0xa2 => Instruction::LDX(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
+      0xa0 => Instruction::LDY(Operand::Immediate(next_byte())),
+      0xa1 => Instruction::LDA(Operand::IndirectX(next_byte())),
+      0xa2 => Instruction::LDX(Operand::Immediate(next_byte())),
       0xa3 => {
-        let op = Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectX(next_byte());
         Instruction::Illegal(Box::new(Instruction::LAX(op.clone())), Some(op))
       }
-      0xa4 => Instruction::LDY(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0xa5 => Instruction::LDA(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0xa6 => Instruction::LDX(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+      0xa4 => Instruction::LDY(Operand::ZeroPage(next_byte())),
+      0xa5 => Instruction::LDA(Operand::ZeroPage(next_byte())),
+      0xa6 => Instruction::LDX(Operand::ZeroPage(next_byte())),
       0xa7 => {
-        let op = Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPage(next_byte());
         Instruction::Illegal(Box::new(Instruction::LAX(op.clone())), Some(op))
       }
       0xa8 => Instruction::TAY,
-      0xa9 => Instruction::LDA(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
+      0xa9 => Instruction::LDA(Operand::Immediate(next_byte())),
       0xaa => Instruction::TAX,
-      0xac => Instruction::LDY(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0xad => Instruction::LDA(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0xae => Instruction::LDX(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
+      0xac => Instruction::LDY(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0xad => Instruction::LDA(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0xae => Instruction::LDX(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
       0xaf => {
-        let op = Operand::Absolute(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::Absolute(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::LAX(op.clone())), Some(op))
       }
 
-      0xb0 => Instruction::BCS(Operand::Relative(Instruction::load_offset(cpu_bus, cpu))),
-      0xb1 => Instruction::LDA(Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu))),
+      0xb0 => Instruction::BCS(Operand::Relative(next_byte() as i8)),
+      0xb1 => Instruction::LDA(Operand::IndirectY(next_byte())),
       0xb3 => {
-        let op = Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectY(next_byte());
         Instruction::Illegal(Box::new(Instruction::LAX(op.clone())), Some(op))
       }
-      0xb4 => Instruction::LDY(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0xb5 => Instruction::LDA(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0xb6 => Instruction::LDX(Operand::ZeroPageY(Instruction::load_byte(cpu_bus, cpu))),
+      0xb4 => Instruction::LDY(Operand::ZeroPageX(next_byte())),
+      0xb5 => Instruction::LDA(Operand::ZeroPageX(next_byte())),
+      0xb6 => Instruction::LDX(Operand::ZeroPageY(next_byte())),
       0xb7 => {
-        let op = Operand::ZeroPageY(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPageY(next_byte());
         Instruction::Illegal(Box::new(Instruction::LAX(op.clone())), Some(op))
       }
-      0xb9 => Instruction::LDA(Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu))),
+      0xb9 => Instruction::LDA(Operand::AbsoluteY(Instruction::next_addr(&mut next_byte))),
       0xba => Instruction::TSX,
-      0xbc => Instruction::LDY(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
-      0xbd => Instruction::LDA(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
-      0xbe => Instruction::LDX(Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu))),
+      0xbc => Instruction::LDY(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
+      0xbd => Instruction::LDA(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
+      0xbe => Instruction::LDX(Operand::AbsoluteY(Instruction::next_addr(&mut next_byte))),
       0xb8 => Instruction::CLV,
       0xbf => {
-        let op = Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteY(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::LAX(op.clone())), Some(op))
       }
 
-      0xc0 => Instruction::CPY(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
-      0xc1 => Instruction::CMP(Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu))),
+      0xc0 => Instruction::CPY(Operand::Immediate(next_byte())),
+      0xc1 => Instruction::CMP(Operand::IndirectX(next_byte())),
       0xc3 => {
-        let op = Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectX(next_byte());
         Instruction::Illegal(Box::new(Instruction::DCP(op.clone())), Some(op))
       }
-      0xc4 => Instruction::CPY(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0xc5 => Instruction::CMP(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0xc6 => Instruction::DEC(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+      0xc4 => Instruction::CPY(Operand::ZeroPage(next_byte())),
+      0xc5 => Instruction::CMP(Operand::ZeroPage(next_byte())),
+      0xc6 => Instruction::DEC(Operand::ZeroPage(next_byte())),
       0xc7 => {
-        let op = Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPage(next_byte());
         Instruction::Illegal(Box::new(Instruction::DCP(op.clone())), Some(op))
       }
       0xc8 => Instruction::INY,
-      0xc9 => Instruction::CMP(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
+      0xc9 => Instruction::CMP(Operand::Immediate(next_byte())),
       0xca => Instruction::DEX,
-      0xcc => Instruction::CPY(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0xcd => Instruction::CMP(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0xce => Instruction::DEC(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
+      0xcc => Instruction::CPY(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0xcd => Instruction::CMP(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0xce => Instruction::DEC(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
       0xcf => {
-        let op = Operand::Absolute(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::Absolute(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::DCP(op.clone())), Some(op))
       }
 
-      0xd0 => Instruction::BNE(Operand::Relative(Instruction::load_offset(cpu_bus, cpu))),
-      0xd1 => Instruction::CMP(Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu))),
+      0xd0 => Instruction::BNE(Operand::Relative(next_byte() as i8)),
+      0xd1 => Instruction::CMP(Operand::IndirectY(next_byte())),
       0xd3 => {
-        let op = Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectY(next_byte());
         Instruction::Illegal(Box::new(Instruction::DCP(op.clone())), Some(op))
       }
       0xd4 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::ZeroPageX(next_byte())),
       ),
-      0xd5 => Instruction::CMP(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0xd6 => Instruction::DEC(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+      0xd5 => Instruction::CMP(Operand::ZeroPageX(next_byte())),
+      0xd6 => Instruction::DEC(Operand::ZeroPageX(next_byte())),
       0xd7 => {
-        let op = Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPageX(next_byte());
         Instruction::Illegal(Box::new(Instruction::DCP(op.clone())), Some(op))
       }
       0xd8 => Instruction::CLD,
-      0xd9 => Instruction::CMP(Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu))),
+      0xd9 => Instruction::CMP(Operand::AbsoluteY(Instruction::next_addr(&mut next_byte))),
       0xda => Instruction::Illegal(Box::new(Instruction::NOP), None),
       0xdb => {
-        let op = Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteY(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::DCP(op.clone())), Some(op))
       }
       0xdc => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+        Some(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       ),
-      0xdd => Instruction::CMP(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
-      0xde => Instruction::DEC(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+      0xdd => Instruction::CMP(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
+      0xde => Instruction::DEC(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       0xdf => {
-        let op = Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteX(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::DCP(op.clone())), Some(op))
       }
 
-      0xe0 => Instruction::CPX(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
-      0xe1 => Instruction::SBC(Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu))),
+      0xe0 => Instruction::CPX(Operand::Immediate(next_byte())),
+      0xe1 => Instruction::SBC(Operand::IndirectX(next_byte())),
       0xe3 => {
-        let op = Operand::IndirectX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectX(next_byte());
         Instruction::Illegal(Box::new(Instruction::ISB(op.clone())), Some(op))
       }
-      0xe4 => Instruction::CPX(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0xe5 => Instruction::SBC(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
-      0xe6 => Instruction::INC(Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu))),
+      0xe4 => Instruction::CPX(Operand::ZeroPage(next_byte())),
+      0xe5 => Instruction::SBC(Operand::ZeroPage(next_byte())),
+      0xe6 => Instruction::INC(Operand::ZeroPage(next_byte())),
       0xe7 => {
-        let op = Operand::ZeroPage(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPage(next_byte());
         Instruction::Illegal(Box::new(Instruction::ISB(op.clone())), Some(op))
       }
       0xe8 => Instruction::INX,
-      0xe9 => Instruction::SBC(Operand::Immediate(Instruction::load_byte(cpu_bus, cpu))),
+      0xe9 => Instruction::SBC(Operand::Immediate(next_byte())),
       0xea => Instruction::NOP,
       0xeb => {
-        let op = Operand::Immediate(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::Immediate(next_byte());
         Instruction::Illegal(Box::new(Instruction::SBC(op.clone())), Some(op))
       }
-      0xec => Instruction::CPX(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0xed => Instruction::SBC(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
-      0xee => Instruction::INC(Operand::Absolute(Instruction::load_addr(cpu_bus, cpu))),
+      0xec => Instruction::CPX(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0xed => Instruction::SBC(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
+      0xee => Instruction::INC(Operand::Absolute(Instruction::next_addr(&mut next_byte))),
       0xef => {
-        let op = Operand::Absolute(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::Absolute(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::ISB(op.clone())), Some(op))
       }
 
-      0xf0 => Instruction::BEQ(Operand::Relative(Instruction::load_offset(cpu_bus, cpu))),
-      0xf1 => Instruction::SBC(Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu))),
+      0xf0 => Instruction::BEQ(Operand::Relative(next_byte() as i8)),
+      0xf1 => Instruction::SBC(Operand::IndirectY(next_byte())),
       0xf3 => {
-        let op = Operand::IndirectY(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::IndirectY(next_byte());
         Instruction::Illegal(Box::new(Instruction::ISB(op.clone())), Some(op))
       }
       0xf4 => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+        Some(Operand::ZeroPageX(next_byte())),
       ),
-      0xf5 => Instruction::SBC(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
-      0xf6 => Instruction::INC(Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu))),
+      0xf5 => Instruction::SBC(Operand::ZeroPageX(next_byte())),
+      0xf6 => Instruction::INC(Operand::ZeroPageX(next_byte())),
       0xf7 => {
-        let op = Operand::ZeroPageX(Instruction::load_byte(cpu_bus, cpu));
+        let op = Operand::ZeroPageX(next_byte());
         Instruction::Illegal(Box::new(Instruction::ISB(op.clone())), Some(op))
       }
       0xf8 => Instruction::SED,
-      0xf9 => Instruction::SBC(Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu))),
+      0xf9 => Instruction::SBC(Operand::AbsoluteY(Instruction::next_addr(&mut next_byte))),
       0xfb => {
-        let op = Operand::AbsoluteY(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteY(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::ISB(op.clone())), Some(op))
       }
       0xfc => Instruction::Illegal(
         Box::new(Instruction::NOP),
-        Some(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+        Some(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       ),
       0xfa => Instruction::Illegal(Box::new(Instruction::NOP), None),
-      0xfd => Instruction::SBC(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
-      0xfe => Instruction::INC(Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu))),
+      0xfd => Instruction::SBC(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
+      0xfe => Instruction::INC(Operand::AbsoluteX(Instruction::next_addr(&mut next_byte))),
       0xff => {
-        let op = Operand::AbsoluteX(Instruction::load_addr(cpu_bus, cpu));
+        let op = Operand::AbsoluteX(Instruction::next_addr(&mut next_byte));
         Instruction::Illegal(Box::new(Instruction::ISB(op.clone())), Some(op))
       }
 
-      _ => {
-        panic!("Unknown opcode {:#04x}", opcode);
-      }
+      _ => return None,
     };
 
-    (instruction, opcode)
+    Some(instruction)
   }
 }
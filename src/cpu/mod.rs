@@ -63,7 +63,10 @@ mod tests {
 
     let mut fake_pixbuf = Pixbuf::new();
     let disasm_writer = StringWriter::new();
-    machine.disassembly_writer = Some(Arc::new(RwLock::new(disasm_writer.clone())));
+    machine
+      .trace_logger
+      .set_writer(Some(Arc::new(RwLock::new(disasm_writer.clone()))));
+    machine.trace_logger.start();
 
     // weird PPU behavior tests start here and I'm not sure those are valid
     while machine.state.cpu_cycle_count < 26520 {
@@ -89,7 +92,7 @@ mod tests {
 
     assert_eq!(
       disasm.split('\n').count(),
-      8980,
+      8981,
       "Number of lines in disassembly log did not match"
     );
   }
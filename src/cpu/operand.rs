@@ -1,4 +1,5 @@
 use super::{CPUBusTrait, CPU};
+use crate::debugger::CPUAccessKind;
 
 #[derive(Debug, Clone)]
 pub enum Operand {
@@ -112,6 +113,17 @@ impl Operand {
     match self {
       Operand::Accumulator => (cpu.a, false),
       Operand::Immediate(value) => (*value, false),
+      Operand::IndirectX(_) | Operand::IndirectY(_) => {
+        let (addr, page_boundary_crossed) = self.get_addr(cpu, cpu_bus);
+        cpu_bus
+          .code_data_logger_mut()
+          .set_access_kind(CPUAccessKind::IndirectData);
+        let value = cpu_bus.read(addr);
+        cpu_bus
+          .code_data_logger_mut()
+          .set_access_kind(CPUAccessKind::Data);
+        (value, page_boundary_crossed)
+      }
       _ => {
         let (addr, page_boundary_crossed) = self.get_addr(cpu, cpu_bus);
         (cpu_bus.read(addr), page_boundary_crossed)
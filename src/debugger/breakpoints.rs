@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+
+use super::{SymbolAddress, SymbolTable};
+
+/// Pauses emulation before the instruction at an address executes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+  pub address: SymbolAddress,
+  /// The symbol the breakpoint was set by, if any.
+  pub label: Option<String>,
+}
+
+impl Breakpoint {
+  /// Parses a symbol name, or a CPU address like `$C000`.
+  pub fn parse(input: &str, symbols: &SymbolTable) -> anyhow::Result<Self> {
+    let input = input.trim();
+    if let Some(address) = symbols.lookup(input) {
+      return Ok(Self {
+        address,
+        label: Some(input.to_owned()),
+      });
+    }
+
+    u16::from_str_radix(input.trim_start_matches('$'), 16)
+      .map(|addr| Self {
+        address: SymbolAddress::CPU(addr),
+        label: None,
+      })
+      .map_err(|_| anyhow!("No symbol or address '{}'", input))
+  }
+
+  pub fn matches(&self, pc: u16, prg_rom_offset: Option<usize>) -> bool {
+    match self.address {
+      SymbolAddress::CPU(addr) => addr == pc,
+      SymbolAddress::PRGROM(offset) => prg_rom_offset == Some(offset),
+    }
+  }
+}
+
+impl Display for Breakpoint {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match &self.label {
+      Some(label) => write!(f, "{} ({})", label, self.address),
+      None => write!(f, "{}", self.address),
+    }
+  }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use super::SymbolAddress;
+
+/// One line of an ld65 `--dbgfile` output: a record type, a tab, then `key=value` fields, e.g.
+/// `id=0,name="reset",addrsize=absolute,val=0x8000,seg=0,type=lab` for a `sym`.
+#[derive(Debug, Clone)]
+pub struct DbgRecord<'a> {
+  pub kind: &'a str,
+  fields: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> DbgRecord<'a> {
+  fn parse(line: &'a str) -> Option<Self> {
+    let (kind, rest) = line.split_once(char::is_whitespace)?;
+
+    let mut fields = HashMap::new();
+    let mut in_quotes = false;
+    let mut field_start = 0;
+    for (index, c) in rest
+      .char_indices()
+      .chain(std::iter::once((rest.len(), ',')))
+    {
+      match c {
+        '"' => in_quotes = !in_quotes,
+        ',' if !in_quotes => {
+          if let Some((key, value)) = rest[field_start..index].split_once('=') {
+            fields.insert(key.trim(), value.trim().trim_matches('"'));
+          }
+          field_start = index + 1;
+        }
+        _ => {}
+      }
+    }
+
+    Some(Self { kind, fields })
+  }
+
+  pub fn get(&self, key: &str) -> Option<&'a str> {
+    self.fields.get(key).copied()
+  }
+
+  /// Numbers are written in hex with a `0x` prefix, or in decimal.
+  pub fn number(&self, key: &str) -> Option<usize> {
+    let value = self.get(key)?;
+    match value.strip_prefix("0x") {
+      Some(hex) => usize::from_str_radix(hex, 16).ok(),
+      None => value.parse().ok(),
+    }
+  }
+
+  /// Lists of ids are joined with `+`.
+  pub fn ids(&self, key: &str) -> Vec<usize> {
+    self
+      .get(key)
+      .map(|value| value.split('+').filter_map(|id| id.parse().ok()).collect())
+      .unwrap_or_default()
+  }
+}
+
+pub fn parse_dbg_records(contents: &str) -> Vec<DbgRecord<'_>> {
+  contents.lines().filter_map(DbgRecord::parse).collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DbgSegment {
+  pub start: u16,
+  pub size: usize,
+  /// Offset of the segment's start into PRG ROM, for segments written to the ROM file.
+  prg_rom_offset: Option<usize>,
+}
+
+impl DbgSegment {
+  pub fn address(&self, addr: u16) -> SymbolAddress {
+    let segment_offset = addr
+      .checked_sub(self.start)
+      .map(usize::from)
+      .filter(|segment_offset| *segment_offset < self.size);
+
+    match (self.prg_rom_offset, segment_offset) {
+      (Some(prg_rom_offset), Some(segment_offset)) => {
+        SymbolAddress::PRGROM(prg_rom_offset + segment_offset)
+      }
+      _ => SymbolAddress::CPU(addr),
+    }
+  }
+}
+
+/// Segments by id. Read-only segments that end up in the ROM file are mapped to PRG ROM offsets,
+/// skipping the iNES header if the linker config writes one.
+pub fn dbg_segments(records: &[DbgRecord]) -> HashMap<usize, DbgSegment> {
+  let segments = records.iter().filter(|record| record.kind == "seg");
+  let header_size = segments
+    .clone()
+    .find(|segment| segment.get("name") == Some("HEADER") && segment.number("ooffs") == Some(0))
+    .and_then(|segment| segment.number("size"))
+    .unwrap_or(0);
+
+  segments
+    .filter_map(|segment| {
+      let prg_rom_offset = match (segment.get("type"), segment.number("ooffs")) {
+        (Some("ro"), Some(ooffs)) if ooffs >= header_size && segment.get("oname").is_some() => {
+          Some(ooffs - header_size)
+        }
+        _ => None,
+      };
+
+      Some((
+        segment.number("id")?,
+        DbgSegment {
+          start: u16::try_from(segment.number("start")?).ok()?,
+          size: segment.number("size")?,
+          prg_rom_offset,
+        },
+      ))
+    })
+    .collect()
+}
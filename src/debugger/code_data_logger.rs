@@ -0,0 +1,256 @@
+use std::{fs, path::Path};
+
+use bitfield_struct::bitfield;
+
+// FCEUX/Mesen .cdl files are one byte per PRG ROM byte followed by one byte per CHR ROM byte
+const CDL_PRG_CODE: u8 = 0x01;
+const CDL_PRG_DATA: u8 = 0x02;
+const CDL_PRG_INDIRECT_CODE: u8 = 0x10;
+const CDL_PRG_INDIRECT_DATA: u8 = 0x20;
+const CDL_CHR_DRAWN: u8 = 0x01;
+const CDL_CHR_READ: u8 = 0x02;
+
+#[bitfield(u8)]
+pub struct PRGAccessFlags {
+  pub opcode: bool,
+  pub operand: bool,
+  pub data: bool,
+  pub indirect_code: bool,
+  pub indirect_data: bool,
+  // which 8KB CPU window ($8000/$A000/$C000/$E000) the byte was last accessed through
+  #[bits(2)]
+  pub bank: u8,
+  _unused: bool,
+}
+
+impl PRGAccessFlags {
+  pub fn is_code(&self) -> bool {
+    self.opcode() || self.operand()
+  }
+
+  pub fn to_cdl(self) -> u8 {
+    let mut cdl = self.bank() << 2;
+
+    if self.is_code() {
+      cdl |= CDL_PRG_CODE;
+    }
+    if self.data() {
+      cdl |= CDL_PRG_DATA;
+    }
+    if self.indirect_code() {
+      cdl |= CDL_PRG_INDIRECT_CODE;
+    }
+    if self.indirect_data() {
+      cdl |= CDL_PRG_INDIRECT_DATA;
+    }
+
+    cdl
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CPUAccessKind {
+  Opcode,
+  IndirectJumpOpcode,
+  Operand,
+  #[default]
+  Data,
+  IndirectData,
+}
+
+/// Records how the CPU accessed each byte of PRG ROM. Lives on the CPU bus, so the CPU can tell it
+/// what kind of access is coming next and the mapper can log the physical ROM offset it reads.
+#[derive(Debug, Clone, Default)]
+pub struct CodeDataLogger {
+  enabled: bool,
+  access_kind: CPUAccessKind,
+  indirect_jump_pending: bool,
+  prg_flags: Vec<PRGAccessFlags>,
+}
+
+impl CodeDataLogger {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.enabled
+  }
+
+  /// `prg_rom_len` is the size of the mapper's PRG ROM storage; flags logged so far are kept as
+  /// long as it doesn't change.
+  pub fn set_enabled(&mut self, enabled: bool, prg_rom_len: usize) {
+    self.enabled = enabled;
+    if self.prg_flags.len() != prg_rom_len {
+      self.prg_flags = vec![PRGAccessFlags::new(); prg_rom_len];
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.prg_flags.fill(PRGAccessFlags::new());
+  }
+
+  pub fn set_access_kind(&mut self, access_kind: CPUAccessKind) {
+    self.access_kind = access_kind;
+  }
+
+  pub fn begin_opcode_fetch(&mut self) {
+    self.access_kind = if self.indirect_jump_pending {
+      CPUAccessKind::IndirectJumpOpcode
+    } else {
+      CPUAccessKind::Opcode
+    };
+    self.indirect_jump_pending = false;
+  }
+
+  pub fn indirect_jump_taken(&mut self) {
+    self.indirect_jump_pending = true;
+  }
+
+  pub fn log_prg_read(&mut self, prg_rom_offset: usize, addr: u16) {
+    let Some(flags) = self.prg_flags.get_mut(prg_rom_offset) else {
+      return;
+    };
+
+    flags.set_bank(((addr >> 13) & 0b11) as u8);
+    match self.access_kind {
+      CPUAccessKind::Opcode => flags.set_opcode(true),
+      CPUAccessKind::IndirectJumpOpcode => {
+        flags.set_opcode(true);
+        flags.set_indirect_code(true);
+      }
+      CPUAccessKind::Operand => flags.set_operand(true),
+      CPUAccessKind::Data => flags.set_data(true),
+      CPUAccessKind::IndirectData => {
+        flags.set_data(true);
+        flags.set_indirect_data(true);
+      }
+    }
+  }
+
+  pub fn prg_flags(&self) -> &[PRGAccessFlags] {
+    &self.prg_flags
+  }
+}
+
+/// Records which CHR bytes the PPU drew as tile data (and which the CPU read through $2007).
+#[derive(Debug, Clone, Default)]
+pub struct CHRDataLogger {
+  enabled: bool,
+  cpu_reading: bool,
+  chr_flags: Vec<u8>,
+}
+
+impl CHRDataLogger {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.enabled
+  }
+
+  pub fn set_enabled(&mut self, enabled: bool, chr_mem_len: usize) {
+    self.enabled = enabled;
+    if self.chr_flags.len() != chr_mem_len {
+      self.chr_flags = vec![0; chr_mem_len];
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.chr_flags.fill(0);
+  }
+
+  pub fn set_cpu_reading(&mut self, cpu_reading: bool) {
+    self.cpu_reading = cpu_reading;
+  }
+
+  pub fn log_chr_read(&mut self, chr_mem_offset: usize) {
+    if let Some(flags) = self.chr_flags.get_mut(chr_mem_offset) {
+      *flags |= if self.cpu_reading {
+        CDL_CHR_READ
+      } else {
+        CDL_CHR_DRAWN
+      };
+    }
+  }
+
+  pub fn chr_flags(&self) -> &[u8] {
+    &self.chr_flags
+  }
+}
+
+// Mappers may mirror a small ROM to fill their address space, so flags are folded back down onto
+// the ROM's actual size.
+fn fold_flags(flags: impl Iterator<Item = u8>, size: usize) -> Vec<u8> {
+  let mut folded = vec![0; size];
+  if size == 0 {
+    return folded;
+  }
+
+  for (offset, flag) in flags.enumerate() {
+    folded[offset % size] |= flag;
+  }
+
+  folded
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CDLFile {
+  pub prg: Vec<u8>,
+  pub chr: Vec<u8>,
+}
+
+impl CDLFile {
+  /// `chr_rom_size` should be 0 for cartridges with CHR RAM, which leaves out the CHR section.
+  pub fn build(
+    code_data_logger: &CodeDataLogger,
+    chr_data_logger: &CHRDataLogger,
+    prg_rom_size: usize,
+    chr_rom_size: usize,
+  ) -> Self {
+    Self {
+      prg: fold_flags(
+        code_data_logger
+          .prg_flags()
+          .iter()
+          .map(|flags| flags.to_cdl()),
+        prg_rom_size,
+      ),
+      chr: fold_flags(chr_data_logger.chr_flags().iter().copied(), chr_rom_size),
+    }
+  }
+
+  pub fn to_bytes(&self) -> Vec<u8> {
+    [self.prg.as_slice(), self.chr.as_slice()].concat()
+  }
+
+  pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+    fs::write(path, self.to_bytes())?;
+    Ok(())
+  }
+
+  pub fn prg_code_bytes(&self) -> usize {
+    self
+      .prg
+      .iter()
+      .filter(|flags| *flags & CDL_PRG_CODE != 0)
+      .count()
+  }
+
+  pub fn prg_data_bytes(&self) -> usize {
+    self
+      .prg
+      .iter()
+      .filter(|flags| *flags & CDL_PRG_DATA != 0)
+      .count()
+  }
+
+  pub fn chr_drawn_bytes(&self) -> usize {
+    self
+      .chr
+      .iter()
+      .filter(|flags| *flags & CDL_CHR_DRAWN != 0)
+      .count()
+  }
+}
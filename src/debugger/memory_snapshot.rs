@@ -0,0 +1,87 @@
+use std::fmt::Display;
+
+use strum::{Display, EnumIter};
+
+use crate::cartridge::Cartridge;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RamAddress {
+  WorkRam(u16),
+  PrgRam(u16),
+}
+
+impl Display for RamAddress {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      RamAddress::WorkRam(offset) => write!(f, "${:04X}", offset),
+      // PRG RAM beyond the first 8KB is only reachable through bank switching
+      RamAddress::PrgRam(offset) if *offset < 0x2000 => write!(f, "${:04X}", 0x6000 + offset),
+      RamAddress::PrgRam(offset) => write!(f, "PRG:${:04X}", offset),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumIter)]
+pub enum ValueType {
+  #[default]
+  U8,
+  I8,
+  U16,
+  I16,
+}
+
+impl ValueType {
+  pub fn byte_len(&self) -> usize {
+    match self {
+      ValueType::U8 | ValueType::I8 => 1,
+      ValueType::U16 | ValueType::I16 => 2,
+    }
+  }
+
+  fn interpret(&self, bytes: &[u8]) -> i32 {
+    match self {
+      ValueType::U8 => i32::from(bytes[0]),
+      ValueType::I8 => i32::from(bytes[0] as i8),
+      ValueType::U16 => i32::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+      ValueType::I16 => i32::from(i16::from_le_bytes([bytes[0], bytes[1]])),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemorySnapshot {
+  pub work_ram: Vec<u8>,
+  pub prg_ram: Vec<u8>,
+}
+
+impl MemorySnapshot {
+  pub fn capture(cartridge: &Cartridge) -> Self {
+    Self {
+      work_ram: cartridge.cpu_bus().work_ram().to_vec(),
+      prg_ram: cartridge.prg_ram().map(<[u8]>::to_vec).unwrap_or_default(),
+    }
+  }
+
+  fn region(&self, addr: RamAddress) -> (&[u8], usize) {
+    match addr {
+      RamAddress::WorkRam(offset) => (&self.work_ram, usize::from(offset)),
+      RamAddress::PrgRam(offset) => (&self.prg_ram, usize::from(offset)),
+    }
+  }
+
+  // 16-bit values are little-endian and don't span across the work RAM/PRG RAM boundary
+  pub fn read_value(&self, addr: RamAddress, value_type: ValueType) -> Option<i32> {
+    let (memory, offset) = self.region(addr);
+    let bytes = memory.get(offset..offset + value_type.byte_len())?;
+    Some(value_type.interpret(bytes))
+  }
+
+  pub fn addresses(&self, value_type: ValueType) -> impl Iterator<Item = RamAddress> {
+    let work_ram_len = (self.work_ram.len() + 1).saturating_sub(value_type.byte_len());
+    let prg_ram_len = (self.prg_ram.len() + 1).saturating_sub(value_type.byte_len());
+
+    (0..work_ram_len)
+      .map(|offset| RamAddress::WorkRam(offset as u16))
+      .chain((0..prg_ram_len).map(|offset| RamAddress::PrgRam(offset as u16)))
+  }
+}
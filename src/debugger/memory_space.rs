@@ -0,0 +1,114 @@
+use strum::{Display, EnumIter};
+
+use crate::{cartridge::Cartridge, ppu::PPUOAMEntry};
+
+pub const MEMORY_PAGE_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumIter)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum MemorySpace {
+  #[default]
+  CPU,
+  PPU,
+  OAM,
+  #[strum(serialize = "PRG ROM")]
+  PRGROM,
+  #[strum(serialize = "CHR")]
+  CHR,
+}
+
+impl MemorySpace {
+  pub fn len(&self, cartridge: &Cartridge) -> usize {
+    match self {
+      MemorySpace::PRGROM => cartridge.prg_rom().len(),
+      MemorySpace::CHR => cartridge.chr_mem().len(),
+      _ => self.fixed_len().unwrap(),
+    }
+  }
+
+  /// The length of the space if it doesn't depend on the cartridge.
+  pub fn fixed_len(&self) -> Option<usize> {
+    match self {
+      MemorySpace::CPU => Some(0x10000),
+      MemorySpace::PPU => Some(0x4000),
+      MemorySpace::OAM => Some(256),
+      MemorySpace::PRGROM | MemorySpace::CHR => None,
+    }
+  }
+
+  // only ever uses readonly reads, so inspecting registers like $2002 or $4016 doesn't disturb them
+  pub fn read(&self, cartridge: &Cartridge, addr: usize) -> Option<u8> {
+    match self {
+      MemorySpace::CPU => cartridge.cpu_bus().try_read_readonly(addr as u16),
+      MemorySpace::PPU => cartridge
+        .ppu_cpu_bus()
+        .ppu_memory()
+        .try_read_readonly(addr as u16),
+      MemorySpace::OAM => {
+        let entry = cartridge.ppu_cpu_bus().oam().get(addr / 4)?;
+        Some(u32::from(*entry).to_le_bytes()[addr % 4])
+      }
+      MemorySpace::PRGROM => cartridge.prg_rom().get(addr).copied(),
+      MemorySpace::CHR => cartridge.chr_mem().get(addr).copied(),
+    }
+  }
+
+  /// Whether `poke` can change the byte at `addr`. Writing an I/O register does more than store a
+  /// value, so those can't be edited.
+  pub fn can_poke(&self, addr: usize) -> bool {
+    *self != MemorySpace::CPU || !(0x2000..0x4020).contains(&addr)
+  }
+
+  // writes the memory behind an address directly instead of going through the CPU bus, so editing
+  // e.g. $8000 doesn't switch banks
+  pub fn poke(&self, cartridge: &mut Cartridge, addr: usize, value: u8) {
+    match self {
+      MemorySpace::CPU => cartridge.poke_cpu(addr as u16, value),
+      MemorySpace::PPU => cartridge
+        .ppu_cpu_bus_mut()
+        .ppu_memory_mut()
+        .write(addr as u16, value),
+      MemorySpace::OAM => {
+        if let Some(entry) = cartridge.ppu_cpu_bus_mut().oam_mut().get_mut(addr / 4) {
+          let mut bytes = u32::from(*entry).to_le_bytes();
+          bytes[addr % 4] = value;
+          *entry = PPUOAMEntry::from(u32::from_le_bytes(bytes));
+        }
+      }
+      MemorySpace::PRGROM => {
+        if let Some(byte) = cartridge.prg_rom_mut().get_mut(addr) {
+          *byte = value;
+        }
+      }
+      MemorySpace::CHR => {
+        if let Some(byte) = cartridge.chr_mem_mut().get_mut(addr) {
+          *byte = value;
+        }
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryPage {
+  pub space: MemorySpace,
+  pub start: usize,
+  pub space_len: usize,
+  pub bytes: Vec<Option<u8>>,
+}
+
+impl MemoryPage {
+  pub fn capture(cartridge: &Cartridge, space: MemorySpace, start: usize) -> Self {
+    let space_len = space.len(cartridge);
+    let end = (start + MEMORY_PAGE_SIZE).min(space_len);
+
+    Self {
+      space,
+      start,
+      space_len,
+      bytes: (start..end)
+        .map(|addr| space.read(cartridge, addr))
+        .collect(),
+    }
+  }
+}
@@ -0,0 +1,475 @@
+mod breakpoints;
+mod ca65_debug_info;
+mod code_data_logger;
+mod memory_snapshot;
+mod memory_space;
+mod ppu_viewer;
+mod prg_disassembler;
+mod ram_search;
+mod ram_watch;
+mod source_map;
+mod symbols;
+mod trace_logger;
+
+pub use breakpoints::*;
+pub use ca65_debug_info::*;
+pub use code_data_logger::*;
+pub use memory_snapshot::*;
+pub use memory_space::*;
+pub use ppu_viewer::*;
+pub use prg_disassembler::*;
+pub use ram_search::*;
+pub use ram_watch::*;
+pub use source_map::*;
+pub use symbols::*;
+pub use trace_logger::*;
+
+#[cfg(test)]
+mod tests {
+  use std::{io::BufReader, path::Path};
+
+  use super::*;
+  use crate::{
+    cartridge::Cartridge,
+    nes::{INESRom, NES},
+    ppu::{PPUControlRegister, PPUOAMEntry, Pixbuf, PALETTE},
+  };
+
+  fn snapshot(work_ram: &[(usize, u8)], prg_ram: &[(usize, u8)]) -> MemorySnapshot {
+    let mut snapshot = MemorySnapshot {
+      work_ram: vec![0; 2048],
+      prg_ram: vec![0; 8 * 1024],
+    };
+
+    for (offset, value) in work_ram {
+      snapshot.work_ram[*offset] = *value;
+    }
+    for (offset, value) in prg_ram {
+      snapshot.prg_ram[*offset] = *value;
+    }
+
+    snapshot
+  }
+
+  #[test]
+  fn test_read_values() {
+    let snapshot = snapshot(
+      &[(0x10, 0xfe), (0x11, 0xff), (0x7ff, 0x12)],
+      &[(0x20, 0x34)],
+    );
+
+    assert_eq!(
+      snapshot.read_value(RamAddress::WorkRam(0x10), ValueType::U8),
+      Some(0xfe)
+    );
+    assert_eq!(
+      snapshot.read_value(RamAddress::WorkRam(0x10), ValueType::I8),
+      Some(-2)
+    );
+    assert_eq!(
+      snapshot.read_value(RamAddress::WorkRam(0x10), ValueType::U16),
+      Some(0xfffe)
+    );
+    assert_eq!(
+      snapshot.read_value(RamAddress::WorkRam(0x10), ValueType::I16),
+      Some(-2)
+    );
+    assert_eq!(
+      snapshot.read_value(RamAddress::PrgRam(0x20), ValueType::U8),
+      Some(0x34)
+    );
+    assert_eq!(
+      snapshot.read_value(RamAddress::WorkRam(0x7ff), ValueType::U16),
+      None
+    );
+    assert_eq!(RamAddress::PrgRam(0x20).to_string(), "$6020");
+    assert_eq!(snapshot.addresses(ValueType::U16).count(), 2047 + 8191);
+  }
+
+  #[test]
+  fn test_search_filters() {
+    let mut search = RamSearch::default();
+    search.reset(snapshot(&[(0x75a, 3), (0x10, 3)], &[]), ValueType::U8);
+    assert_eq!(search.candidates().len(), 2048 + 8 * 1024);
+
+    search.apply_filter(
+      SearchFilter::EqualTo(3),
+      snapshot(&[(0x75a, 3), (0x10, 3)], &[]),
+    );
+    assert_eq!(
+      search.candidates(),
+      &[RamAddress::WorkRam(0x10), RamAddress::WorkRam(0x75a)]
+    );
+
+    search.apply_filter(
+      SearchFilter::Decreased,
+      snapshot(&[(0x75a, 2), (0x10, 4)], &[]),
+    );
+    assert_eq!(search.candidates(), &[RamAddress::WorkRam(0x75a)]);
+    assert_eq!(search.previous_value(RamAddress::WorkRam(0x75a)), Some(2));
+  }
+
+  #[test]
+  fn test_search_by_delta_wraps() {
+    let mut search = RamSearch::default();
+    search.reset(snapshot(&[(0x20, 0x00)], &[(0x30, 0x05)]), ValueType::U8);
+
+    search.apply_filter(
+      SearchFilter::ChangedBy(-1),
+      snapshot(&[(0x20, 0xff)], &[(0x30, 0x04)]),
+    );
+    assert_eq!(
+      search.candidates(),
+      &[RamAddress::WorkRam(0x20), RamAddress::PrgRam(0x30)]
+    );
+
+    search.reset(snapshot(&[(0x20, 0x7f)], &[]), ValueType::I8);
+    search.apply_filter(SearchFilter::Increased, snapshot(&[(0x20, 0x80)], &[]));
+    assert!(search.candidates().is_empty());
+
+    // changing the value type keeps the search going
+    search.reset(snapshot(&[], &[]), ValueType::U8);
+    search.apply_filter(SearchFilter::EqualTo(0), snapshot(&[(0x20, 0xff)], &[]));
+    search.set_value_type(ValueType::U16);
+    assert_eq!(search.candidates().len(), 2046 + 8191);
+    assert_eq!(
+      search.previous_value(RamAddress::WorkRam(0x1f)),
+      Some(0xff00)
+    );
+  }
+
+  fn ppu_snapshot() -> PPUSnapshot {
+    let mut snapshot = PPUSnapshot {
+      pattern_tables: vec![0; 0x2000],
+      name_tables: vec![0; 0x1000],
+      palette_ram: [0; 32],
+      oam: [PPUOAMEntry::new(); 64],
+      control: PPUControlRegister::from(0),
+      scroll_x: 0,
+      scroll_y: 0,
+    };
+
+    // tile 1 in the left pattern table: color 1 in the top-left pixel, color 3 in the top-right
+    snapshot.pattern_tables[0x10] = 0b1000_0001;
+    snapshot.pattern_tables[0x18] = 0b0000_0001;
+    snapshot.palette_ram = std::array::from_fn(|index| index as u8);
+
+    snapshot
+  }
+
+  fn pixel(image: &RgbaImage, x: u32, y: u32) -> [u8; 3] {
+    let offset = ((x + y * image.width) * 4) as usize;
+    [
+      image.data[offset],
+      image.data[offset + 1],
+      image.data[offset + 2],
+    ]
+  }
+
+  #[test]
+  fn test_render_pattern_table() {
+    let snapshot = ppu_snapshot();
+    assert_eq!(snapshot.tile_pixel(0, 1, 0, 0), 1);
+    assert_eq!(snapshot.tile_pixel(0, 1, 7, 0), 3);
+
+    let image = snapshot.render_pattern_table(0, 2);
+    assert_eq!(pixel(&image, 8, 0), PALETTE[9]);
+    assert_eq!(pixel(&image, 15, 0), PALETTE[11]);
+    assert_eq!(pixel(&image, 9, 0), PALETTE[0]);
+  }
+
+  #[test]
+  fn test_render_name_tables_and_sprites() {
+    let mut snapshot = ppu_snapshot();
+    // top-left tile of the second nametable uses tile 1 with palette 3
+    snapshot.name_tables[0x400] = 1;
+    snapshot.name_tables[0x400 + 0x3c0] = 0b0000_0011;
+    snapshot.scroll_x = 300;
+
+    let image = snapshot.render_name_tables();
+    assert_eq!(pixel(&image, 256, 0), PALETTE[13]);
+    assert_eq!(pixel(&image, 263, 0), PALETTE[15]);
+    // the scroll window wraps around to the left nametables
+    assert_eq!(pixel(&image, 300, 100), [0xff, 0x00, 0xff]);
+    assert_eq!(pixel(&image, 43, 100), [0xff, 0x00, 0xff]);
+    assert_eq!(pixel(&image, 0, 0), [0xff, 0x00, 0xff]);
+
+    snapshot.oam[0] = PPUOAMEntry::new()
+      .with_tile_id(1)
+      .with_flip_horizontal(true);
+    let sprite = snapshot.render_sprite(0);
+    assert_eq!(pixel(&sprite, 0, 0), PALETTE[19]);
+    assert_eq!(sprite.data[4 * 3 + 3], 0);
+  }
+
+  #[test]
+  fn test_memory_spaces() {
+    let rom_data = include_bytes!("../../smoketest/1.frame_basics.nes");
+    let rom = INESRom::from_reader(&mut BufReader::new(&rom_data[..])).unwrap();
+    let mut cartridge = Cartridge::from_ines_rom(rom.clone());
+    cartridge
+      .ppu_cpu_bus_mut()
+      .status_mut()
+      .set_vertical_blank(true);
+
+    let page = MemoryPage::capture(&cartridge, MemorySpace::CPU, 0x2000);
+    assert_eq!(page.bytes.len(), MEMORY_PAGE_SIZE);
+    assert_eq!(page.bytes[2].map(|value| value & 0x80), Some(0x80));
+    // reading PPUSTATUS through the viewer must not clear the vblank flag
+    assert!(cartridge.ppu_cpu_bus_mut().status_mut().vertical_blank());
+
+    MemorySpace::OAM.poke(&mut cartridge, 5, 0x42);
+    assert_eq!(cartridge.ppu_cpu_bus_mut().oam_mut()[1].tile_id(), 0x42);
+    assert_eq!(MemorySpace::OAM.read(&cartridge, 5), Some(0x42));
+
+    MemorySpace::CPU.poke(&mut cartridge, 0x0801, 0x12);
+    assert_eq!(MemorySpace::CPU.read(&cartridge, 0x0001), Some(0x12));
+
+    assert_eq!(
+      MemorySpace::PRGROM.read(&cartridge, 0),
+      Some(rom.prg_data[0])
+    );
+    // pokes go to the memory behind the address instead of through the bus
+    MemorySpace::CPU.poke(&mut cartridge, 0x8000, 0x34);
+    assert_eq!(MemorySpace::PRGROM.read(&cartridge, 0), Some(0x34));
+    // the PRG ROM space is the 16K image, which the CPU sees mirrored
+    assert_eq!(MemorySpace::PRGROM.len(&cartridge), 0x4000);
+    assert_eq!(MemorySpace::CPU.read(&cartridge, 0xc000), Some(0x34));
+    assert!(!MemorySpace::CPU.can_poke(0x2000));
+
+    let page = MemoryPage::capture(&cartridge, MemorySpace::OAM, 0x80);
+    assert_eq!(page.bytes.len(), 0x80);
+  }
+
+  #[test]
+  fn test_cdl_flags_fold_mirrored_prg() {
+    let mut logger = CodeDataLogger::new();
+    logger.set_enabled(true, 0x8000);
+
+    logger.begin_opcode_fetch();
+    logger.log_prg_read(0x0010, 0x8010);
+    logger.set_access_kind(CPUAccessKind::Operand);
+    logger.log_prg_read(0x4011, 0xC011);
+    logger.set_access_kind(CPUAccessKind::IndirectData);
+    logger.log_prg_read(0x0020, 0x8020);
+
+    // 16K of PRG ROM mirrored into 32K of mapper storage
+    let cdl = CDLFile::build(&logger, &CHRDataLogger::new(), 0x4000, 0);
+    assert_eq!(cdl.prg.len(), 0x4000);
+    assert!(cdl.chr.is_empty());
+    assert_eq!(cdl.prg[0x0010], 0x01);
+    assert_eq!(cdl.prg[0x0011], 0x01 | (2 << 2));
+    assert_eq!(cdl.prg[0x0020], 0x02 | 0x20);
+    assert_eq!(cdl.prg_code_bytes(), 2);
+    assert_eq!(cdl.prg_data_bytes(), 1);
+  }
+
+  #[test]
+  fn test_cdl_logs_running_rom() {
+    let rom_data = include_bytes!("../../smoketest/1.frame_basics.nes");
+    let rom = INESRom::from_reader(&mut BufReader::new(&rom_data[..])).unwrap();
+    let (sender, _receiver) = smol::channel::unbounded();
+    let mut machine = NES::from_rom(rom, sender);
+    machine.state.cartridge.set_code_data_logging(true);
+    machine.reset();
+
+    let mut pixbuf = Pixbuf::new();
+    for _ in 0..5 {
+      machine.execute_frame(&mut pixbuf);
+    }
+
+    let cdl = machine.code_data_log();
+    assert_eq!(cdl.prg.len(), machine.prg_rom_size);
+    assert_eq!(cdl.chr.len(), machine.chr_rom_size);
+
+    let cartridge = &machine.state.cartridge;
+    let reset_vector = u16::from_le_bytes([
+      cartridge.cpu_bus().read_readonly(0xFFFC),
+      cartridge.cpu_bus().read_readonly(0xFFFD),
+    ]);
+    let prg_offset = |addr: u16| (addr as usize - 0x8000) % machine.prg_rom_size;
+    assert_ne!(cdl.prg[prg_offset(reset_vector)] & 0x01, 0);
+    assert_ne!(cdl.prg[prg_offset(0xFFFC)] & 0x02, 0);
+    assert_ne!(cdl.prg[prg_offset(0xFFFD)] & 0x02, 0);
+  }
+
+  #[test]
+  fn test_trace_logger_ring_buffer_and_filters() {
+    let rom_data = include_bytes!("../../smoketest/1.frame_basics.nes");
+    let rom = INESRom::from_reader(&mut BufReader::new(&rom_data[..])).unwrap();
+    let (sender, _receiver) = smol::channel::unbounded();
+    let mut machine = NES::from_rom(rom, sender);
+    machine.trace_logger.set_capacity(50);
+    machine.trace_logger.start();
+
+    let mut pixbuf = Pixbuf::new();
+    for _ in 0..2 {
+      machine.execute_frame(&mut pixbuf);
+    }
+    let lines = machine.trace_logger.recent_lines(100);
+    assert_eq!(lines.len(), 50);
+    assert_eq!(machine.trace_logger.recent_lines(10), lines[40..]);
+
+    machine.trace_logger.clear();
+    machine.trace_logger.set_filter(TraceFilter {
+      pc_range: Some(0xe000..=0xffff),
+      bank: Some(0),
+      interrupt: InterruptFilter::Outside,
+    });
+    machine.execute_frame(&mut pixbuf);
+    let lines = machine.trace_logger.recent_lines(50);
+    assert!(!lines.is_empty());
+    for line in lines {
+      let pc = u16::from_str_radix(&line[0..4], 16).unwrap();
+      assert!(pc >= 0xe000, "{}", line);
+    }
+
+    machine.trace_logger.stop();
+    machine.trace_logger.clear();
+    machine.execute_frame(&mut pixbuf);
+    assert!(machine.trace_logger.recent_lines(50).is_empty());
+  }
+
+  #[test]
+  fn test_prg_disassembler() {
+    let mut prg_data = vec![0; 3 * 16 * 1024];
+    // switchable bank 0 at $8000: LDA $C010,X / RTS
+    prg_data[0..4].copy_from_slice(&[0xbd, 0x10, 0xc0, 0x60]);
+    // fixed bank at $C000: SEI / JSR $8000 / JMP $C000 ... RTI at $C00F, data at $C010
+    let fixed = 2 * 16 * 1024;
+    prg_data[fixed..fixed + 7].copy_from_slice(&[0x78, 0x20, 0x00, 0x80, 0x4c, 0x00, 0xc0]);
+    prg_data[fixed + 0x0f..fixed + 0x13].copy_from_slice(&[0x40, 0x01, 0x02, 0x03]);
+    prg_data[fixed + 0x3ffa..].copy_from_slice(&[0x0f, 0xc0, 0x00, 0xc0, 0x0f, 0xc0]);
+    let rom = INESRom {
+      prg_data,
+      chr_data: vec![],
+      trainer_data: None,
+      has_battery_ram: false,
+      vertical_mirroring: true,
+      mapper_id: 2,
+      playchoice_10: false,
+      vs_unisystem: false,
+      uses_chr_ram: true,
+    };
+
+    let source = PRGDisassembler::new(&rom).disassemble(None);
+    assert!(source.contains("reset:\n  SEI\n  JSR $8000\n  JMP reset\n"));
+    assert!(source.contains("nmi:\n  RTI\n  .byte $01, $02, $03"));
+    assert!(source.contains("  .word nmi, reset, nmi\n"));
+    assert!(source.contains(".segment \"BANK00\"\n  .byte $BD, $10, $C0, $60"));
+    assert!(!source.contains(".segment \"CHARS\""));
+
+    let mut cdl = vec![0; rom.prg_data.len()];
+    cdl[0..4].fill(0x01);
+    let source = PRGDisassembler::new(&rom).disassemble(Some(&cdl));
+    assert!(source.contains(".segment \"BANK00\"\n  LDA L02_C010,X\n  RTS\n"));
+    assert!(source.contains("nmi:\n  RTI\nL02_C010:\n  .byte $01, $02, $03"));
+
+    let empty_rom = INESRom {
+      prg_data: vec![],
+      ..rom
+    };
+    PRGDisassembler::new(&empty_rom).disassemble(None);
+  }
+
+  #[test]
+  fn test_symbol_files() {
+    let dbg = "version\tmajor=2,minor=0\n\
+      seg\tid=0,name=\"HEADER\",start=0x000000,size=0x0010,addrsize=absolute,type=ro,oname=\"game.nes\",ooffs=0\n\
+      seg\tid=1,name=\"CODE\",start=0x00C000,size=0x0100,addrsize=absolute,type=ro,oname=\"game.nes\",ooffs=16400\n\
+      seg\tid=2,name=\"BSS\",start=0x000300,size=0x0010,addrsize=absolute,type=rw\n\
+      sym\tid=0,name=\"@loop\",addrsize=absolute,val=0xC010,seg=1,type=lab\n\
+      sym\tid=1,name=\"wait\",addrsize=absolute,val=0xC010,seg=1,type=lab\n\
+      sym\tid=2,name=\"player_x\",addrsize=absolute,val=0x300,seg=2,type=lab\n\
+      sym\tid=3,name=\"SPEED\",addrsize=zeropage,val=0x3,type=equ\n";
+    let symbols = SymbolTable::parse_dbg(dbg).unwrap();
+    assert_eq!(symbols.len(), 3);
+    assert_eq!(symbols.lookup("wait"), Some(SymbolAddress::PRGROM(0x4010)));
+    assert_eq!(symbols.label(0xC010, Some(0x4010)), Some("wait"));
+    assert_eq!(symbols.label(0xC010, Some(0x0010)), None);
+    assert_eq!(symbols.label(0x0300, None), Some("player_x"));
+    assert!(SymbolTable::parse_dbg("$0300#player_x#").is_err());
+
+    let symbols = SymbolTable::parse_nl("$C010#wait#spins\n$0300/10#enemies#\n", Some(1));
+    assert_eq!(symbols.lookup("wait"), Some(SymbolAddress::PRGROM(0x4010)));
+    assert_eq!(symbols.lookup("enemies"), Some(SymbolAddress::CPU(0x0300)));
+
+    let symbols =
+      SymbolTable::parse_mlb("P:4010:wait:spins\nR:0300-0302:player_x\nNesSaveRam:0010:score\n");
+    assert_eq!(symbols.lookup("wait"), Some(SymbolAddress::PRGROM(0x4010)));
+    assert_eq!(symbols.label(0x0300, None), Some("player_x"));
+    assert_eq!(symbols.lookup("score"), Some(SymbolAddress::CPU(0x6010)));
+
+    let breakpoint = Breakpoint::parse("wait", &symbols).unwrap();
+    assert!(breakpoint.matches(0x8010, Some(0x4010)));
+    assert_eq!(
+      Breakpoint::parse("$C000", &symbols).unwrap().address,
+      SymbolAddress::CPU(0xC000)
+    );
+    assert!(Breakpoint::parse("nowhere", &symbols).is_err());
+  }
+
+  #[test]
+  fn test_breakpoints_and_labelled_trace() {
+    let rom_data = include_bytes!("../../smoketest/1.frame_basics.nes");
+    let rom = INESRom::from_reader(&mut BufReader::new(&rom_data[..])).unwrap();
+    let (sender, _receiver) = smol::channel::unbounded();
+    let mut machine = NES::from_rom(rom, sender);
+    // the 16K ROM is mirrored, so its routine at $E0B8 is at offset $20B8
+    machine.symbols = SymbolTable::parse_mlb("P:20B8:init\nR:00F8:test_state\n");
+    machine.breakpoints = vec![Breakpoint::parse("init", &machine.symbols).unwrap()];
+
+    let mut pixbuf = Pixbuf::new();
+    assert!((0..5).any(|_| machine.execute_frame(&mut pixbuf)));
+    assert_eq!(machine.state.cpu.pc, 0xE0B8);
+
+    machine.breakpoints.clear();
+    machine.trace_logger.set_capacity(usize::MAX);
+    machine.trace_logger.start();
+    assert!(!machine.execute_frame(&mut pixbuf));
+    let lines = machine.trace_logger.recent_lines(usize::MAX);
+    assert!(lines[0].starts_with("E0B8"), "{}", lines[0]);
+    assert!(lines[1].contains("STA test_state = "), "{}", lines[1]);
+  }
+
+  #[test]
+  fn test_source_map_and_line_stepping() {
+    // a C function at $E0B8 in frame_basics, compiled to two lines of assembly each
+    let dbg = "version\tmajor=2,minor=0\n\
+      file\tid=0,name=\"main.c\",size=100,mtime=0x0,mod=0\n\
+      file\tid=1,name=\"main.s\",size=100,mtime=0x0,mod=0\n\
+      seg\tid=0,name=\"HEADER\",start=0x000000,size=0x0010,addrsize=absolute,type=ro,oname=\"game.nes\",ooffs=0\n\
+      seg\tid=1,name=\"CODE\",start=0x00C000,size=0x4000,addrsize=absolute,type=ro,oname=\"game.nes\",ooffs=16\n\
+      span\tid=0,seg=1,start=8376,size=4\n\
+      span\tid=1,seg=1,start=8376,size=2\n\
+      span\tid=2,seg=1,start=8378,size=2\n\
+      span\tid=3,seg=1,start=8380,size=5\n\
+      line\tid=0,file=0,line=10,type=1,span=0\n\
+      line\tid=1,file=1,line=100,span=1\n\
+      line\tid=2,file=1,line=101,span=2\n\
+      line\tid=3,file=0,line=11,type=1,span=3\n";
+    let source_map = SourceMap::parse_dbg(dbg, Path::new("/src")).unwrap();
+    assert_eq!(source_map.files()[0].path, Path::new("/src/main.c"));
+    let c_line = |line| Some(SourceLocation { file: 0, line });
+    assert_eq!(source_map.location(0xE0BA, Some(0x20BA)), c_line(10));
+    assert_eq!(source_map.location(0xE0BC, Some(0x20BC)), c_line(11));
+    assert_eq!(source_map.location(0xE0BA, Some(0x00BA)), None);
+
+    let rom_data = include_bytes!("../../smoketest/1.frame_basics.nes");
+    let rom = INESRom::from_reader(&mut BufReader::new(&rom_data[..])).unwrap();
+    let (sender, _receiver) = smol::channel::unbounded();
+    let mut machine = NES::from_rom(rom, sender);
+    machine.source_map = source_map;
+    machine.breakpoints = vec![Breakpoint::parse("$E0B8", &SymbolTable::default()).unwrap()];
+
+    let mut pixbuf = Pixbuf::new();
+    assert!((0..5).any(|_| machine.execute_frame(&mut pixbuf)));
+    assert_eq!(machine.source_location(machine.state.cpu.pc), c_line(10));
+
+    machine.breakpoints.clear();
+    machine.start_source_step();
+    assert!(machine.execute_frame(&mut pixbuf));
+    assert_eq!(machine.state.cpu.pc, 0xE0BC);
+    assert_eq!(machine.source_location(machine.state.cpu.pc), c_line(11));
+  }
+}
@@ -0,0 +1,231 @@
+use crate::{
+  cartridge::Cartridge,
+  ppu::{PPUControlRegister, PPUOAMEntry, PALETTE},
+};
+
+const SCROLL_OVERLAY_COLOR: [u8; 3] = [0xff, 0x00, 0xff];
+
+#[derive(Debug, Clone)]
+pub struct RgbaImage {
+  pub width: u32,
+  pub height: u32,
+  pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+  pub fn new(width: u32, height: u32) -> Self {
+    Self {
+      width,
+      height,
+      data: vec![0; (width * height * 4) as usize],
+    }
+  }
+
+  pub fn set_pixel(&mut self, x: u32, y: u32, color: [u8; 3]) {
+    let offset = ((x + y * self.width) * 4) as usize;
+    self.data[offset..offset + 4].copy_from_slice(&[color[0], color[1], color[2], 255]);
+  }
+
+  // nearest-neighbor, so tiles stay crisp when the GUI draws them bigger
+  pub fn scaled(&self, factor: u32) -> Self {
+    let mut scaled = Self::new(self.width * factor, self.height * factor);
+
+    for y in 0..scaled.height {
+      for x in 0..scaled.width {
+        let source_offset = (((x / factor) + (y / factor) * self.width) * 4) as usize;
+        let offset = ((x + y * scaled.width) * 4) as usize;
+        scaled.data[offset..offset + 4]
+          .copy_from_slice(&self.data[source_offset..source_offset + 4]);
+      }
+    }
+
+    scaled
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct PPUSnapshot {
+  // $0000-$1FFF as seen through the mapper
+  pub pattern_tables: Vec<u8>,
+  // $2000-$2FFF with the cartridge's mirroring applied
+  pub name_tables: Vec<u8>,
+  pub palette_ram: [u8; 32],
+  pub oam: [PPUOAMEntry; 64],
+  pub control: PPUControlRegister,
+  pub scroll_x: u16,
+  pub scroll_y: u16,
+}
+
+impl PPUSnapshot {
+  pub fn capture(cartridge: &Cartridge) -> Self {
+    let ppu_cpu_bus = cartridge.ppu_cpu_bus();
+    let ppu_memory = ppu_cpu_bus.ppu_memory();
+    let tram_addr = ppu_cpu_bus.tram_addr();
+
+    Self {
+      pattern_tables: (0x0000..0x2000)
+        .map(|addr| ppu_memory.read_readonly(addr))
+        .collect(),
+      name_tables: (0x2000..0x3000)
+        .map(|addr| ppu_memory.read_readonly(addr))
+        .collect(),
+      palette_ram: *ppu_memory.palette_ram(),
+      oam: *ppu_cpu_bus.oam(),
+      control: ppu_cpu_bus.control(),
+      scroll_x: (u16::from(tram_addr.nametable_x()) * 256)
+        + (u16::from(tram_addr.coarse_x()) * 8)
+        + u16::from(ppu_cpu_bus.fine_x()),
+      scroll_y: (u16::from(tram_addr.nametable_y()) * 240)
+        + (u16::from(tram_addr.coarse_y()) * 8)
+        + u16::from(tram_addr.fine_y()),
+    }
+  }
+
+  /// Palettes 0-3 are background palettes and 4-7 are sprite palettes.
+  pub fn palette_color(&self, palette: u8, color: u8) -> [u8; 3] {
+    let palette_ram_index = if color == 0 {
+      0
+    } else {
+      usize::from(palette) * 4 + usize::from(color)
+    };
+
+    PALETTE[usize::from(self.palette_ram[palette_ram_index] & 0x3f)]
+  }
+
+  pub fn tile_pixel(&self, pattern_table: u8, tile_id: u8, x: u8, y: u8) -> u8 {
+    let addr = usize::from(pattern_table) * 0x1000 + usize::from(tile_id) * 16 + usize::from(y);
+    let low = (self.pattern_tables[addr] >> (7 - x)) & 1;
+    let high = (self.pattern_tables[addr + 8] >> (7 - x)) & 1;
+    (high << 1) | low
+  }
+
+  pub fn render_pattern_table(&self, pattern_table: u8, palette: u8) -> RgbaImage {
+    let mut image = RgbaImage::new(128, 128);
+
+    for tile_id in 0..=255_u8 {
+      let tile_x = u32::from(tile_id % 16) * 8;
+      let tile_y = u32::from(tile_id / 16) * 8;
+
+      for y in 0..8 {
+        for x in 0..8 {
+          let color = self.palette_color(palette, self.tile_pixel(pattern_table, tile_id, x, y));
+          image.set_pixel(tile_x + u32::from(x), tile_y + u32::from(y), color);
+        }
+      }
+    }
+
+    image
+  }
+
+  /// Renders all four logical nametables in a 2x2 grid, with the current scroll window outlined.
+  pub fn render_name_tables(&self) -> RgbaImage {
+    let mut image = RgbaImage::new(512, 480);
+    let pattern_table = u8::from(self.control.pattern_background());
+
+    for name_table in 0..4_usize {
+      let name_table_data = &self.name_tables[name_table * 1024..(name_table + 1) * 1024];
+      let origin_x = (name_table as u32 % 2) * 256;
+      let origin_y = (name_table as u32 / 2) * 240;
+
+      for row in 0..30_usize {
+        for col in 0..32_usize {
+          let tile_id = name_table_data[row * 32 + col];
+          let attribute = name_table_data[0x3c0 + (row / 4) * 8 + col / 4];
+          let attribute_shift = ((row % 4) / 2) * 4 + ((col % 4) / 2) * 2;
+          let palette = (attribute >> attribute_shift) & 0x03;
+
+          for y in 0..8 {
+            for x in 0..8 {
+              let color =
+                self.palette_color(palette, self.tile_pixel(pattern_table, tile_id, x, y));
+              image.set_pixel(
+                origin_x + (col as u32) * 8 + u32::from(x),
+                origin_y + (row as u32) * 8 + u32::from(y),
+                color,
+              );
+            }
+          }
+        }
+      }
+    }
+
+    let scroll_x = u32::from(self.scroll_x);
+    let scroll_y = u32::from(self.scroll_y);
+    for x in 0..256 {
+      image.set_pixel((scroll_x + x) % 512, scroll_y % 480, SCROLL_OVERLAY_COLOR);
+      image.set_pixel(
+        (scroll_x + x) % 512,
+        (scroll_y + 239) % 480,
+        SCROLL_OVERLAY_COLOR,
+      );
+    }
+    for y in 0..240 {
+      image.set_pixel(scroll_x % 512, (scroll_y + y) % 480, SCROLL_OVERLAY_COLOR);
+      image.set_pixel(
+        (scroll_x + 255) % 512,
+        (scroll_y + y) % 480,
+        SCROLL_OVERLAY_COLOR,
+      );
+    }
+
+    image
+  }
+
+  /// Renders each palette RAM entry as a 16x16 swatch, in two rows of 16.
+  pub fn render_palette_ram(&self) -> RgbaImage {
+    let mut image = RgbaImage::new(256, 32);
+
+    for (index, value) in self.palette_ram.iter().enumerate() {
+      let color = PALETTE[usize::from(value & 0x3f)];
+      let swatch_x = (index as u32 % 16) * 16;
+      let swatch_y = (index as u32 / 16) * 16;
+
+      for y in 0..16 {
+        for x in 0..16 {
+          image.set_pixel(swatch_x + x, swatch_y + y, color);
+        }
+      }
+    }
+
+    image
+  }
+
+  /// Renders a sprite as it'd appear on screen, leaving transparent pixels transparent.
+  pub fn render_sprite(&self, oam_index: usize) -> RgbaImage {
+    let entry = self.oam[oam_index];
+    let height = self.control.sprite_height();
+    let mut image = RgbaImage::new(8, u32::from(height));
+
+    for y in 0..height {
+      let sprite_y = if entry.flip_vertical() {
+        height - 1 - y
+      } else {
+        y
+      };
+
+      let (pattern_table, tile_id) = if height == 16 {
+        (
+          entry.tile_id() & 0x01,
+          (entry.tile_id() & 0xfe) + (sprite_y / 8),
+        )
+      } else {
+        (u8::from(self.control.pattern_sprite()), entry.tile_id())
+      };
+
+      for x in 0..8 {
+        let sprite_x = if entry.flip_horizontal() { 7 - x } else { x };
+        let pixel = self.tile_pixel(pattern_table, tile_id, sprite_x, sprite_y % 8);
+
+        if pixel != 0 {
+          image.set_pixel(
+            u32::from(x),
+            u32::from(y),
+            self.palette_color(entry.palette_index() + 4, pixel),
+          );
+        }
+      }
+    }
+
+    image
+  }
+}
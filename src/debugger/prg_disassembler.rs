@@ -0,0 +1,511 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  fmt::Write,
+};
+
+use crate::{
+  cpu::{DisassembledOperand, Instruction, Operand},
+  nes::INESRom,
+};
+
+const BANK_SIZE: usize = 16 * 1024;
+const BYTES_PER_DATA_LINE: usize = 16;
+const VECTORS_ADDR: u16 = 0xfffa;
+// CDL flags, see code_data_logger.rs
+const CDL_CODE: u8 = 0x01;
+const CDL_DATA: u8 = 0x02;
+
+/// Where a chunk of PRG ROM sits in the CPU address space. ROMs up to 32KiB are treated as a single
+/// bank (16KiB ones mirrored into $8000-$BFFF); larger ones as 16KiB banks switched in at $8000
+/// with the last bank fixed at $C000, the layout UxROM and MMC1 boot with.
+#[derive(Debug, Clone, Copy)]
+struct BankLayout {
+  offset: usize,
+  len: usize,
+  base_addr: u16,
+  fixed: bool,
+}
+
+impl BankLayout {
+  fn for_prg_rom(prg_len: usize) -> Vec<Self> {
+    if prg_len <= 2 * BANK_SIZE {
+      return vec![Self {
+        offset: 0,
+        len: prg_len,
+        base_addr: (0x10000 - prg_len) as u16,
+        fixed: true,
+      }];
+    }
+
+    let bank_count = prg_len / BANK_SIZE;
+    (0..bank_count)
+      .map(|index| {
+        let fixed = index == bank_count - 1;
+        Self {
+          offset: index * BANK_SIZE,
+          len: BANK_SIZE,
+          base_addr: if fixed { 0xc000 } else { 0x8000 },
+          fixed,
+        }
+      })
+      .collect()
+  }
+
+  fn contains_addr(&self, addr: u16) -> bool {
+    addr >= self.base_addr && usize::from(addr - self.base_addr) < self.len
+  }
+
+  fn contains_offset(&self, offset: usize) -> bool {
+    offset >= self.offset && offset < self.offset + self.len
+  }
+
+  fn addr(&self, offset: usize) -> u16 {
+    self.base_addr + (offset - self.offset) as u16
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteKind {
+  Unknown,
+  Data,
+  Opcode,
+  Operand,
+}
+
+/// Statically disassembles PRG ROM into a ca65 source file by following control flow from the
+/// interrupt vectors (and optionally a CDL file's code bytes).
+pub struct PRGDisassembler<'a> {
+  rom: &'a INESRom,
+  banks: Vec<BankLayout>,
+  kinds: Vec<ByteKind>,
+  instructions: HashMap<usize, (Instruction, u8)>,
+  // (bank the reference was made from, referenced address)
+  references: Vec<(usize, u16)>,
+  labels: BTreeMap<usize, String>,
+}
+
+impl<'a> PRGDisassembler<'a> {
+  pub fn new(rom: &'a INESRom) -> Self {
+    Self {
+      rom,
+      banks: BankLayout::for_prg_rom(rom.prg_data.len()),
+      kinds: vec![ByteKind::Unknown; rom.prg_data.len()],
+      instructions: HashMap::new(),
+      references: Vec::new(),
+      labels: BTreeMap::new(),
+    }
+  }
+
+  /// Seeds the disassembly from a .cdl file: bytes logged only as data are never treated as code,
+  /// and each run of logged code is followed from its first byte.
+  fn seed_from_cdl(&mut self, cdl: &[u8]) -> Vec<usize> {
+    let prg_flags = &cdl[..cdl.len().min(self.rom.prg_data.len())];
+    let mut entry_points = Vec::new();
+
+    for (offset, flags) in prg_flags.iter().enumerate() {
+      if flags & CDL_CODE != 0 {
+        if offset == 0 || prg_flags[offset - 1] & CDL_CODE == 0 {
+          entry_points.push(offset);
+        }
+      } else if flags & CDL_DATA != 0 {
+        self.kinds[offset] = ByteKind::Data;
+      }
+    }
+
+    entry_points
+  }
+
+  fn bank_index_for_offset(&self, offset: usize) -> Option<usize> {
+    self
+      .banks
+      .iter()
+      .position(|bank| bank.contains_offset(offset))
+  }
+
+  /// Finds the PRG ROM offset an address refers to from code in `from_bank`: either the bank itself
+  /// or the fixed bank. The flag is false for mirrored addresses that can't be given a label.
+  fn resolve(&self, from_bank: usize, addr: u16) -> Option<(usize, bool)> {
+    let bank = &self.banks[from_bank];
+    let candidates = std::iter::once(bank).chain(self.banks.iter().filter(|bank| bank.fixed));
+    for bank in candidates {
+      if bank.contains_addr(addr) {
+        return Some((bank.offset + usize::from(addr - bank.base_addr), true));
+      }
+
+      // small NROM images are mirrored across the whole $8000-$FFFF range (unless there's no PRG
+      // ROM to mirror)
+      if self.banks.len() == 1 && addr >= 0x8000 {
+        return usize::from(addr - 0x8000)
+          .checked_rem(bank.len)
+          .map(|offset| (offset, false));
+      }
+    }
+
+    None
+  }
+
+  fn vector_target(&self, vector_addr: u16) -> Option<u16> {
+    let fixed_bank = self.banks.len() - 1;
+    let (offset, _) = self.resolve(fixed_bank, vector_addr)?;
+    let prg = &self.rom.prg_data;
+    Some(u16::from_le_bytes([
+      *prg.get(offset)?,
+      *prg.get(offset + 1)?,
+    ]))
+  }
+
+  fn decode(&self, offset: usize, bank: &BankLayout) -> Option<(Instruction, u8)> {
+    let prg = &self.rom.prg_data;
+    let bank_end = bank.offset + bank.len;
+    let mut next = offset + 1;
+    let mut out_of_bank = false;
+    let instruction = Instruction::decode(prg[offset], || {
+      let byte = if next < bank_end {
+        prg[next]
+      } else {
+        out_of_bank = true;
+        0
+      };
+      next += 1;
+      byte
+    })?;
+
+    if out_of_bank {
+      return None;
+    }
+
+    Some((instruction, (next - offset) as u8))
+  }
+
+  fn trace(&mut self, mut queue: Vec<(usize, u16)>) {
+    while let Some((from_bank, addr)) = queue.pop() {
+      let Some((mut offset, _)) = self.resolve(from_bank, addr) else {
+        continue;
+      };
+      let Some(bank_index) = self.bank_index_for_offset(offset) else {
+        continue;
+      };
+      let bank = self.banks[bank_index];
+
+      while self.kinds[offset] == ByteKind::Unknown {
+        let Some((instruction, len)) = self.decode(offset, &bank) else {
+          break;
+        };
+        let len = usize::from(len);
+        if self.kinds[offset + 1..offset + len]
+          .iter()
+          .any(|kind| *kind != ByteKind::Unknown)
+        {
+          break;
+        }
+
+        self.kinds[offset] = ByteKind::Opcode;
+        self.kinds[offset + 1..offset + len].fill(ByteKind::Operand);
+
+        let next_pc = bank.addr(offset) + len as u16;
+        let target = instruction
+          .operand()
+          .and_then(|operand| operand.disassemble_static(next_pc).target_addr());
+        if let Some(target) = target {
+          self.references.push((bank_index, target));
+        }
+
+        let falls_through = match &instruction {
+          Instruction::JMP(Operand::Absolute(_)) => {
+            queue.extend(target.map(|target| (bank_index, target)));
+            false
+          }
+          Instruction::JMP(_) | Instruction::RTS | Instruction::RTI | Instruction::BRK => false,
+          Instruction::JSR(_)
+          | Instruction::BCC(_)
+          | Instruction::BCS(_)
+          | Instruction::BEQ(_)
+          | Instruction::BMI(_)
+          | Instruction::BNE(_)
+          | Instruction::BPL(_)
+          | Instruction::BVC(_)
+          | Instruction::BVS(_) => {
+            queue.extend(target.map(|target| (bank_index, target)));
+            true
+          }
+          _ => true,
+        };
+
+        self.instructions.insert(offset, (instruction, len as u8));
+        offset += len;
+        if !falls_through || !bank.contains_offset(offset) {
+          break;
+        }
+      }
+    }
+  }
+
+  fn label_name(&self, offset: usize) -> String {
+    let bank_index = self.bank_index_for_offset(offset).unwrap();
+    let addr = self.banks[bank_index].addr(offset);
+    if self.banks.len() == 1 {
+      format!("L{:04X}", addr)
+    } else {
+      format!("L{:02}_{:04X}", bank_index, addr)
+    }
+  }
+
+  // references into the middle of an instruction are labelled at the instruction's start
+  fn label_offset(&self, offset: usize) -> (usize, usize) {
+    let mut start = offset;
+    while self.kinds[start] == ByteKind::Operand && start > 0 {
+      start -= 1;
+    }
+    (start, offset - start)
+  }
+
+  fn assign_labels(&mut self) {
+    for (name, vector_addr) in [("nmi", 0xfffa), ("reset", 0xfffc), ("irq", 0xfffe)] {
+      let fixed_bank = self.banks.len() - 1;
+      let Some(target) = self.vector_target(vector_addr) else {
+        continue;
+      };
+      if let Some((offset, true)) = self.resolve(fixed_bank, target) {
+        let (start, _) = self.label_offset(offset);
+        self.labels.entry(start).or_insert_with(|| name.to_owned());
+      }
+    }
+
+    for (from_bank, addr) in self.references.clone() {
+      if let Some((offset, true)) = self.resolve(from_bank, addr) {
+        let (start, _) = self.label_offset(offset);
+        if !self.labels.contains_key(&start) {
+          self.labels.insert(start, self.label_name(start));
+        }
+      }
+    }
+  }
+
+  fn addr_expression(&self, from_bank: usize, addr: u16) -> Option<String> {
+    let (offset, true) = self.resolve(from_bank, addr)? else {
+      return None;
+    };
+    let (start, delta) = self.label_offset(offset);
+    let label = self.labels.get(&start)?;
+    Some(if delta == 0 {
+      label.clone()
+    } else {
+      format!("{}+{}", label, delta)
+    })
+  }
+
+  fn format_instruction(&self, bank_index: usize, offset: usize) -> String {
+    let (instruction, len) = &self.instructions[&offset];
+    let bytes = &self.rom.prg_data[offset..offset + usize::from(*len)];
+
+    // illegal opcodes don't have consistent ca65 mnemonics, so keep their bytes exact
+    if let Instruction::Illegal(inner, _) = instruction {
+      return format!(
+        "  {} ; {}",
+        data_directive(bytes),
+        <&'static str>::from(inner.as_ref())
+      );
+    }
+
+    let mnemonic: &'static str = instruction.into();
+    let next_pc = self.banks[bank_index].addr(offset) + u16::from(*len);
+    let Some(operand) = instruction
+      .operand()
+      .map(|operand| operand.disassemble_static(next_pc))
+    else {
+      return format!("  {}", mnemonic);
+    };
+
+    let target = operand
+      .target_addr()
+      .and_then(|addr| Some((addr, self.addr_expression(bank_index, addr)?)));
+    let operand_text = match (&operand, target) {
+      (DisassembledOperand::Absolute { .. }, Some((_, label))) => label,
+      (DisassembledOperand::AbsoluteX { .. }, Some((_, label))) => format!("{},X", label),
+      (DisassembledOperand::AbsoluteY { .. }, Some((_, label))) => format!("{},Y", label),
+      (DisassembledOperand::Indirect { .. }, Some((_, label))) => format!("({})", label),
+      (DisassembledOperand::Relative { .. }, Some((_, label))) => label,
+      // keep absolute addressing for addresses ca65 would otherwise assemble as zero page
+      (
+        DisassembledOperand::Absolute { addr, .. }
+        | DisassembledOperand::AbsoluteX {
+          base_addr: addr, ..
+        }
+        | DisassembledOperand::AbsoluteY {
+          base_addr: addr, ..
+        },
+        None,
+      ) if *addr < 0x100 => format!("a:{}", operand),
+      _ => operand.to_string(),
+    };
+
+    format!("  {} {}", mnemonic, operand_text)
+  }
+
+  fn vectors_line(&self, bank_index: usize, offset: usize) -> Option<String> {
+    let bank = &self.banks[bank_index];
+    if !bank.fixed || bank.addr(offset) != VECTORS_ADDR || offset + 6 > bank.offset + bank.len {
+      return None;
+    }
+    // the vectors can only be written as words when nothing else points into them
+    if (offset..offset + 6)
+      .any(|offset| self.kinds[offset] != ByteKind::Data && self.kinds[offset] != ByteKind::Unknown)
+      || (offset + 1..offset + 6).any(|offset| self.labels.contains_key(&offset))
+    {
+      return None;
+    }
+
+    let targets = [0xfffa, 0xfffc, 0xfffe]
+      .into_iter()
+      .map(|vector_addr| {
+        let target = self.vector_target(vector_addr)?;
+        Some(
+          self
+            .addr_expression(bank_index, target)
+            .unwrap_or_else(|| format!("${:04X}", target)),
+        )
+      })
+      .collect::<Option<Vec<_>>>()?;
+
+    Some(format!("  .word {}", targets.join(", ")))
+  }
+
+  fn linker_config(&self) -> String {
+    let mut memory = String::from("  HEADER: start = $0000, size = $0010, fill = yes;\n");
+    let mut segments = String::from("  HEADER: load = HEADER, type = ro;\n");
+    if self.rom.trainer_data.is_some() {
+      memory.push_str("  TRAINER: start = $7000, size = $0200, fill = yes;\n");
+      segments.push_str("  TRAINER: load = TRAINER, type = ro;\n");
+    }
+    for (index, bank) in self.banks.iter().enumerate() {
+      let _ = writeln!(
+        memory,
+        "  PRG{:02}: start = ${:04X}, size = ${:04X}, fill = yes;",
+        index, bank.base_addr, bank.len
+      );
+      let _ = writeln!(
+        segments,
+        "  BANK{:02}: load = PRG{:02}, type = ro;",
+        index, index
+      );
+    }
+    if !self.rom.chr_data.is_empty() {
+      let _ = writeln!(
+        memory,
+        "  CHR: start = $0000, size = ${:04X}, fill = yes;",
+        self.rom.chr_data.len()
+      );
+      segments.push_str("  CHARS: load = CHR, type = ro;\n");
+    }
+
+    format!("MEMORY {{\n{}}}\nSEGMENTS {{\n{}}}\n", memory, segments)
+  }
+
+  /// Runs the disassembly and returns the ca65 source.
+  pub fn disassemble(mut self, cdl: Option<&[u8]>) -> String {
+    let entry_points = cdl.map(|cdl| self.seed_from_cdl(cdl)).unwrap_or_default();
+
+    let fixed_bank = self.banks.len() - 1;
+    let mut queue = entry_points
+      .into_iter()
+      .map(|offset| {
+        let bank_index = self.bank_index_for_offset(offset).unwrap();
+        (bank_index, self.banks[bank_index].addr(offset))
+      })
+      .collect::<Vec<_>>();
+    // vectors go last so they're traced first
+    queue.extend(
+      [0xfffa, 0xfffc, 0xfffe]
+        .into_iter()
+        .filter_map(|vector_addr| self.vector_target(vector_addr))
+        .map(|target| (fixed_bank, target)),
+    );
+    self.trace(queue);
+
+    self.assign_labels();
+    self.write_source()
+  }
+
+  fn write_source(&self) -> String {
+    let mut source = String::new();
+    let _ = writeln!(source, "; ld65 configuration for reassembling this file:");
+    for line in self.linker_config().lines() {
+      let _ = writeln!(source, "; {}", line);
+    }
+
+    let _ = writeln!(source, "\n.segment \"HEADER\"");
+    let _ = writeln!(source, "  {}", data_directive(&self.rom.header()));
+
+    if let Some(trainer) = &self.rom.trainer_data {
+      let _ = writeln!(source, "\n.segment \"TRAINER\"");
+      for chunk in trainer.chunks(BYTES_PER_DATA_LINE) {
+        let _ = writeln!(source, "  {}", data_directive(chunk));
+      }
+    }
+
+    for (bank_index, bank) in self.banks.iter().enumerate() {
+      let _ = writeln!(source, "\n.segment \"BANK{:02}\"", bank_index);
+      self.write_bank(&mut source, bank_index, bank);
+    }
+
+    if !self.rom.chr_data.is_empty() {
+      let _ = writeln!(source, "\n.segment \"CHARS\"");
+      for chunk in self.rom.chr_data.chunks(BYTES_PER_DATA_LINE) {
+        let _ = writeln!(source, "  {}", data_directive(chunk));
+      }
+    }
+
+    source
+  }
+
+  fn write_bank(&self, source: &mut String, bank_index: usize, bank: &BankLayout) {
+    let bank_end = bank.offset + bank.len;
+    let mut offset = bank.offset;
+    while offset < bank_end {
+      if let Some(label) = self.labels.get(&offset) {
+        let _ = writeln!(source, "{}:", label);
+      }
+
+      if self.kinds[offset] == ByteKind::Opcode {
+        let _ = writeln!(source, "{}", self.format_instruction(bank_index, offset));
+        offset += usize::from(self.instructions[&offset].1);
+        continue;
+      }
+
+      if let Some(line) = self.vectors_line(bank_index, offset) {
+        let _ = writeln!(source, "{}", line);
+        offset += 6;
+        continue;
+      }
+
+      // data runs until the next label, instruction or the vectors
+      let mut end = offset + 1;
+      while end < bank_end
+        && end - offset < BYTES_PER_DATA_LINE
+        && self.kinds[end] != ByteKind::Opcode
+        && !self.labels.contains_key(&end)
+        && !(bank.fixed && bank.addr(end) == VECTORS_ADDR)
+      {
+        end += 1;
+      }
+
+      let _ = writeln!(
+        source,
+        "  {}",
+        data_directive(&self.rom.prg_data[offset..end])
+      );
+      offset = end;
+    }
+  }
+}
+
+fn data_directive(bytes: &[u8]) -> String {
+  format!(
+    ".byte {}",
+    bytes
+      .iter()
+      .map(|byte| format!("${:02X}", byte))
+      .collect::<Vec<_>>()
+      .join(", ")
+  )
+}
@@ -0,0 +1,93 @@
+use super::{MemorySnapshot, RamAddress, ValueType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFilter {
+  EqualTo(i32),
+  Unchanged,
+  Changed,
+  Increased,
+  Decreased,
+  ChangedBy(i32),
+}
+
+impl SearchFilter {
+  pub fn matches(&self, value_type: ValueType, previous: i32, current: i32) -> bool {
+    match self {
+      SearchFilter::EqualTo(value) => current == *value,
+      SearchFilter::Unchanged => current == previous,
+      SearchFilter::Changed => current != previous,
+      SearchFilter::Increased => current > previous,
+      SearchFilter::Decreased => current < previous,
+      SearchFilter::ChangedBy(delta) => {
+        // deltas wrap around like the game's own arithmetic, so -1 matches $00 -> $FF
+        let mask = (1_i32 << (value_type.byte_len() * 8)) - 1;
+        (current - previous) & mask == delta & mask
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RamSearch {
+  value_type: ValueType,
+  candidates: Vec<RamAddress>,
+  previous_snapshot: Option<MemorySnapshot>,
+}
+
+impl RamSearch {
+  pub fn reset(&mut self, snapshot: MemorySnapshot, value_type: ValueType) {
+    self.value_type = value_type;
+    self.candidates = snapshot.addresses(value_type).collect();
+    self.previous_snapshot = Some(snapshot);
+  }
+
+  /// Reads the candidates as `value_type` from now on, dropping any that a value that wide doesn't
+  /// fit at.
+  pub fn set_value_type(&mut self, value_type: ValueType) {
+    self.value_type = value_type;
+    if let Some(previous_snapshot) = &self.previous_snapshot {
+      self
+        .candidates
+        .retain(|addr| previous_snapshot.read_value(*addr, value_type).is_some());
+    }
+  }
+
+  /// Narrows the candidates down by comparing the previous snapshot against `snapshot`, which then
+  /// becomes the previous snapshot for the next filter.
+  pub fn apply_filter(&mut self, filter: SearchFilter, snapshot: MemorySnapshot) {
+    let Some(previous_snapshot) = &self.previous_snapshot else {
+      return self.reset(snapshot, self.value_type);
+    };
+
+    let value_type = self.value_type;
+    self.candidates.retain(|addr| {
+      match (
+        previous_snapshot.read_value(*addr, value_type),
+        snapshot.read_value(*addr, value_type),
+      ) {
+        (Some(previous), Some(current)) => filter.matches(value_type, previous, current),
+        _ => false,
+      }
+    });
+    self.previous_snapshot = Some(snapshot);
+  }
+
+  pub fn value_type(&self) -> ValueType {
+    self.value_type
+  }
+
+  pub fn candidates(&self) -> &[RamAddress] {
+    &self.candidates
+  }
+
+  pub fn is_started(&self) -> bool {
+    self.previous_snapshot.is_some()
+  }
+
+  pub fn previous_value(&self, addr: RamAddress) -> Option<i32> {
+    self
+      .previous_snapshot
+      .as_ref()?
+      .read_value(addr, self.value_type)
+  }
+}
@@ -0,0 +1,32 @@
+use super::{MemorySnapshot, RamAddress, ValueType};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RamWatch {
+  pub address: RamAddress,
+  pub value_type: ValueType,
+  pub label: String,
+}
+
+impl RamWatch {
+  pub fn new(address: RamAddress, value_type: ValueType) -> Self {
+    Self {
+      address,
+      value_type,
+      label: String::new(),
+    }
+  }
+
+  pub fn format_value(&self, snapshot: &MemorySnapshot) -> String {
+    match snapshot.read_value(self.address, self.value_type) {
+      Some(value) => format_value(value, self.value_type),
+      None => "--".to_owned(),
+    }
+  }
+}
+
+pub fn format_value(value: i32, value_type: ValueType) -> String {
+  match value_type.byte_len() {
+    1 => format!("{} (${:02X})", value, value & 0xff),
+    _ => format!("{} (${:04X})", value, value & 0xffff),
+  }
+}
@@ -0,0 +1,138 @@
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+
+use super::{dbg_segments, parse_dbg_records, SymbolAddress};
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+  /// The name as given to the assembler or compiler.
+  pub name: String,
+  pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation {
+  /// Index into `SourceMap::files`.
+  pub file: usize,
+  /// 1-based.
+  pub line: usize,
+}
+
+// ld65 line types, in order of preference when several lines cover the same byte
+const LINE_TYPE_PRIORITY: [usize; 3] = [1, 0, 2];
+
+/// Maps addresses back to the C and assembly source lines they were built from, using ld65 debug
+/// info.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+  files: Vec<SourceFile>,
+  locations: HashMap<SymbolAddress, (usize, SourceLocation)>,
+}
+
+impl SourceMap {
+  pub fn load(path: &Path) -> anyhow::Result<Self> {
+    Self::parse_dbg(
+      &fs::read_to_string(path)?,
+      path.parent().unwrap_or(Path::new("")),
+    )
+  }
+
+  /// Parses an ld65 `--dbgfile`. Relative source paths are resolved against `base_dir`.
+  pub fn parse_dbg(contents: &str, base_dir: &Path) -> anyhow::Result<Self> {
+    let records = parse_dbg_records(contents);
+    if !records.iter().any(|record| record.kind == "version") {
+      return Err(anyhow!("Not an ld65 debug info file"));
+    }
+
+    let segments = dbg_segments(&records);
+    let mut source_map = Self::default();
+
+    let mut file_indexes = HashMap::new();
+    for record in records.iter().filter(|record| record.kind == "file") {
+      let (Some(id), Some(name)) = (record.number("id"), record.get("name")) else {
+        continue;
+      };
+
+      file_indexes.insert(id, source_map.files.len());
+      source_map.files.push(SourceFile {
+        name: name.to_owned(),
+        path: base_dir.join(name),
+      });
+    }
+
+    let spans: HashMap<usize, (usize, usize, usize)> = records
+      .iter()
+      .filter(|record| record.kind == "span")
+      .filter_map(|record| {
+        Some((
+          record.number("id")?,
+          (
+            record.number("seg")?,
+            record.number("start")?,
+            record.number("size")?,
+          ),
+        ))
+      })
+      .collect();
+
+    for record in records.iter().filter(|record| record.kind == "line") {
+      let (Some(file), Some(line)) = (
+        record
+          .number("file")
+          .and_then(|file| file_indexes.get(&file)),
+        record.number("line"),
+      ) else {
+        continue;
+      };
+      let location = SourceLocation { file: *file, line };
+      let line_type = record.number("type").unwrap_or(0);
+      let priority = LINE_TYPE_PRIORITY
+        .iter()
+        .position(|preferred| *preferred == line_type)
+        .unwrap_or(LINE_TYPE_PRIORITY.len());
+
+      for (seg, start, size) in record.ids("span").iter().filter_map(|span| spans.get(span)) {
+        let Some(segment) = segments.get(seg) else {
+          continue;
+        };
+
+        for offset in *start..start + size {
+          let Ok(addr) = u16::try_from(usize::from(segment.start) + offset) else {
+            continue;
+          };
+
+          let entry = source_map
+            .locations
+            .entry(segment.address(addr))
+            .or_insert((priority, location));
+          if priority < entry.0 {
+            *entry = (priority, location);
+          }
+        }
+      }
+    }
+
+    Ok(source_map)
+  }
+
+  pub fn files(&self) -> &[SourceFile] {
+    &self.files
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.locations.is_empty()
+  }
+
+  /// The source line for a CPU address, given the PRG ROM offset currently mapped there.
+  pub fn location(&self, addr: u16, prg_rom_offset: Option<usize>) -> Option<SourceLocation> {
+    prg_rom_offset
+      .and_then(|offset| self.locations.get(&SymbolAddress::PRGROM(offset)))
+      .or_else(|| self.locations.get(&SymbolAddress::CPU(addr)))
+      .map(|(_, location)| *location)
+  }
+}
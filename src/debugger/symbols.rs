@@ -0,0 +1,242 @@
+use std::{collections::HashMap, fmt::Display, fs, path::Path};
+
+use anyhow::anyhow;
+
+use super::{dbg_segments, parse_dbg_records};
+use crate::nes::PRG_BANK_SIZE;
+
+/// Where a symbol points. PRG ROM symbols only apply while their bank is mapped in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolAddress {
+  #[allow(clippy::upper_case_acronyms)]
+  CPU(u16),
+  #[allow(clippy::upper_case_acronyms)]
+  PRGROM(usize),
+}
+
+impl Display for SymbolAddress {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SymbolAddress::CPU(addr) => write!(f, "${:04X}", addr),
+      SymbolAddress::PRGROM(offset) => write!(f, "PRG:{:05X}", offset),
+    }
+  }
+}
+
+/// Labels imported from ca65/ld65 `.dbg`, FCEUX `.nl` and Mesen `.mlb` files.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+  cpu: HashMap<u16, String>,
+  prg_rom: HashMap<usize, String>,
+  names: HashMap<String, SymbolAddress>,
+}
+
+impl SymbolTable {
+  /// Loads a symbol file, picking the format from its extension. Loading one FCEUX `.nl` file also
+  /// loads the other RAM and bank files next to it.
+  pub fn load(path: &Path) -> anyhow::Result<Self> {
+    let extension = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+      Some("dbg") => Self::parse_dbg(&fs::read_to_string(path)?),
+      Some("mlb") => Ok(Self::parse_mlb(&fs::read_to_string(path)?)),
+      Some("nl") => Self::load_nl_files(path),
+      _ => Err(anyhow!(
+        "Unknown symbol file type: {} (expected .dbg, .nl or .mlb)",
+        path.display()
+      )),
+    }
+  }
+
+  fn load_nl_files(path: &Path) -> anyhow::Result<Self> {
+    // FCEUX names them game.nes.ram.nl, game.nes.0.nl, game.nes.1.nl, ...
+    let file_name = path
+      .file_name()
+      .and_then(|file_name| file_name.to_str())
+      .unwrap_or_default();
+    let rom_name = file_name
+      .strip_suffix(".nl")
+      .and_then(|name| name.rsplit_once('.'))
+      .map(|(rom_name, _)| rom_name)
+      .unwrap_or(file_name);
+    let directory = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+
+    let mut symbols = Self::default();
+    for entry in fs::read_dir(directory.unwrap_or(Path::new(".")))? {
+      let entry = entry?;
+      let name = entry.file_name();
+      let Some(part) = name.to_str().and_then(|name| {
+        name
+          .strip_prefix(rom_name)?
+          .strip_prefix('.')?
+          .strip_suffix(".nl")
+      }) else {
+        continue;
+      };
+
+      let bank = match part {
+        "ram" => None,
+        bank => match bank.parse::<usize>() {
+          Ok(bank) => Some(bank),
+          Err(_) => continue,
+        },
+      };
+      symbols.merge(Self::parse_nl(&fs::read_to_string(entry.path())?, bank));
+    }
+
+    if symbols.is_empty() {
+      symbols = Self::parse_nl(&fs::read_to_string(path)?, None);
+    }
+
+    Ok(symbols)
+  }
+
+  /// Parses an ld65 `--dbgfile`. Labels in segments that are written to the ROM are tied to their
+  /// PRG ROM offset so they follow bank switching.
+  pub fn parse_dbg(contents: &str) -> anyhow::Result<Self> {
+    let records = parse_dbg_records(contents);
+    if !records.iter().any(|record| record.kind == "version") {
+      return Err(anyhow!("Not an ld65 debug info file"));
+    }
+
+    let segments = dbg_segments(&records);
+
+    let mut symbols = Self::default();
+    for record in records
+      .iter()
+      .filter(|record| record.kind == "sym" && record.get("type") == Some("lab"))
+    {
+      let (Some(name), Some(value)) = (record.get("name"), record.number("val")) else {
+        continue;
+      };
+      let Ok(addr) = u16::try_from(value) else {
+        continue;
+      };
+
+      let address = record
+        .number("seg")
+        .and_then(|seg| segments.get(&seg))
+        .map(|segment| segment.address(addr))
+        .unwrap_or(SymbolAddress::CPU(addr));
+      symbols.insert(address, name);
+    }
+
+    Ok(symbols)
+  }
+
+  /// Parses an FCEUX name list, e.g. `$0300#player_x#comment`. `bank` is the 16KiB PRG bank for
+  /// `game.nes.N.nl` files, or `None` for `game.nes.ram.nl`.
+  pub fn parse_nl(contents: &str, bank: Option<usize>) -> Self {
+    let mut symbols = Self::default();
+    for line in contents.lines() {
+      let mut parts = line.split('#');
+      let (Some(addr), Some(name)) = (parts.next(), parts.next()) else {
+        continue;
+      };
+
+      // arrays are written as $0300/10
+      let addr = addr.split('/').next().unwrap_or_default();
+      let Some(addr) = addr
+        .trim()
+        .strip_prefix('$')
+        .and_then(|addr| u16::from_str_radix(addr, 16).ok())
+      else {
+        continue;
+      };
+
+      let address = match bank {
+        Some(bank) if addr >= 0x8000 => {
+          SymbolAddress::PRGROM(bank * PRG_BANK_SIZE + (usize::from(addr) - 0x8000) % PRG_BANK_SIZE)
+        }
+        _ => SymbolAddress::CPU(addr),
+      };
+      symbols.insert(address, name.trim());
+    }
+
+    symbols
+  }
+
+  /// Parses a Mesen label file, in either the Mesen (`P:1234:name`) or Mesen 2
+  /// (`NesPrgRom:1234:name`) style.
+  pub fn parse_mlb(contents: &str) -> Self {
+    let mut symbols = Self::default();
+    for line in contents.lines() {
+      let mut parts = line.splitn(4, ':');
+      let (Some(memory_type), Some(addr), Some(name)) = (parts.next(), parts.next(), parts.next())
+      else {
+        continue;
+      };
+
+      // ranges are written as 0300-0302
+      let addr = addr.split('-').next().unwrap_or_default();
+      let Ok(addr) = usize::from_str_radix(addr.trim(), 16) else {
+        continue;
+      };
+
+      let address = match memory_type.trim() {
+        "P" | "NesPrgRom" => SymbolAddress::PRGROM(addr),
+        "R" | "NesInternalRam" => SymbolAddress::CPU((addr & 0x7ff) as u16),
+        "S" | "W" | "NesSaveRam" | "NesWorkRam" => {
+          SymbolAddress::CPU(0x6000 + (addr & 0x1fff) as u16)
+        }
+        "G" | "NesMemory" => match u16::try_from(addr) {
+          Ok(addr) => SymbolAddress::CPU(addr),
+          Err(_) => continue,
+        },
+        _ => continue,
+      };
+      symbols.insert(address, name.trim());
+    }
+
+    symbols
+  }
+
+  /// Adds a symbol. The first non-local name for an address is the one shown in disassembly.
+  pub fn insert(&mut self, address: SymbolAddress, name: &str) {
+    if name.is_empty() {
+      return;
+    }
+
+    let label = match address {
+      SymbolAddress::CPU(addr) => self.cpu.entry(addr).or_insert_with(|| name.to_owned()),
+      SymbolAddress::PRGROM(offset) => self
+        .prg_rom
+        .entry(offset)
+        .or_insert_with(|| name.to_owned()),
+    };
+    // prefer `loop` over ca65's cheap local `@loop`
+    if label.starts_with('@') && !name.starts_with('@') {
+      *label = name.to_owned();
+    }
+    self.names.insert(name.to_owned(), address);
+  }
+
+  pub fn merge(&mut self, other: SymbolTable) {
+    for (name, address) in other.names {
+      self.insert(address, &name);
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.names.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.names.is_empty()
+  }
+
+  /// The label for a CPU address, given the PRG ROM offset currently mapped there.
+  pub fn label(&self, addr: u16, prg_rom_offset: Option<usize>) -> Option<&str> {
+    prg_rom_offset
+      .and_then(|offset| self.prg_rom.get(&offset))
+      .or_else(|| self.cpu.get(&addr))
+      .map(String::as_str)
+  }
+
+  pub fn lookup(&self, name: &str) -> Option<SymbolAddress> {
+    self.names.get(name).copied()
+  }
+}
@@ -0,0 +1,195 @@
+use std::{
+  collections::VecDeque,
+  ops::RangeInclusive,
+  sync::{Arc, RwLock},
+};
+
+use strum::{Display, EnumIter};
+
+use crate::{
+  cpu::{CPUInterrupt, DisassemblyMachineState, ExecutedInstruction},
+  nes::DisassemblyWriter,
+};
+
+pub const DEFAULT_TRACE_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumIter)]
+pub enum TraceFormat {
+  #[default]
+  #[strum(serialize = "nestest")]
+  Nestest,
+  #[strum(serialize = "nestest + PPU")]
+  WithPPU,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumIter)]
+pub enum InterruptFilter {
+  #[default]
+  #[strum(serialize = "Any")]
+  Any,
+  #[strum(serialize = "Outside interrupts")]
+  Outside,
+  #[allow(clippy::upper_case_acronyms)]
+  #[strum(serialize = "In NMI")]
+  NMI,
+  #[allow(clippy::upper_case_acronyms)]
+  #[strum(serialize = "In IRQ")]
+  IRQ,
+}
+
+impl InterruptFilter {
+  fn matches(&self, interrupt: Option<CPUInterrupt>) -> bool {
+    match self {
+      InterruptFilter::Any => true,
+      InterruptFilter::Outside => interrupt.is_none(),
+      InterruptFilter::NMI => interrupt == Some(CPUInterrupt::NMI),
+      InterruptFilter::IRQ => interrupt == Some(CPUInterrupt::IRQ),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceFilter {
+  pub pc_range: Option<RangeInclusive<u16>>,
+  /// 16KiB PRG ROM bank the instruction was fetched from.
+  pub bank: Option<usize>,
+  pub interrupt: InterruptFilter,
+}
+
+impl TraceFilter {
+  pub fn matches(&self, pc: u16, bank: Option<usize>, interrupt: Option<CPUInterrupt>) -> bool {
+    self
+      .pc_range
+      .as_ref()
+      .is_none_or(|pc_range| pc_range.contains(&pc))
+      && self
+        .bank
+        .is_none_or(|filter_bank| bank == Some(filter_bank))
+      && self.interrupt.matches(interrupt)
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+  pub instruction: ExecutedInstruction,
+  /// Machine state from just before the instruction executed.
+  pub state: DisassemblyMachineState,
+}
+
+impl TraceEntry {
+  pub fn format(&self, format: TraceFormat) -> String {
+    match format {
+      TraceFormat::Nestest => self.instruction.disassemble(&self.state),
+      TraceFormat::WithPPU => self.instruction.disassemble_with_ppu(&self.state),
+    }
+  }
+}
+
+/// Keeps the most recent executed instructions that pass the filter, and optionally streams them to
+/// a writer as they're logged.
+#[derive(Debug)]
+pub struct TraceLogger {
+  enabled: bool,
+  format: TraceFormat,
+  filter: TraceFilter,
+  capacity: usize,
+  entries: VecDeque<TraceEntry>,
+  writer: Option<Arc<RwLock<dyn DisassemblyWriter + Send + Sync>>>,
+}
+
+impl Default for TraceLogger {
+  fn default() -> Self {
+    Self::new(DEFAULT_TRACE_CAPACITY)
+  }
+}
+
+impl TraceLogger {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      enabled: false,
+      format: TraceFormat::default(),
+      filter: TraceFilter::default(),
+      capacity,
+      entries: VecDeque::with_capacity(capacity),
+      writer: None,
+    }
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.enabled
+  }
+
+  pub fn start(&mut self) {
+    self.enabled = true;
+  }
+
+  pub fn stop(&mut self) {
+    self.enabled = false;
+    self.flush_writer();
+  }
+
+  pub fn set_format(&mut self, format: TraceFormat) {
+    self.format = format;
+  }
+
+  pub fn filter(&self) -> &TraceFilter {
+    &self.filter
+  }
+
+  pub fn set_filter(&mut self, filter: TraceFilter) {
+    self.filter = filter;
+  }
+
+  pub fn set_capacity(&mut self, capacity: usize) {
+    self.capacity = capacity;
+    while self.entries.len() > capacity {
+      self.entries.pop_front();
+    }
+  }
+
+  pub fn set_writer(&mut self, writer: Option<Arc<RwLock<dyn DisassemblyWriter + Send + Sync>>>) {
+    self.flush_writer();
+    self.writer = writer;
+  }
+
+  fn flush_writer(&mut self) {
+    if let Some(writer) = &self.writer {
+      writer.write().unwrap().flush().unwrap();
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+
+  /// `bank` only needs to be provided when the filter has a bank set. `interrupt` is the one
+  /// being serviced when the instruction ran, if any.
+  pub fn log(&mut self, entry: TraceEntry, bank: Option<usize>, interrupt: Option<CPUInterrupt>) {
+    if !self.enabled || !self.filter.matches(entry.state.cpu.pc, bank, interrupt) {
+      return;
+    }
+
+    if let Some(writer) = &self.writer {
+      writeln!(writer.write().unwrap(), "{}", entry.format(self.format)).unwrap();
+    }
+
+    if self.capacity == 0 {
+      return;
+    }
+
+    if self.entries.len() == self.capacity {
+      self.entries.pop_front();
+    }
+    self.entries.push_back(entry);
+  }
+
+  /// The last `count` logged instructions, oldest first.
+  pub fn recent_lines(&self, count: usize) -> Vec<String> {
+    self
+      .entries
+      .iter()
+      .skip(self.entries.len().saturating_sub(count))
+      .map(|entry| entry.format(self.format))
+      .collect()
+  }
+}
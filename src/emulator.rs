@@ -1,23 +1,44 @@
 use std::{
-  env,
-  io::BufWriter,
+  fmt::Display,
+  mem,
+  path::{Path, PathBuf},
   sync::{Arc, RwLock},
+  thread,
   time::{Duration, Instant},
 };
 
 use smol::channel::{Receiver, Sender};
-use smol::stream::StreamExt;
 use strum::IntoStaticStr;
 
 use crate::{
-  apu::APUSynthChannel,
-  audio::synth::SynthCommand,
+  apu::{emulated_time, APUSynthChannel, AudioClock},
+  audio::synth::{PlaybackPosition, SynthCommand},
+  cheats::Cheat,
   cpu::CPU,
-  nes::{ControllerButton, INESRom, NES},
-  ppu::{PPULoopyRegister, Pixbuf},
+  debugger::{
+    Breakpoint, CDLFile, MemoryPage, MemorySnapshot, MemorySpace, PPUSnapshot, RgbaImage,
+    SourceLocation, SourceMap, SymbolTable, TraceFilter, TraceFormat,
+  },
+  nes::{ControllerButton, DisassemblyWriter, INESRom, RewindBuffer, NES},
+  ppu::{
+    screenshot_path, FrameBlend, NTSCFilter, PPULoopyRegister, Palette, Pixbuf, ScreenshotOptions,
+    TripleBufferWriter,
+  },
+  recording::{recording_paths, Recorder},
 };
 
-const TARGET_FRAME_DURATION: f64 = 1.0 / 60.0;
+// NTSC runs 29780.5 CPU cycles per frame
+const FRAME_DURATION: f64 = 29780.5 / 1_789_773.0;
+// share of each host frame spent emulating before giving up on the rest of a fast-forward batch
+const FRAME_TIME_BUDGET: f64 = 0.9;
+// how much frames can be sped up or slowed down to keep the synth's queue at its target latency;
+// small enough that the pitch change isn't noticeable
+const MAX_RATE_ADJUSTMENT: f64 = 0.005;
+// how far off target latency the synth's queue can get (e.g. after pausing) before it's resynced
+const MAX_FILL_ERROR: f64 = 2.0;
+// sleeps can overshoot by a millisecond or so, so the end of each wait is spun instead
+const SPIN_DURATION: Duration = Duration::from_millis(1);
+pub const MAX_RUN_AHEAD_FRAMES: usize = 4;
 
 #[derive(Debug, Clone, Copy, IntoStaticStr, Default)]
 pub enum EmulatorState {
@@ -26,11 +47,71 @@ pub enum EmulatorState {
   Pause,
   RunUntilNextFrame,
   RunUntilNextInstruction,
+  RunUntilNextSourceLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmulationSpeed {
+  Quarter,
+  Half,
+  #[default]
+  Normal,
+  Double,
+  Quadruple,
+  Octuple,
+  Unlimited,
+}
+
+impl EmulationSpeed {
+  /// Emulated frames per host frame, or `None` to run as fast as possible.
+  pub fn multiplier(self) -> Option<f64> {
+    match self {
+      EmulationSpeed::Quarter => Some(0.25),
+      EmulationSpeed::Half => Some(0.5),
+      EmulationSpeed::Normal => Some(1.0),
+      EmulationSpeed::Double => Some(2.0),
+      EmulationSpeed::Quadruple => Some(4.0),
+      EmulationSpeed::Octuple => Some(8.0),
+      EmulationSpeed::Unlimited => None,
+    }
+  }
+
+  pub fn faster(self) -> Self {
+    match self {
+      EmulationSpeed::Quarter => EmulationSpeed::Half,
+      EmulationSpeed::Half => EmulationSpeed::Normal,
+      EmulationSpeed::Normal => EmulationSpeed::Double,
+      EmulationSpeed::Double => EmulationSpeed::Quadruple,
+      EmulationSpeed::Quadruple => EmulationSpeed::Octuple,
+      EmulationSpeed::Octuple | EmulationSpeed::Unlimited => EmulationSpeed::Unlimited,
+    }
+  }
+
+  pub fn slower(self) -> Self {
+    match self {
+      EmulationSpeed::Quarter | EmulationSpeed::Half => EmulationSpeed::Quarter,
+      EmulationSpeed::Normal => EmulationSpeed::Half,
+      EmulationSpeed::Double => EmulationSpeed::Normal,
+      EmulationSpeed::Quadruple => EmulationSpeed::Double,
+      EmulationSpeed::Octuple => EmulationSpeed::Quadruple,
+      EmulationSpeed::Unlimited => EmulationSpeed::Octuple,
+    }
+  }
+}
+
+impl Display for EmulationSpeed {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.multiplier() {
+      Some(multiplier) => write!(f, "{}x", multiplier),
+      None => write!(f, "Unlimited"),
+    }
+  }
 }
 
 #[derive(Default, Debug, Clone)]
 pub struct MachineState {
   pub emulator_state: EmulatorState,
+  pub speed: EmulationSpeed,
   pub cpu: CPU,
   pub vram_addr: PPULoopyRegister,
   pub tram_addr: PPULoopyRegister,
@@ -39,37 +120,264 @@ pub struct MachineState {
   pub mem2002: u8,
   pub mem2004: u8,
   pub mem2007: u8,
+  pub source_location: Option<SourceLocation>,
+  pub frame_count: u64,
+  pub lag_frame_count: u64,
+  pub lag_frame: bool,
+  pub input_polls: u32,
 }
 
 #[derive(Debug)]
 pub enum EmulationInboundMessage {
   ControllerButtonChanged(ControllerButton, bool),
   EmulatorStateChangeRequested(EmulatorState),
+  CheatsChanged(Vec<Cheat>),
+  MachineStatesRequested(bool),
+  MemorySnapshotsRequested(bool),
+  PPUSnapshotsRequested(bool),
+  MemoryPageRequested(Option<(MemorySpace, usize)>),
+  MemoryWriteRequested(MemorySpace, usize, u8),
+  CodeDataLoggingToggled(bool),
+  CodeDataLogCleared,
+  CodeDataLogRequested,
+  TraceLoggingToggled(bool),
+  TraceFilterChanged(TraceFilter),
+  TraceFormatChanged(TraceFormat),
+  TraceCapacityChanged(usize),
+  TraceWriterChanged(Option<Arc<RwLock<dyn DisassemblyWriter + Send + Sync>>>),
+  TraceCleared,
+  /// How many of the most recent trace lines to send while paused, or `None` to stop sending them.
+  TraceRequested(Option<usize>),
+  SymbolsChanged(SymbolTable),
+  BreakpointsChanged(Vec<Breakpoint>),
+  SourceMapChanged(SourceMap),
+  /// While rewinding, running plays back through earlier frames instead.
+  RewindToggled(bool),
+  SpeedChanged(EmulationSpeed),
+  /// Runs at unlimited speed while set, regardless of the chosen speed.
+  FastForwardToggled(bool),
+  /// With frame skip on, only the last of the frames run in a host frame is shown.
+  FrameSkipToggled(bool),
+  NTSCFilterChanged(Option<NTSCFilter>),
+  /// Blends each frame with the one before it, or stops with `None`.
+  FrameBlendChanged(Option<FrameBlend>),
+  UnlimitedSpritesToggled(bool),
+  /// Also redraws the current frame with it, so it shows even while paused.
+  PaletteChanged(Palette),
+  /// Saves the picture on screen as a PNG next to the ROM at this path.
+  ScreenshotRequested(PathBuf, ScreenshotOptions),
+  /// Starts recording video and audio next to the ROM at this path, or stops if already recording.
+  RecordingToggled(PathBuf),
 }
 
 #[derive(Debug)]
 pub enum EmulationOutboundMessage {
   FrameReady,
   MachineStateChanged(MachineState),
+  MemorySnapshotCaptured(MemorySnapshot),
+  PPUSnapshotCaptured(Box<PPUSnapshot>),
+  MemoryPageCaptured(MemoryPage),
+  CodeDataLogCaptured(CDLFile),
+  TraceCaptured(Vec<String>),
+  /// Where the screenshot went, or why it couldn't be saved.
+  ScreenshotSaved(Result<PathBuf, String>),
+  /// The video file being recorded to, or why recording couldn't start.
+  RecordingStarted(Result<PathBuf, String>),
+  /// The video file recorded, or why recording stopped early.
+  RecordingStopped(Result<PathBuf, String>),
   Shutdown,
 }
 
 pub struct Emulator {
   nes: NES,
   state: EmulatorState,
-  last_tick: Instant,
-  last_tick_duration: Duration,
-  pixbuf: Arc<RwLock<Pixbuf>>,
+  // what the PPU draws into; finished frames are rendered from it and published to the UI
+  pixbuf: Pixbuf,
+  frame_writer: TripleBufferWriter<RgbaImage>,
+  palette: Palette,
+  machine_states_requested: bool,
+  memory_snapshots_requested: bool,
+  ppu_snapshots_requested: bool,
+  memory_page_requested: Option<(MemorySpace, usize)>,
+  trace_lines_requested: Option<usize>,
+  rewind_buffer: RewindBuffer,
+  rewinding: bool,
+  // not part of `state`, which switches to Pause and back every time the user steps, while the
+  // speed has to carry over to whenever it runs next
+  speed: EmulationSpeed,
+  fast_forward: bool,
+  frame_skip: bool,
+  // fractional frames owed at speeds that don't divide evenly into host frames
+  frame_budget: f64,
+  run_ahead_frames: usize,
+  recorder: Option<Recorder>,
 }
 
 impl Emulator {
-  pub fn new(nes: NES, pixbuf: Arc<RwLock<Pixbuf>>) -> Self {
+  pub fn new(
+    nes: NES,
+    frame_writer: TripleBufferWriter<RgbaImage>,
+    rewind_memory_limit: usize,
+  ) -> Self {
     Self {
       nes,
       state: EmulatorState::Run,
-      last_tick: Instant::now(),
-      last_tick_duration: Duration::default(),
-      pixbuf,
+      pixbuf: Pixbuf::new(),
+      frame_writer,
+      palette: Palette::default(),
+      machine_states_requested: false,
+      memory_snapshots_requested: false,
+      ppu_snapshots_requested: false,
+      memory_page_requested: None,
+      trace_lines_requested: None,
+      rewind_buffer: RewindBuffer::new(rewind_memory_limit),
+      rewinding: false,
+      speed: EmulationSpeed::default(),
+      fast_forward: false,
+      frame_skip: true,
+      frame_budget: 0.0,
+      run_ahead_frames: 0,
+      recorder: None,
+    }
+  }
+
+  /// How many frames to run ahead of the one being emulated when showing the picture, up to
+  /// `MAX_RUN_AHEAD_FRAMES`. 0 turns run-ahead off.
+  pub fn set_run_ahead_frames(&mut self, frames: usize) {
+    self.run_ahead_frames = frames.min(MAX_RUN_AHEAD_FRAMES);
+  }
+
+  fn effective_speed(&self) -> EmulationSpeed {
+    if self.fast_forward {
+      EmulationSpeed::Unlimited
+    } else {
+      self.speed
+    }
+  }
+
+  fn speed_changed(&mut self) {
+    self.frame_budget = 0.0;
+    self.nes.audio_clock.resync(
+      self.nes.state.cpu_cycle_count,
+      self.effective_speed().multiplier(),
+    );
+  }
+
+  /// Runs a frame, or plays one back while rewinding. Returns whether a breakpoint stopped it.
+  fn run_frame(&mut self) -> bool {
+    if self.rewinding {
+      // replay a frame from each snapshot to have something to show, then throw it away
+      let Some(state) = self.rewind_buffer.pop() else {
+        return false;
+      };
+      self.nes.restore(state);
+      self.nes.execute_hidden_frame(&mut self.pixbuf);
+      return false;
+    }
+
+    self.rewind_buffer.push(self.nes.snapshot());
+    let frame_start = emulated_time(self.nes.state.cpu_cycle_count);
+    let stopped = self.nes.execute_frame(&mut self.pixbuf);
+    let frame_end = emulated_time(self.nes.state.cpu_cycle_count);
+    // recorded before running ahead draws over it, so the video stays in step with the audio
+    if let Some(recorder) = &mut self.recorder {
+      recorder.record_frame(&self.pixbuf.frame, &self.palette, frame_start, frame_end);
+    }
+
+    stopped
+  }
+
+  /// Draws the frame `run_ahead_frames` after the one just run, for showing instead of it.
+  fn run_ahead(&mut self) {
+    if self.run_ahead_frames > 0 && !self.rewinding {
+      self.nes.run_ahead(self.run_ahead_frames, &mut self.pixbuf);
+    }
+  }
+
+  fn start_recording(&mut self, rom_path: &Path) -> Result<PathBuf, anyhow::Error> {
+    let (video_path, audio_path) = recording_paths(rom_path, self.nes.state.ppu.frame_count);
+    let recorder = Recorder::create(&video_path, &audio_path)?;
+    self.nes.recording_sender = Some(recorder.command_sender());
+    self.recorder = Some(recorder);
+    Ok(video_path)
+  }
+
+  fn stop_recording(&mut self) -> Option<Result<PathBuf, anyhow::Error>> {
+    let mut recorder = self.recorder.take()?;
+    self.nes.recording_sender = None;
+
+    Some(match recorder.take_error() {
+      Some(err) => Err(err),
+      None => recorder.finish(),
+    })
+  }
+
+  /// Stops a recording that failed to write, letting the UI know why.
+  async fn check_recording(&mut self, sender: &Sender<EmulationOutboundMessage>) {
+    if self
+      .recorder
+      .as_ref()
+      .is_some_and(|recorder| recorder.has_error())
+    {
+      if let Some(result) = self.stop_recording() {
+        sender
+          .send(EmulationOutboundMessage::RecordingStopped(
+            result.map_err(|err| format!("{:#}", err)),
+          ))
+          .await
+          .unwrap();
+      }
+    }
+  }
+
+  async fn run_host_frame(&mut self, sender: &Sender<EmulationOutboundMessage>) {
+    let start = Instant::now();
+    let frames = match self.effective_speed().multiplier() {
+      Some(multiplier) => {
+        self.frame_budget += multiplier;
+        let frames = self.frame_budget.floor();
+        self.frame_budget -= frames;
+        frames as usize
+      }
+      None => usize::MAX,
+    };
+    let deadline = start + Duration::from_secs_f64(FRAME_DURATION * FRAME_TIME_BUDGET);
+
+    let mut frames_run = 0;
+    let mut stopped = false;
+    while frames_run < frames && Instant::now() < deadline {
+      frames_run += 1;
+      stopped = self.run_frame();
+      if stopped {
+        self.state = EmulatorState::Pause;
+      }
+
+      if !self.frame_skip {
+        if !stopped {
+          self.run_ahead();
+        }
+        self.send_frame_messages(sender).await;
+      }
+
+      if stopped {
+        break;
+      }
+    }
+
+    // frames that get skipped are never seen, so only the last one needs running ahead from
+    if self.frame_skip && frames_run > 0 {
+      if !stopped {
+        self.run_ahead();
+      }
+      self.send_frame_messages(sender).await;
+    }
+
+    let cpu_cycle_count = self.nes.state.cpu_cycle_count;
+    if self.nes.audio_clock.fill_error(cpu_cycle_count).abs() > MAX_FILL_ERROR {
+      self
+        .nes
+        .audio_clock
+        .resync(cpu_cycle_count, self.effective_speed().multiplier());
     }
   }
 
@@ -78,6 +386,7 @@ impl Emulator {
 
     MachineState {
       emulator_state: self.state,
+      speed: self.effective_speed(),
       cpu: self.nes.state.cpu.clone(),
       vram_addr: *self.nes.state.cartridge.ppu_cpu_bus().vram_addr(),
       tram_addr: *self.nes.state.cartridge.ppu_cpu_bus().tram_addr(),
@@ -86,19 +395,148 @@ impl Emulator {
       mem2002: cpu_bus.read_readonly(0x2002),
       mem2004: cpu_bus.read_readonly(0x2004),
       mem2007: cpu_bus.read_readonly(0x2007),
+      source_location: self.nes.source_location(self.nes.state.cpu.pc),
+      frame_count: self.nes.state.ppu.frame_count,
+      lag_frame_count: self.nes.state.lag_frame_count,
+      lag_frame: self.nes.is_lag_frame(),
+      input_polls: self.nes.state.last_frame_input_polls,
+    }
+  }
+
+  async fn send_memory_snapshot(&self, sender: &Sender<EmulationOutboundMessage>) {
+    sender
+      .send(EmulationOutboundMessage::MemorySnapshotCaptured(
+        MemorySnapshot::capture(&self.nes.state.cartridge),
+      ))
+      .await
+      .unwrap();
+  }
+
+  async fn send_ppu_snapshot(&self, sender: &Sender<EmulationOutboundMessage>) {
+    sender
+      .send(EmulationOutboundMessage::PPUSnapshotCaptured(Box::new(
+        PPUSnapshot::capture(&self.nes.state.cartridge),
+      )))
+      .await
+      .unwrap();
+  }
+
+  async fn send_memory_page(&self, sender: &Sender<EmulationOutboundMessage>) {
+    let Some((space, start)) = self.memory_page_requested else {
+      return;
+    };
+
+    sender
+      .send(EmulationOutboundMessage::MemoryPageCaptured(
+        MemoryPage::capture(&self.nes.state.cartridge, space, start),
+      ))
+      .await
+      .unwrap();
+  }
+
+  async fn send_trace(&self, sender: &Sender<EmulationOutboundMessage>) {
+    let Some(count) = self.trace_lines_requested else {
+      return;
+    };
+
+    sender
+      .send(EmulationOutboundMessage::TraceCaptured(
+        self.nes.trace_logger.recent_lines(count),
+      ))
+      .await
+      .unwrap();
+  }
+
+  async fn send_machine_state(&self, sender: &Sender<EmulationOutboundMessage>) {
+    sender
+      .send(EmulationOutboundMessage::MachineStateChanged(
+        self.get_machine_state(),
+      ))
+      .await
+      .unwrap();
+  }
+
+  /// Renders the frame drawn so far and hands it to the UI, swapping the picture into the back
+  /// buffer rather than copying it. This happens once per host frame rather than at the PPU's
+  /// vblank, since run-ahead and rewinding run several frames per host frame and only the last
+  /// one's picture is meant to be seen. It also leaves the palette, NTSC filter and frame blending
+  /// to be applied once per frame shown rather than per frame emulated.
+  async fn publish_frame(&mut self, sender: &Sender<EmulationOutboundMessage>) {
+    self.pixbuf.render(&self.palette);
+    mem::swap(
+      &mut *self.frame_writer.back_buffer(),
+      &mut self.pixbuf.image,
+    );
+    self.frame_writer.publish();
+
+    sender
+      .send(EmulationOutboundMessage::FrameReady)
+      .await
+      .unwrap();
+  }
+
+  async fn send_frame_messages(&mut self, sender: &Sender<EmulationOutboundMessage>) {
+    if self.machine_states_requested {
+      self.send_machine_state(sender).await;
     }
+
+    if self.memory_snapshots_requested {
+      self.send_memory_snapshot(sender).await;
+    }
+
+    if self.ppu_snapshots_requested {
+      self.send_ppu_snapshot(sender).await;
+    }
+
+    self.send_memory_page(sender).await;
+
+    // formatting the trace every frame is too slow, so it's only sent once emulation stops
+    if !matches!(self.state, EmulatorState::Run) {
+      self.send_trace(sender).await;
+    }
+
+    self.publish_frame(sender).await;
   }
 
-  pub async fn run(
+  /// Time until the next host frame. While running, it's nudged so the audio queued in the synth
+  /// stays near its target latency, which keeps sound and picture in step without crackles.
+  fn frame_period(&self) -> Duration {
+    let audio_paced = matches!(
+      self.state,
+      EmulatorState::Run | EmulatorState::RunUntilNextSourceLine
+    ) && !self.rewinding
+      && self.effective_speed().multiplier().is_some();
+    if !audio_paced {
+      return Duration::from_secs_f64(FRAME_DURATION);
+    }
+
+    let fill_error = self
+      .nes
+      .audio_clock
+      .fill_error(self.nes.state.cpu_cycle_count)
+      .clamp(-1.0, 1.0);
+    Duration::from_secs_f64(FRAME_DURATION * (1.0 + MAX_RATE_ADJUSTMENT * fill_error))
+  }
+
+  /// Runs on the calling thread until the UI hangs up.
+  pub fn run(
     &mut self,
     inbound_receiver: Receiver<EmulationInboundMessage>,
     outbound_sender: Sender<EmulationOutboundMessage>,
   ) {
-    let mut timer = smol::Timer::interval(Duration::from_secs_f64(TARGET_FRAME_DURATION));
+    let mut next_frame = Instant::now();
+
+    while !inbound_receiver.is_closed() {
+      smol::block_on(self.run_once(&inbound_receiver, &outbound_sender));
 
-    loop {
-      timer.next().await;
-      self.run_once(&inbound_receiver, &outbound_sender).await;
+      // after falling behind (e.g. a slow debug panel update), carry on from now rather than
+      // rushing to catch up
+      next_frame = (next_frame + self.frame_period()).max(Instant::now());
+      sleep_until(next_frame);
+    }
+
+    if let Some(Err(err)) = self.stop_recording() {
+      eprintln!("Couldn't finish recording: {:#}", err);
     }
   }
 
@@ -115,101 +553,256 @@ impl Emulator {
           .cartridge
           .cpu_bus_mut()
           .set_controller_button_state(0, button, pressed),
-        EmulationInboundMessage::EmulatorStateChangeRequested(new_state) => self.state = new_state,
-      }
-    }
+        EmulationInboundMessage::EmulatorStateChangeRequested(new_state) => {
+          self.state = new_state;
 
-    match self.state {
-      EmulatorState::Pause => {}
-      EmulatorState::Run => {
-        let now = Instant::now();
-        self.last_tick_duration = now - self.last_tick;
-        self.last_tick = now;
+          match new_state {
+            // without debug info every instruction is its own line
+            EmulatorState::RunUntilNextSourceLine if self.nes.source_map.is_empty() => {
+              self.state = EmulatorState::RunUntilNextInstruction
+            }
+            EmulatorState::RunUntilNextSourceLine => self.nes.start_source_step(),
+            _ => self.nes.cancel_source_step(),
+          }
+        }
+        EmulationInboundMessage::CheatsChanged(cheats) => self
+          .nes
+          .state
+          .cartridge
+          .cpu_bus_mut()
+          .cheats_mut()
+          .set_cheats(&cheats),
+        EmulationInboundMessage::MachineStatesRequested(requested) => {
+          self.machine_states_requested = requested;
 
-        self.nes.execute_frame(&mut self.pixbuf.write().unwrap());
-        sender
-          .send(EmulationOutboundMessage::MachineStateChanged(
-            self.get_machine_state(),
+          if requested {
+            self.send_machine_state(sender).await;
+          }
+        }
+        EmulationInboundMessage::MemorySnapshotsRequested(requested) => {
+          self.memory_snapshots_requested = requested;
+
+          // send one right away so the panel has something to show while paused
+          if requested {
+            self.send_memory_snapshot(sender).await;
+          }
+        }
+        EmulationInboundMessage::PPUSnapshotsRequested(requested) => {
+          self.ppu_snapshots_requested = requested;
+
+          if requested {
+            self.send_ppu_snapshot(sender).await;
+          }
+        }
+        EmulationInboundMessage::MemoryPageRequested(request) => {
+          self.memory_page_requested = request;
+          self.send_memory_page(sender).await;
+        }
+        EmulationInboundMessage::MemoryWriteRequested(space, addr, value) => {
+          space.poke(&mut self.nes.state.cartridge, addr, value);
+          self.send_memory_page(sender).await;
+        }
+        EmulationInboundMessage::CodeDataLoggingToggled(enabled) => {
+          self.nes.state.cartridge.set_code_data_logging(enabled)
+        }
+        EmulationInboundMessage::CodeDataLogCleared => {
+          self.nes.state.cartridge.clear_code_data_log()
+        }
+        EmulationInboundMessage::CodeDataLogRequested => sender
+          .send(EmulationOutboundMessage::CodeDataLogCaptured(
+            self.nes.code_data_log(),
           ))
           .await
-          .unwrap();
-        sender
-          .send(EmulationOutboundMessage::FrameReady)
-          .await
-          .unwrap();
+          .unwrap(),
+        EmulationInboundMessage::TraceLoggingToggled(enabled) => {
+          if enabled {
+            self.nes.trace_logger.start();
+          } else {
+            self.nes.trace_logger.stop();
+            self.send_trace(sender).await;
+          }
+        }
+        EmulationInboundMessage::TraceFilterChanged(filter) => {
+          self.nes.trace_logger.set_filter(filter)
+        }
+        EmulationInboundMessage::TraceFormatChanged(format) => {
+          self.nes.trace_logger.set_format(format);
+          self.send_trace(sender).await;
+        }
+        EmulationInboundMessage::TraceCapacityChanged(capacity) => {
+          self.nes.trace_logger.set_capacity(capacity)
+        }
+        EmulationInboundMessage::TraceWriterChanged(writer) => {
+          self.nes.trace_logger.set_writer(writer)
+        }
+        EmulationInboundMessage::TraceCleared => {
+          self.nes.trace_logger.clear();
+          self.send_trace(sender).await;
+        }
+        EmulationInboundMessage::TraceRequested(count) => {
+          self.trace_lines_requested = count;
+          self.send_trace(sender).await;
+        }
+        EmulationInboundMessage::SymbolsChanged(symbols) => {
+          self.nes.symbols = symbols;
+        }
+        EmulationInboundMessage::BreakpointsChanged(breakpoints) => {
+          self.nes.breakpoints = breakpoints;
+        }
+        EmulationInboundMessage::SourceMapChanged(source_map) => {
+          self.nes.source_map = source_map;
+          self.send_frame_messages(sender).await;
+        }
+        EmulationInboundMessage::RewindToggled(rewinding) => {
+          self.rewinding = rewinding;
+          // the clock went backwards, so the audio has to pick up from where it stopped
+          self.speed_changed();
+        }
+        EmulationInboundMessage::SpeedChanged(speed) => {
+          self.speed = speed;
+          self.speed_changed();
+        }
+        EmulationInboundMessage::FastForwardToggled(fast_forward) => {
+          self.fast_forward = fast_forward;
+          self.speed_changed();
+        }
+        EmulationInboundMessage::FrameSkipToggled(frame_skip) => {
+          self.frame_skip = frame_skip;
+        }
+        EmulationInboundMessage::NTSCFilterChanged(filter) => {
+          self.pixbuf.set_ntsc_filter(filter);
+          self.publish_frame(sender).await;
+        }
+        EmulationInboundMessage::UnlimitedSpritesToggled(enabled) => {
+          self.nes.state.ppu.unlimited_sprites = enabled;
+        }
+        EmulationInboundMessage::FrameBlendChanged(blend) => {
+          self.pixbuf.set_frame_blend(blend);
+          self.publish_frame(sender).await;
+        }
+        EmulationInboundMessage::ScreenshotRequested(rom_path, options) => {
+          let result = if self.nes.is_drawing_frame() {
+            Err("Can't take a screenshot partway through a frame".to_owned())
+          } else {
+            self.pixbuf.render(&self.palette);
+            let path = screenshot_path(&rom_path, self.nes.state.ppu.frame_count);
+            self
+              .pixbuf
+              .save_png(&path, options)
+              .map(|()| path)
+              .map_err(|err| err.to_string())
+          };
+          sender
+            .send(EmulationOutboundMessage::ScreenshotSaved(result))
+            .await
+            .unwrap();
+        }
+        EmulationInboundMessage::RecordingToggled(rom_path) => {
+          let message = match self.stop_recording() {
+            Some(result) => {
+              EmulationOutboundMessage::RecordingStopped(result.map_err(|err| format!("{:#}", err)))
+            }
+            None => EmulationOutboundMessage::RecordingStarted(
+              self
+                .start_recording(&rom_path)
+                .map_err(|err| format!("{:#}", err)),
+            ),
+          };
+          sender.send(message).await.unwrap();
+        }
+        EmulationInboundMessage::PaletteChanged(palette) => {
+          self.palette = palette;
+          self.publish_frame(sender).await;
+        }
       }
+    }
+
+    match self.state {
+      EmulatorState::Pause => {}
+      EmulatorState::Run => self.run_host_frame(sender).await,
       EmulatorState::RunUntilNextFrame => {
-        self.nes.execute_frame(&mut self.pixbuf.write().unwrap());
-        sender
-          .send(EmulationOutboundMessage::MachineStateChanged(
-            self.get_machine_state(),
-          ))
-          .await
-          .unwrap();
-        sender
-          .send(EmulationOutboundMessage::FrameReady)
-          .await
-          .unwrap();
+        let frame_start = emulated_time(self.nes.state.cpu_cycle_count);
+        self.nes.execute_frame(&mut self.pixbuf);
+        if let Some(recorder) = &mut self.recorder {
+          let frame_end = emulated_time(self.nes.state.cpu_cycle_count);
+          recorder.record_frame(&self.pixbuf.frame, &self.palette, frame_start, frame_end);
+        }
+        self.send_frame_messages(sender).await;
         self.state = EmulatorState::Pause;
       }
       EmulatorState::RunUntilNextInstruction => {
         let start_cycles = self.nes.state.cpu_cycle_count;
         loop {
-          self.nes.tick(&mut self.pixbuf.write().unwrap());
+          self.nes.tick(&mut self.pixbuf);
 
           if self.nes.state.cpu_cycle_count > start_cycles {
             break;
           }
         }
-        sender
-          .send(EmulationOutboundMessage::MachineStateChanged(
-            self.get_machine_state(),
-          ))
-          .await
-          .unwrap();
-        sender
-          .send(EmulationOutboundMessage::FrameReady)
-          .await
-          .unwrap();
-
+        self.send_frame_messages(sender).await;
         self.state = EmulatorState::Pause;
       }
+      EmulatorState::RunUntilNextSourceLine => {
+        // a line can take several frames (e.g. waiting for vblank), so keep going between calls
+        if self.nes.execute_frame(&mut self.pixbuf) {
+          self.nes.cancel_source_step();
+          self.state = EmulatorState::Pause;
+        }
+        self.send_frame_messages(sender).await;
+      }
     }
+
+    self.check_recording(sender).await;
+  }
+}
+
+fn sleep_until(deadline: Instant) {
+  let now = Instant::now();
+  if deadline > now + SPIN_DURATION {
+    thread::sleep(deadline - now - SPIN_DURATION);
+  }
+
+  while Instant::now() < deadline {
+    std::hint::spin_loop();
   }
 }
 
 pub trait EmulatorBuilder: Send + Sync {
   fn build(
     &self,
-    pixbuf: Arc<RwLock<Pixbuf>>,
+    frame_writer: TripleBufferWriter<RgbaImage>,
     apu_sender: Sender<SynthCommand<APUSynthChannel>>,
+    playback_position: PlaybackPosition,
   ) -> Emulator;
 }
 
 pub struct NESEmulatorBuilder {
   rom: INESRom,
+  rewind_memory_limit: usize,
+  run_ahead_frames: usize,
 }
 
 impl NESEmulatorBuilder {
-  pub fn new(rom: INESRom) -> Self {
-    Self { rom }
+  pub fn new(rom: INESRom, rewind_memory_limit: usize, run_ahead_frames: usize) -> Self {
+    Self {
+      rom,
+      rewind_memory_limit,
+      run_ahead_frames,
+    }
   }
 }
 
 impl EmulatorBuilder for NESEmulatorBuilder {
   fn build(
     &self,
-    pixbuf: Arc<RwLock<Pixbuf>>,
+    frame_writer: TripleBufferWriter<RgbaImage>,
     apu_sender: Sender<SynthCommand<APUSynthChannel>>,
+    playback_position: PlaybackPosition,
   ) -> Emulator {
     let mut machine = NES::from_rom(self.rom.clone(), apu_sender);
-    let stdout = std::io::stdout();
-
-    if !env::var("DISASSEMBLE").unwrap_or_default().is_empty() {
-      let disassembly_writer = BufWriter::new(stdout);
-      machine.disassembly_writer = Some(Arc::new(RwLock::new(disassembly_writer)));
-    }
-
-    Emulator::new(machine, pixbuf)
+    machine.audio_clock = AudioClock::new(playback_position);
+    let mut emulator = Emulator::new(machine, frame_writer, self.rewind_memory_limit);
+    emulator.set_run_ahead_frames(self.run_ahead_frames);
+    emulator
   }
 }
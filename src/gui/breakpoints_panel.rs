@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+
+use iced::{
+  widget::{button, column, row, text, text_input, Column},
+  Alignment, Element, Font,
+};
+use native_dialog::FileDialog;
+
+use crate::{
+  debugger::{Breakpoint, SymbolTable},
+  emulator::EmulationInboundMessage,
+};
+
+#[derive(Debug, Clone)]
+pub enum BreakpointsPanelMessage {
+  LoadSymbolsRequested,
+  ClearSymbols,
+  NewBreakpointChanged(String),
+  AddBreakpoint,
+  RemoveBreakpoint(usize),
+}
+
+#[derive(Default)]
+pub struct BreakpointsPanel {
+  symbols: SymbolTable,
+  symbols_path: Option<PathBuf>,
+  breakpoints: Vec<Breakpoint>,
+  new_breakpoint: String,
+  error: Option<String>,
+}
+
+impl BreakpointsPanel {
+  pub fn update(&mut self, message: BreakpointsPanelMessage) -> Option<EmulationInboundMessage> {
+    match message {
+      BreakpointsPanelMessage::LoadSymbolsRequested => {
+        let Ok(Some(path)) = FileDialog::new()
+          .add_filter("Symbols", &["dbg", "nl", "mlb"])
+          .set_title("Load symbols")
+          .show_open_single_file()
+        else {
+          return None;
+        };
+
+        match SymbolTable::load(&path) {
+          Ok(symbols) => {
+            self.symbols = symbols;
+            self.symbols_path = Some(path);
+            self.error = None;
+            Some(EmulationInboundMessage::SymbolsChanged(
+              self.symbols.clone(),
+            ))
+          }
+          Err(err) => {
+            self.error = Some(err.to_string());
+            None
+          }
+        }
+      }
+      BreakpointsPanelMessage::ClearSymbols => {
+        self.symbols = SymbolTable::default();
+        self.symbols_path = None;
+        Some(EmulationInboundMessage::SymbolsChanged(
+          SymbolTable::default(),
+        ))
+      }
+      BreakpointsPanelMessage::NewBreakpointChanged(new_breakpoint) => {
+        self.new_breakpoint = new_breakpoint;
+        None
+      }
+      BreakpointsPanelMessage::AddBreakpoint => {
+        match Breakpoint::parse(&self.new_breakpoint, &self.symbols) {
+          Ok(breakpoint) => {
+            self.error = None;
+            self.new_breakpoint.clear();
+            if !self.breakpoints.contains(&breakpoint) {
+              self.breakpoints.push(breakpoint);
+            }
+            Some(EmulationInboundMessage::BreakpointsChanged(
+              self.breakpoints.clone(),
+            ))
+          }
+          Err(err) => {
+            self.error = Some(err.to_string());
+            None
+          }
+        }
+      }
+      BreakpointsPanelMessage::RemoveBreakpoint(index) => {
+        self.breakpoints.remove(index);
+        Some(EmulationInboundMessage::BreakpointsChanged(
+          self.breakpoints.clone(),
+        ))
+      }
+    }
+  }
+
+  pub fn view(&self) -> Element<'_, BreakpointsPanelMessage> {
+    let symbols_status = match &self.symbols_path {
+      Some(path) => format!("{} symbols from {}", self.symbols.len(), path.display()),
+      None => String::from("No symbols loaded"),
+    };
+    let symbol_controls = row![
+      button(text("Load symbols")).on_press(BreakpointsPanelMessage::LoadSymbolsRequested),
+      button(text("Clear")).on_press(BreakpointsPanelMessage::ClearSymbols),
+    ]
+    .spacing(5);
+
+    let new_breakpoint = row![
+      text_input("Label or $address", &self.new_breakpoint)
+        .on_input(BreakpointsPanelMessage::NewBreakpointChanged)
+        .on_submit(BreakpointsPanelMessage::AddBreakpoint),
+      button(text("Add")).on_press(BreakpointsPanelMessage::AddBreakpoint),
+    ]
+    .spacing(5);
+
+    let breakpoints = Column::with_children(
+      self
+        .breakpoints
+        .iter()
+        .enumerate()
+        .map(|(index, breakpoint)| {
+          row![
+            button(text("X")).on_press(BreakpointsPanelMessage::RemoveBreakpoint(index)),
+            text(breakpoint.to_string()).font(Font::MONOSPACE),
+          ]
+          .spacing(10)
+          .align_items(Alignment::Center)
+          .into()
+        })
+        .collect(),
+    )
+    .spacing(5);
+
+    column![
+      text(symbols_status),
+      symbol_controls,
+      new_breakpoint,
+      text(self.error.as_deref().unwrap_or_default()),
+      breakpoints,
+    ]
+    .spacing(10)
+    .into()
+  }
+}
@@ -0,0 +1,151 @@
+use iced::{
+  widget::{button, checkbox, column, row, scrollable, text, text_input, Column},
+  Element, Length,
+};
+use native_dialog::FileDialog;
+
+use crate::cheats::{load_cheat_list, save_cheat_list, Cheat};
+
+#[derive(Debug, Clone)]
+pub enum CheatsPanelMessage {
+  NewCodeChanged(String),
+  NewDescriptionChanged(String),
+  AddCheat,
+  CheatToggled(usize, bool),
+  RemoveCheat(usize),
+  LoadRequested,
+  SaveRequested,
+}
+
+#[derive(Default)]
+pub struct CheatsPanel {
+  cheats: Vec<Cheat>,
+  new_code: String,
+  new_description: String,
+  error: Option<String>,
+}
+
+impl CheatsPanel {
+  /// Returns the updated cheat list when it has changed and needs to be sent to the emulator.
+  pub fn update(&mut self, message: CheatsPanelMessage) -> Option<Vec<Cheat>> {
+    match message {
+      CheatsPanelMessage::NewCodeChanged(code) => {
+        self.new_code = code;
+        None
+      }
+      CheatsPanelMessage::NewDescriptionChanged(description) => {
+        self.new_description = description;
+        None
+      }
+      CheatsPanelMessage::AddCheat => match Cheat::new(&self.new_code, &self.new_description) {
+        Ok(cheat) => {
+          self.cheats.push(cheat);
+          self.new_code.clear();
+          self.new_description.clear();
+          self.error = None;
+          Some(self.cheats.clone())
+        }
+        Err(err) => {
+          self.error = Some(err.to_string());
+          None
+        }
+      },
+      CheatsPanelMessage::CheatToggled(index, enabled) => {
+        let cheat = self.cheats.get_mut(index)?;
+        cheat.enabled = enabled;
+        Some(self.cheats.clone())
+      }
+      CheatsPanelMessage::RemoveCheat(index) => {
+        if index >= self.cheats.len() {
+          return None;
+        }
+
+        self.cheats.remove(index);
+        Some(self.cheats.clone())
+      }
+      CheatsPanelMessage::LoadRequested => {
+        let Ok(Some(path)) = FileDialog::new()
+          .add_filter("Cheat list", &["cht"])
+          .set_title("Load cheat list")
+          .show_open_single_file()
+        else {
+          return None;
+        };
+
+        match load_cheat_list(&path) {
+          Ok(cheats) => {
+            self.cheats = cheats;
+            self.error = None;
+            Some(self.cheats.clone())
+          }
+          Err(err) => {
+            self.error = Some(err.to_string());
+            None
+          }
+        }
+      }
+      CheatsPanelMessage::SaveRequested => {
+        if let Ok(Some(path)) = FileDialog::new()
+          .add_filter("Cheat list", &["cht"])
+          .set_title("Save cheat list")
+          .show_save_single_file()
+        {
+          self.error = save_cheat_list(&path, &self.cheats)
+            .err()
+            .map(|err| err.to_string());
+        }
+
+        None
+      }
+    }
+  }
+
+  pub fn view(&self) -> Element<'_, CheatsPanelMessage> {
+    let file_buttons = row![
+      button(text("Load")).on_press(CheatsPanelMessage::LoadRequested),
+      button(text("Save")).on_press(CheatsPanelMessage::SaveRequested),
+    ]
+    .spacing(5);
+
+    let new_cheat_row = row![
+      text_input("Code", &self.new_code)
+        .on_input(CheatsPanelMessage::NewCodeChanged)
+        .on_submit(CheatsPanelMessage::AddCheat)
+        .width(Length::FillPortion(1)),
+      text_input("Description", &self.new_description)
+        .on_input(CheatsPanelMessage::NewDescriptionChanged)
+        .on_submit(CheatsPanelMessage::AddCheat)
+        .width(Length::FillPortion(2)),
+      button(text("Add")).on_press(CheatsPanelMessage::AddCheat),
+    ]
+    .spacing(5);
+
+    let cheat_rows = self
+      .cheats
+      .iter()
+      .enumerate()
+      .map(|(index, cheat)| {
+        row![
+          checkbox(
+            format!("{} {}", cheat.code_text, cheat.description),
+            cheat.enabled,
+            move |enabled| CheatsPanelMessage::CheatToggled(index, enabled)
+          )
+          .width(Length::Fill),
+          button(text("Remove")).on_press(CheatsPanelMessage::RemoveCheat(index)),
+        ]
+        .spacing(5)
+        .into()
+      })
+      .collect::<Vec<_>>();
+
+    column![
+      file_buttons,
+      new_cheat_row,
+      text(self.error.as_deref().unwrap_or_default()),
+      scrollable(Column::with_children(cheat_rows).spacing(5)),
+    ]
+    .spacing(10)
+    .into()
+  }
+}
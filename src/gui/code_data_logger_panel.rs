@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use iced::{
+  widget::{button, column, row, text},
+  Element,
+};
+use native_dialog::FileDialog;
+
+use crate::{debugger::CDLFile, emulator::EmulationInboundMessage};
+
+#[derive(Debug, Clone)]
+pub enum CodeDataLoggerPanelMessage {
+  LoggingToggled,
+  Clear,
+  Refresh,
+  SaveRequested,
+}
+
+#[derive(Default)]
+pub struct CodeDataLoggerPanel {
+  logging: bool,
+  log: Option<CDLFile>,
+  // where to write the log once the emulator sends it back
+  pending_save_path: Option<PathBuf>,
+  status: Option<String>,
+}
+
+impl CodeDataLoggerPanel {
+  pub fn log_captured(&mut self, log: CDLFile) {
+    if let Some(path) = self.pending_save_path.take() {
+      self.status = Some(match log.save(&path) {
+        Ok(()) => format!("Saved {}", path.display()),
+        Err(err) => err.to_string(),
+      });
+    }
+
+    self.log = Some(log);
+  }
+
+  pub fn update(&mut self, message: CodeDataLoggerPanelMessage) -> Option<EmulationInboundMessage> {
+    match message {
+      CodeDataLoggerPanelMessage::LoggingToggled => {
+        self.logging = !self.logging;
+        Some(EmulationInboundMessage::CodeDataLoggingToggled(
+          self.logging,
+        ))
+      }
+      CodeDataLoggerPanelMessage::Clear => {
+        self.log = None;
+        Some(EmulationInboundMessage::CodeDataLogCleared)
+      }
+      CodeDataLoggerPanelMessage::Refresh => Some(EmulationInboundMessage::CodeDataLogRequested),
+      CodeDataLoggerPanelMessage::SaveRequested => {
+        let Ok(Some(path)) = FileDialog::new()
+          .add_filter("Code/Data Log", &["cdl"])
+          .set_title("Save code/data log")
+          .show_save_single_file()
+        else {
+          return None;
+        };
+
+        self.pending_save_path = Some(path);
+        Some(EmulationInboundMessage::CodeDataLogRequested)
+      }
+    }
+  }
+
+  pub fn view(&self) -> Element<'_, CodeDataLoggerPanelMessage> {
+    let buttons = row![
+      button(text(if self.logging { "Stop" } else { "Start" }))
+        .on_press(CodeDataLoggerPanelMessage::LoggingToggled),
+      button(text("Clear")).on_press(CodeDataLoggerPanelMessage::Clear),
+      button(text("Refresh")).on_press(CodeDataLoggerPanelMessage::Refresh),
+      button(text("Save .cdl")).on_press(CodeDataLoggerPanelMessage::SaveRequested),
+    ]
+    .spacing(5);
+
+    let stats = match &self.log {
+      Some(log) => {
+        let mut stats = format!(
+          "PRG: {} code, {} data of {} bytes",
+          log.prg_code_bytes(),
+          log.prg_data_bytes(),
+          log.prg.len()
+        );
+        if !log.chr.is_empty() {
+          stats.push_str(&format!(
+            "\nCHR: {} drawn of {} bytes",
+            log.chr_drawn_bytes(),
+            log.chr.len()
+          ));
+        }
+        stats
+      }
+      None => String::from("Press Refresh to see coverage"),
+    };
+
+    let mut content = column![buttons, text(stats)].spacing(10);
+    if let Some(status) = &self.status {
+      content = content.push(text(status));
+    }
+
+    content.into()
+  }
+}
@@ -1,23 +1,104 @@
-use std::sync::{Arc, RwLock};
+use iced::{
+  advanced::{
+    image::{self, Handle},
+    layout, mouse, renderer,
+    widget::Tree,
+    Layout, Widget,
+  },
+  Element, Length, Point, Rectangle, Size,
+};
 
-use crate::ppu::{Pixbuf, PIXEL_BUFFER_HEIGHT, PIXEL_BUFFER_WIDTH};
+use crate::{
+  debugger::RgbaImage,
+  ppu::{
+    triple_buffer, TripleBufferReader, TripleBufferWriter, PIXEL_BUFFER_HEIGHT, PIXEL_BUFFER_WIDTH,
+  },
+  settings::DisplaySettings,
+};
 
 pub struct CRTScreen {
-  pub pixbuf: Arc<RwLock<Pixbuf>>,
+  frame_reader: TripleBufferReader<RgbaImage>,
+  handle: Handle,
 }
 
 impl CRTScreen {
-  pub fn new() -> Self {
-    Self {
-      pixbuf: Arc::new(RwLock::new(Pixbuf::new())),
-    }
-  }
+  /// Also returns the writer the emulator publishes its frames through.
+  pub fn new(display: &DisplaySettings) -> (Self, TripleBufferWriter<RgbaImage>) {
+    let (frame_writer, mut frame_reader) =
+      triple_buffer(RgbaImage::new(PIXEL_BUFFER_WIDTH, PIXEL_BUFFER_HEIGHT));
+    let handle = Self::image_handle(&frame_reader.read(), display);
 
-  pub fn image_handle(&self) -> iced::advanced::image::Handle {
-    iced::advanced::image::Handle::from_pixels(
-      PIXEL_BUFFER_WIDTH,
-      PIXEL_BUFFER_HEIGHT,
-      self.pixbuf.read().unwrap().data,
+    (
+      Self {
+        frame_reader,
+        handle,
+      },
+      frame_writer,
     )
   }
+
+  /// Picks up the latest frame, or redraws the current one after `display` changes.
+  pub fn update(&mut self, display: &DisplaySettings) {
+    self.handle = Self::image_handle(&self.frame_reader.read(), display);
+  }
+
+  /// The picture with the overscan cropped off.
+  fn image_handle(image: &RgbaImage, display: &DisplaySettings) -> Handle {
+    let image = display.overscan.crop(image);
+    Handle::from_pixels(image.width, image.height, image.data)
+  }
+
+  pub fn view<'a, Message>(&self, display: DisplaySettings) -> Element<'a, Message> {
+    Element::new(CRTScreenView {
+      handle: self.handle.clone(),
+      display,
+    })
+  }
+}
+
+/// Fills the space it's given and draws the picture in the middle, sized by the display settings.
+struct CRTScreenView {
+  handle: Handle,
+  display: DisplaySettings,
+}
+
+impl<Message, Renderer> Widget<Message, Renderer> for CRTScreenView
+where
+  Renderer: image::Renderer<Handle = Handle>,
+{
+  fn width(&self) -> Length {
+    Length::Fill
+  }
+
+  fn height(&self) -> Length {
+    Length::Fill
+  }
+
+  fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+    layout::Node::new(limits.width(Length::Fill).height(Length::Fill).max())
+  }
+
+  fn draw(
+    &self,
+    _state: &Tree,
+    renderer: &mut Renderer,
+    _theme: &Renderer::Theme,
+    _style: &renderer::Style,
+    layout: Layout<'_>,
+    _cursor: mouse::Cursor,
+    _viewport: &Rectangle,
+  ) {
+    let bounds = layout.bounds();
+    let (width, height) = self.display.screen_size(bounds.width, bounds.height);
+
+    // keep it on whole pixels so integer scaling stays crisp
+    let top_left = Point::new(
+      (bounds.x + (bounds.width - width) / 2.0).round(),
+      (bounds.y + (bounds.height - height) / 2.0).round(),
+    );
+    renderer.draw(
+      self.handle.clone(),
+      Rectangle::new(top_left, Size::new(width, height)),
+    );
+  }
 }
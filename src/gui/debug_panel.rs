@@ -0,0 +1,31 @@
+use strum::{EnumIter, IntoStaticStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr, EnumIter)]
+pub enum DebugPanel {
+  Settings,
+  Cheats,
+  #[strum(serialize = "RAM Search")]
+  RamSearch,
+  Memory,
+  #[strum(serialize = "Code/Data Log")]
+  CodeDataLogger,
+  #[strum(serialize = "Trace Log")]
+  TraceLogger,
+  Breakpoints,
+  Source,
+  #[strum(serialize = "Pattern Tables")]
+  PatternTables,
+  Nametables,
+  Palettes,
+  #[allow(clippy::upper_case_acronyms)]
+  OAM,
+}
+
+impl DebugPanel {
+  pub fn shows_ppu_state(&self) -> bool {
+    matches!(
+      self,
+      DebugPanel::PatternTables | DebugPanel::Nametables | DebugPanel::Palettes | DebugPanel::OAM
+    )
+  }
+}
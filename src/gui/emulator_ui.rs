@@ -1,4 +1,5 @@
 use std::{
+  path::PathBuf,
   sync::Arc,
   time::{Duration, Instant},
 };
@@ -6,51 +7,129 @@ use std::{
 use iced::{
   executor,
   theme::Palette,
-  widget::{column, image, row, text, vertical_space},
-  Application, Color, Command, Font, Length, Subscription, Theme,
+  widget::{button, column, container, row, scrollable, text, vertical_space, Column},
+  window, Application, Color, Command, Font, Length, Subscription, Theme,
 };
 use smol::channel::{Receiver, Sender};
+use strum::IntoEnumIterator;
 
 use crate::{
+  debugger::{CDLFile, MemoryPage, MemorySnapshot, PPUSnapshot},
   emulator::{
-    EmulationInboundMessage, EmulationOutboundMessage, EmulatorBuilder, EmulatorState, MachineState,
+    EmulationInboundMessage, EmulationOutboundMessage, EmulationSpeed, EmulatorBuilder,
+    EmulatorState, MachineState,
   },
   nes::ControllerButton,
+  ppu::NTSCFilter,
+  settings::GameSettings,
 };
 
-use super::{keys::handle_key_event, run_emulator, CRTScreen};
+use super::{
+  keys::handle_key_event, run_emulator::run_emulator, BreakpointsPanel, BreakpointsPanelMessage,
+  CRTScreen, CheatsPanel, CheatsPanelMessage, CodeDataLoggerPanel, CodeDataLoggerPanelMessage,
+  DebugPanel, MemoryViewerPanel, MemoryViewerPanelMessage, PPUViewerPanel, PPUViewerPanelMessage,
+  RamSearchPanel, RamSearchPanelMessage, SettingsPanel, SettingsPanelMessage, SourcePanel,
+  SourcePanelMessage, TraceLoggerPanel, TraceLoggerPanelMessage,
+};
 
 const PIXEL_NES_FONT: Font = Font::with_name("Pixel NES");
 
 pub struct EmulatorUIFlags {
   emulator_builder: Box<dyn EmulatorBuilder>,
+  rom_path: PathBuf,
+  record: bool,
 }
 
 impl EmulatorUIFlags {
-  pub fn new(emulator_builder: Box<dyn EmulatorBuilder>) -> Self {
-    Self { emulator_builder }
+  /// With `record` set, recording starts as soon as the game does.
+  pub fn new(emulator_builder: Box<dyn EmulatorBuilder>, rom_path: PathBuf, record: bool) -> Self {
+    Self {
+      emulator_builder,
+      rom_path,
+      record,
+    }
   }
 }
 
 #[derive(Debug, Clone)]
 pub enum EmulatorUIMessage {
+  CheatsPanel(CheatsPanelMessage),
+  SettingsPanel(SettingsPanelMessage),
   ControllerButtonChanged(ControllerButton, bool),
+  DebugPanelToggled(DebugPanel),
   EmulatorStateChangeRequested(EmulatorState),
+  RewindToggled(bool),
+  SpeedChangeRequested(EmulationSpeed),
+  /// Steps to the next speed up (`true`) or down.
+  SpeedStepped(bool),
+  FastForwardToggled(bool),
+  FrameSkipToggled,
+  NTSCFilterToggled,
+  /// Makes the NTSC filter sharper (`true`) or softer.
+  NTSCSharpnessStepped(bool),
+  ScreenshotRequested,
+  ScreenshotSaved(Result<PathBuf, String>),
+  RecordingToggled,
+  RecordingStarted(Result<PathBuf, String>),
+  RecordingStopped(Result<PathBuf, String>),
+  FullscreenToggled,
   FontLoaded(Result<(), iced::font::Error>),
   FrameReady,
   MachineStateChanged(MachineState),
+  MemoryPageCaptured(MemoryPage),
+  CodeDataLoggerPanel(CodeDataLoggerPanelMessage),
+  CodeDataLogCaptured(CDLFile),
+  TraceLoggerPanel(TraceLoggerPanelMessage),
+  TraceCaptured(Vec<String>),
+  BreakpointsPanel(BreakpointsPanelMessage),
+  SourcePanel(SourcePanelMessage),
+  MemorySnapshotCaptured(MemorySnapshot),
+  MemoryViewerPanel(MemoryViewerPanelMessage),
+  PPUSnapshotCaptured(Box<PPUSnapshot>),
+  PPUViewerPanel(PPUViewerPanelMessage),
+  RamSearchPanel(RamSearchPanelMessage),
   Shutdown,
 }
 
 pub struct EmulatorUI {
   crt_screen: CRTScreen,
+  rom_path: PathBuf,
+  status: Option<String>,
   last_frame_duration: Duration,
   last_frame: Instant,
   last_machine_state: MachineState,
+  speed: EmulationSpeed,
+  frame_skip: bool,
+  ntsc_enabled: bool,
+  ntsc_filter: NTSCFilter,
+  active_debug_panel: Option<DebugPanel>,
+  settings_panel: SettingsPanel,
+  cheats_panel: CheatsPanel,
+  ram_search_panel: RamSearchPanel,
+  ppu_viewer_panel: PPUViewerPanel,
+  memory_viewer_panel: MemoryViewerPanel,
+  code_data_logger_panel: CodeDataLoggerPanel,
+  trace_logger_panel: TraceLoggerPanel,
+  breakpoints_panel: BreakpointsPanel,
+  source_panel: SourcePanel,
   inbound_sender: Sender<EmulationInboundMessage>,
   outbound_receiver: Arc<Receiver<EmulationOutboundMessage>>,
 }
 
+fn window_mode(fullscreen: bool) -> Command<EmulatorUIMessage> {
+  window::change_mode(if fullscreen {
+    window::Mode::Fullscreen
+  } else {
+    window::Mode::Windowed
+  })
+}
+
+impl EmulatorUI {
+  fn send_inbound_message(&self, message: EmulationInboundMessage) {
+    smol::block_on(async { self.inbound_sender.send(message).await }).unwrap();
+  }
+}
+
 impl Application for EmulatorUI {
   type Executor = executor::Default;
   type Message = EmulatorUIMessage;
@@ -58,32 +137,70 @@ impl Application for EmulatorUI {
   type Theme = Theme;
 
   fn new(flags: EmulatorUIFlags) -> (EmulatorUI, Command<Self::Message>) {
-    let crt_screen = CRTScreen::new();
-    let pixbuf = crt_screen.pixbuf.clone();
+    let mut settings_panel = SettingsPanel::new(GameSettings::path_for_rom(&flags.rom_path));
+    let (crt_screen, frame_writer) = CRTScreen::new(&settings_panel.display());
     let (inbound_sender, inbound_receiver) = smol::channel::unbounded();
     let (outbound_sender, outbound_receiver) = smol::channel::unbounded();
+    run_emulator(
+      flags.emulator_builder,
+      frame_writer,
+      inbound_receiver,
+      outbound_sender,
+    );
+
+    if let Some(message) = settings_panel.palette_message() {
+      smol::block_on(inbound_sender.send(message)).unwrap();
+    }
+    smol::block_on(inbound_sender.send(settings_panel.frame_blend_message())).unwrap();
+    smol::block_on(inbound_sender.send(settings_panel.unlimited_sprites_message())).unwrap();
+    let fullscreen = settings_panel.display().fullscreen;
+    // the info column that shows the machine state is hidden in fullscreen
+    smol::block_on(
+      inbound_sender.send(EmulationInboundMessage::MachineStatesRequested(!fullscreen)),
+    )
+    .unwrap();
+    if flags.record {
+      smol::block_on(
+        inbound_sender.send(EmulationInboundMessage::RecordingToggled(
+          flags.rom_path.clone(),
+        )),
+      )
+      .unwrap();
+    }
 
     (
       EmulatorUI {
         crt_screen,
+        rom_path: flags.rom_path,
+        status: None,
         last_frame_duration: Duration::from_millis(1000),
         last_frame: Instant::now(),
         last_machine_state: MachineState::default(),
+        speed: EmulationSpeed::default(),
+        frame_skip: true,
+        ntsc_enabled: false,
+        ntsc_filter: NTSCFilter::default(),
+        active_debug_panel: None,
+        settings_panel,
+        cheats_panel: CheatsPanel::default(),
+        ram_search_panel: RamSearchPanel::default(),
+        ppu_viewer_panel: PPUViewerPanel::default(),
+        memory_viewer_panel: MemoryViewerPanel::default(),
+        code_data_logger_panel: CodeDataLoggerPanel::default(),
+        trace_logger_panel: TraceLoggerPanel::default(),
+        breakpoints_panel: BreakpointsPanel::default(),
+        source_panel: SourcePanel::default(),
         inbound_sender,
         outbound_receiver: Arc::new(outbound_receiver),
       },
       Command::batch([
-        Command::perform(
-          run_emulator::run_emulator(
-            flags.emulator_builder,
-            pixbuf,
-            inbound_receiver,
-            outbound_sender,
-          ),
-          |_| EmulatorUIMessage::FrameReady,
-        ),
         iced::font::load(include_bytes!("./Pixel_NES.otf").as_slice())
           .map(EmulatorUIMessage::FontLoaded),
+        if fullscreen {
+          window_mode(true)
+        } else {
+          Command::none()
+        },
       ]),
     )
   }
@@ -106,36 +223,215 @@ impl Application for EmulatorUI {
     match message {
       EmulatorUIMessage::FontLoaded(_) => Command::none(),
       EmulatorUIMessage::ControllerButtonChanged(button, pressed) => {
-        smol::block_on(async {
-          self
-            .inbound_sender
-            .send(EmulationInboundMessage::ControllerButtonChanged(
-              button, pressed,
-            ))
-            .await
-        })
-        .unwrap();
+        self.send_inbound_message(EmulationInboundMessage::ControllerButtonChanged(
+          button, pressed,
+        ));
         Command::none()
       }
       EmulatorUIMessage::EmulatorStateChangeRequested(new_state) => {
-        smol::block_on(async {
+        self.send_inbound_message(EmulationInboundMessage::EmulatorStateChangeRequested(
+          new_state,
+        ));
+        Command::none()
+      }
+      EmulatorUIMessage::RewindToggled(rewinding) => {
+        self.send_inbound_message(EmulationInboundMessage::RewindToggled(rewinding));
+        Command::none()
+      }
+      EmulatorUIMessage::SpeedChangeRequested(speed) => {
+        self.speed = speed;
+        self.send_inbound_message(EmulationInboundMessage::SpeedChanged(speed));
+        Command::none()
+      }
+      EmulatorUIMessage::SpeedStepped(faster) => {
+        self.speed = if faster {
+          self.speed.faster()
+        } else {
+          self.speed.slower()
+        };
+        self.send_inbound_message(EmulationInboundMessage::SpeedChanged(self.speed));
+        Command::none()
+      }
+      EmulatorUIMessage::FastForwardToggled(fast_forward) => {
+        self.send_inbound_message(EmulationInboundMessage::FastForwardToggled(fast_forward));
+        Command::none()
+      }
+      EmulatorUIMessage::FrameSkipToggled => {
+        self.frame_skip = !self.frame_skip;
+        self.send_inbound_message(EmulationInboundMessage::FrameSkipToggled(self.frame_skip));
+        Command::none()
+      }
+      EmulatorUIMessage::NTSCFilterToggled => {
+        self.ntsc_enabled = !self.ntsc_enabled;
+        self.send_inbound_message(EmulationInboundMessage::NTSCFilterChanged(
+          self.ntsc_enabled.then_some(self.ntsc_filter),
+        ));
+        Command::none()
+      }
+      EmulatorUIMessage::NTSCSharpnessStepped(sharper) => {
+        let step = if sharper { 0.25 } else { -0.25 };
+        self.ntsc_filter.sharpness = (self.ntsc_filter.sharpness + step).clamp(-1.0, 1.0);
+        self.send_inbound_message(EmulationInboundMessage::NTSCFilterChanged(
+          self.ntsc_enabled.then_some(self.ntsc_filter),
+        ));
+        Command::none()
+      }
+      EmulatorUIMessage::ScreenshotRequested => {
+        self.send_inbound_message(EmulationInboundMessage::ScreenshotRequested(
+          self.rom_path.clone(),
+          self.settings_panel.screenshot_options(),
+        ));
+        Command::none()
+      }
+      EmulatorUIMessage::ScreenshotSaved(result) => {
+        self.status = Some(match result {
+          Ok(path) => format!("Saved {}", path.display()),
+          Err(err) => format!("Screenshot failed: {}", err),
+        });
+        Command::none()
+      }
+      EmulatorUIMessage::RecordingToggled => {
+        self.send_inbound_message(EmulationInboundMessage::RecordingToggled(
+          self.rom_path.clone(),
+        ));
+        Command::none()
+      }
+      EmulatorUIMessage::RecordingStarted(result) => {
+        self.status = Some(match result {
+          Ok(path) => format!("Recording to {}", path.display()),
+          Err(err) => format!("Couldn't start recording: {}", err),
+        });
+        Command::none()
+      }
+      EmulatorUIMessage::RecordingStopped(result) => {
+        self.status = Some(match result {
+          Ok(path) => format!("Saved {}", path.display()),
+          Err(err) => format!("Recording failed: {}", err),
+        });
+        Command::none()
+      }
+      EmulatorUIMessage::DebugPanelToggled(panel) => {
+        self.active_debug_panel = if self.active_debug_panel == Some(panel) {
+          None
+        } else {
+          Some(panel)
+        };
+        self.send_inbound_message(EmulationInboundMessage::MemorySnapshotsRequested(
+          self.active_debug_panel == Some(DebugPanel::RamSearch),
+        ));
+        self.send_inbound_message(EmulationInboundMessage::PPUSnapshotsRequested(
           self
-            .inbound_sender
-            .send(EmulationInboundMessage::EmulatorStateChangeRequested(
-              new_state,
-            ))
-            .await
-        })
-        .unwrap();
+            .active_debug_panel
+            .is_some_and(|panel| panel.shows_ppu_state()),
+        ));
+        self.send_inbound_message(EmulationInboundMessage::MemoryPageRequested(
+          (self.active_debug_panel == Some(DebugPanel::Memory))
+            .then(|| self.memory_viewer_panel.page_request()),
+        ));
+        self.send_inbound_message(EmulationInboundMessage::TraceRequested(
+          (self.active_debug_panel == Some(DebugPanel::TraceLogger))
+            .then(|| self.trace_logger_panel.trace_request()),
+        ));
+        Command::none()
+      }
+      EmulatorUIMessage::FullscreenToggled => {
+        let fullscreen = !self.settings_panel.display().fullscreen;
+        self.update(EmulatorUIMessage::SettingsPanel(
+          SettingsPanelMessage::FullscreenToggled(fullscreen),
+        ))
+      }
+      EmulatorUIMessage::SettingsPanel(message) => {
+        let was_fullscreen = self.settings_panel.display().fullscreen;
+        if let Some(message) = self.settings_panel.update(message) {
+          self.send_inbound_message(message);
+        }
+
+        let display = self.settings_panel.display();
+        self.crt_screen.update(&display);
+        if display.fullscreen != was_fullscreen {
+          self.send_inbound_message(EmulationInboundMessage::MachineStatesRequested(
+            !display.fullscreen,
+          ));
+          window_mode(display.fullscreen)
+        } else {
+          Command::none()
+        }
+      }
+      EmulatorUIMessage::CheatsPanel(message) => {
+        if let Some(cheats) = self.cheats_panel.update(message) {
+          self.send_inbound_message(EmulationInboundMessage::CheatsChanged(cheats));
+        }
+        Command::none()
+      }
+      EmulatorUIMessage::RamSearchPanel(message) => {
+        self.ram_search_panel.update(message);
+        Command::none()
+      }
+      EmulatorUIMessage::MemorySnapshotCaptured(snapshot) => {
+        self.ram_search_panel.snapshot_captured(snapshot);
+        Command::none()
+      }
+      EmulatorUIMessage::PPUViewerPanel(message) => {
+        self.ppu_viewer_panel.update(message);
+        Command::none()
+      }
+      EmulatorUIMessage::PPUSnapshotCaptured(snapshot) => {
+        self.ppu_viewer_panel.snapshot_captured(*snapshot);
+        Command::none()
+      }
+      EmulatorUIMessage::MemoryViewerPanel(message) => {
+        if let Some(message) = self.memory_viewer_panel.update(message) {
+          self.send_inbound_message(message);
+        }
+        Command::none()
+      }
+      EmulatorUIMessage::MemoryPageCaptured(page) => {
+        self.memory_viewer_panel.page_captured(page);
+        Command::none()
+      }
+      EmulatorUIMessage::CodeDataLoggerPanel(message) => {
+        if let Some(message) = self.code_data_logger_panel.update(message) {
+          self.send_inbound_message(message);
+        }
+        Command::none()
+      }
+      EmulatorUIMessage::CodeDataLogCaptured(log) => {
+        self.code_data_logger_panel.log_captured(log);
+        Command::none()
+      }
+      EmulatorUIMessage::TraceLoggerPanel(message) => {
+        if let Some(message) = self.trace_logger_panel.update(message) {
+          self.send_inbound_message(message);
+        }
+        Command::none()
+      }
+      EmulatorUIMessage::TraceCaptured(lines) => {
+        self.trace_logger_panel.trace_captured(lines);
+        Command::none()
+      }
+      EmulatorUIMessage::BreakpointsPanel(message) => {
+        if let Some(message) = self.breakpoints_panel.update(message) {
+          self.send_inbound_message(message);
+        }
+        Command::none()
+      }
+      EmulatorUIMessage::SourcePanel(message) => {
+        if let Some(message) = self.source_panel.update(message) {
+          self.send_inbound_message(message);
+        }
         Command::none()
       }
       EmulatorUIMessage::FrameReady => {
+        self.crt_screen.update(&self.settings_panel.display());
         let now = Instant::now();
         self.last_frame_duration = now - self.last_frame;
         self.last_frame = now;
         Command::none()
       }
       EmulatorUIMessage::MachineStateChanged(machine_state) => {
+        self
+          .source_panel
+          .location_changed(machine_state.source_location);
         self.last_machine_state = machine_state;
         Command::none()
       }
@@ -148,8 +444,10 @@ impl Application for EmulatorUI {
   fn subscription(&self) -> Subscription<EmulatorUIMessage> {
     let outbound_receiver = self.outbound_receiver.clone();
     iced::Subscription::batch([
-      iced::subscription::events_with(|event, _status| match event {
-        iced::Event::Keyboard(event) => handle_key_event(event),
+      iced::subscription::events_with(|event, status| match (event, status) {
+        // keys captured by a focused widget (e.g. a text input in a debug panel) shouldn't
+        // control the emulator
+        (iced::Event::Keyboard(event), iced::event::Status::Ignored) => handle_key_event(event),
         _ => None,
       }),
       iced::subscription::unfold("emulator-outbound", (), move |()| {
@@ -165,6 +463,30 @@ impl Application for EmulatorUI {
             EmulationOutboundMessage::MachineStateChanged(state) => {
               EmulatorUIMessage::MachineStateChanged(state)
             }
+            EmulationOutboundMessage::MemorySnapshotCaptured(snapshot) => {
+              EmulatorUIMessage::MemorySnapshotCaptured(snapshot)
+            }
+            EmulationOutboundMessage::MemoryPageCaptured(page) => {
+              EmulatorUIMessage::MemoryPageCaptured(page)
+            }
+            EmulationOutboundMessage::CodeDataLogCaptured(log) => {
+              EmulatorUIMessage::CodeDataLogCaptured(log)
+            }
+            EmulationOutboundMessage::TraceCaptured(lines) => {
+              EmulatorUIMessage::TraceCaptured(lines)
+            }
+            EmulationOutboundMessage::PPUSnapshotCaptured(snapshot) => {
+              EmulatorUIMessage::PPUSnapshotCaptured(snapshot)
+            }
+            EmulationOutboundMessage::ScreenshotSaved(result) => {
+              EmulatorUIMessage::ScreenshotSaved(result)
+            }
+            EmulationOutboundMessage::RecordingStarted(result) => {
+              EmulatorUIMessage::RecordingStarted(result)
+            }
+            EmulationOutboundMessage::RecordingStopped(result) => {
+              EmulatorUIMessage::RecordingStopped(result)
+            }
             EmulationOutboundMessage::Shutdown => EmulatorUIMessage::Shutdown,
           };
 
@@ -179,9 +501,13 @@ impl Application for EmulatorUI {
       text(format!("{:.02} FPS", 1.0 / self.last_frame_duration.as_secs_f32()).as_str())
         .font(PIXEL_NES_FONT)
         .size(20);
-    let state_text = text(<&'static str>::from(self.last_machine_state.emulator_state).to_string())
-      .font(PIXEL_NES_FONT)
-      .size(20);
+    let state_text = text(format!(
+      "{} {}",
+      <&'static str>::from(self.last_machine_state.emulator_state),
+      self.last_machine_state.speed
+    ))
+    .font(PIXEL_NES_FONT)
+    .size(20);
     let machine = &self.last_machine_state;
     let registers_text = text(
       format!(
@@ -223,21 +549,104 @@ impl Application for EmulatorUI {
     .font(PIXEL_NES_FONT)
     .size(20);
 
+    let frame_text = text(
+      format!(
+        "Frame {}\nLag {}{}\nPolls {}",
+        machine.frame_count,
+        machine.lag_frame_count,
+        if machine.lag_frame { " LAG" } else { "" },
+        machine.input_polls,
+      )
+      .as_str(),
+    )
+    .font(PIXEL_NES_FONT)
+    .size(20);
+
+    let status_text = text(self.status.as_deref().unwrap_or_default());
+
+    let debug_panel_buttons = Column::with_children(
+      DebugPanel::iter()
+        .map(|panel| {
+          button(text(<&'static str>::from(panel)))
+            .on_press(EmulatorUIMessage::DebugPanelToggled(panel))
+            .into()
+        })
+        .collect(),
+    )
+    .spacing(5);
+
     let info_column = column![
       fps_text,
       state_text,
       registers_text,
       cpu_status_text,
       ppu_status_text,
+      frame_text,
+      status_text,
       vertical_space(10),
+      debug_panel_buttons,
     ]
     .width(Length::FillPortion(1));
 
-    let screen_view = image(self.crt_screen.image_handle())
+    let display = self.settings_panel.display();
+    let screen_view = container(self.crt_screen.view(display))
       .width(Length::FillPortion(4))
       .height(Length::Fill);
 
-    let layout = row![screen_view, info_column].spacing(20);
+    if display.fullscreen {
+      return screen_view.into();
+    }
+
+    let mut layout = row![screen_view, info_column].spacing(20);
+
+    if let Some(panel) = self.active_debug_panel {
+      let panel_view = match panel {
+        DebugPanel::Settings => self
+          .settings_panel
+          .view()
+          .map(EmulatorUIMessage::SettingsPanel),
+        DebugPanel::Cheats => self.cheats_panel.view().map(EmulatorUIMessage::CheatsPanel),
+        DebugPanel::RamSearch => self
+          .ram_search_panel
+          .view()
+          .map(EmulatorUIMessage::RamSearchPanel),
+        DebugPanel::Memory => self
+          .memory_viewer_panel
+          .view()
+          .map(EmulatorUIMessage::MemoryViewerPanel),
+        DebugPanel::CodeDataLogger => self
+          .code_data_logger_panel
+          .view()
+          .map(EmulatorUIMessage::CodeDataLoggerPanel),
+        DebugPanel::TraceLogger => self
+          .trace_logger_panel
+          .view()
+          .map(EmulatorUIMessage::TraceLoggerPanel),
+        DebugPanel::Breakpoints => self
+          .breakpoints_panel
+          .view()
+          .map(EmulatorUIMessage::BreakpointsPanel),
+        DebugPanel::Source => self.source_panel.view().map(EmulatorUIMessage::SourcePanel),
+        DebugPanel::PatternTables => self
+          .ppu_viewer_panel
+          .pattern_tables_view()
+          .map(EmulatorUIMessage::PPUViewerPanel),
+        DebugPanel::Nametables => self
+          .ppu_viewer_panel
+          .name_tables_view()
+          .map(EmulatorUIMessage::PPUViewerPanel),
+        DebugPanel::Palettes => self
+          .ppu_viewer_panel
+          .palettes_view()
+          .map(EmulatorUIMessage::PPUViewerPanel),
+        DebugPanel::OAM => self
+          .ppu_viewer_panel
+          .oam_view()
+          .map(EmulatorUIMessage::PPUViewerPanel),
+      };
+
+      layout = layout.push(scrollable(panel_view).width(Length::FillPortion(2)));
+    }
 
     layout.into()
   }
@@ -1,6 +1,9 @@
 use iced::keyboard::{self, KeyCode};
 
-use crate::{emulator::EmulatorState, nes::ControllerButton};
+use crate::{
+  emulator::{EmulationSpeed, EmulatorState},
+  nes::ControllerButton,
+};
 
 use super::EmulatorUIMessage;
 
@@ -40,6 +43,23 @@ pub fn handle_key_event(event: iced::keyboard::Event) -> Option<EmulatorUIMessag
           KeyCode::I => Some(EmulatorUIMessage::EmulatorStateChangeRequested(
             EmulatorState::RunUntilNextInstruction,
           )),
+          KeyCode::L => Some(EmulatorUIMessage::EmulatorStateChangeRequested(
+            EmulatorState::RunUntilNextSourceLine,
+          )),
+          KeyCode::Backspace => Some(EmulatorUIMessage::RewindToggled(true)),
+          KeyCode::Tab => Some(EmulatorUIMessage::FastForwardToggled(true)),
+          KeyCode::Minus => Some(EmulatorUIMessage::SpeedStepped(false)),
+          KeyCode::Equals => Some(EmulatorUIMessage::SpeedStepped(true)),
+          KeyCode::Key0 => Some(EmulatorUIMessage::SpeedChangeRequested(
+            EmulationSpeed::Normal,
+          )),
+          KeyCode::K => Some(EmulatorUIMessage::FrameSkipToggled),
+          KeyCode::N => Some(EmulatorUIMessage::NTSCFilterToggled),
+          KeyCode::LBracket => Some(EmulatorUIMessage::NTSCSharpnessStepped(false)),
+          KeyCode::RBracket => Some(EmulatorUIMessage::NTSCSharpnessStepped(true)),
+          KeyCode::F9 => Some(EmulatorUIMessage::RecordingToggled),
+          KeyCode::F11 => Some(EmulatorUIMessage::FullscreenToggled),
+          KeyCode::F12 => Some(EmulatorUIMessage::ScreenshotRequested),
           _ => None,
         }
       }
@@ -47,8 +67,17 @@ pub fn handle_key_event(event: iced::keyboard::Event) -> Option<EmulatorUIMessag
     keyboard::Event::KeyReleased {
       key_code,
       modifiers: _,
-    } => key_code_to_controller_button(key_code)
-      .map(|button| EmulatorUIMessage::ControllerButtonChanged(button, false)),
+    } => {
+      if let Some(button) = key_code_to_controller_button(key_code) {
+        Some(EmulatorUIMessage::ControllerButtonChanged(button, false))
+      } else {
+        match key_code {
+          KeyCode::Backspace => Some(EmulatorUIMessage::RewindToggled(false)),
+          KeyCode::Tab => Some(EmulatorUIMessage::FastForwardToggled(false)),
+          _ => None,
+        }
+      }
+    }
     _ => None,
   }
 }
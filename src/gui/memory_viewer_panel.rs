@@ -0,0 +1,264 @@
+use iced::{
+  theme,
+  widget::{button, column, pick_list, row, text, text_input, Column, Row},
+  Color, Element, Font, Length,
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+  debugger::{MemoryPage, MemorySpace, MEMORY_PAGE_SIZE},
+  emulator::EmulationInboundMessage,
+};
+
+const BYTES_PER_ROW: usize = 16;
+// bytes stay highlighted for this many page updates (about half a second while running)
+const CHANGE_HIGHLIGHT_UPDATES: u8 = 30;
+const CHANGED_BYTE_COLOR: Color = Color::from_rgb(1.0, 0.4, 0.4);
+
+pub fn parse_hex(value: &str) -> Option<usize> {
+  let value = value.trim();
+  let value = value.strip_prefix('$').unwrap_or(value);
+  usize::from_str_radix(value, 16).ok()
+}
+
+#[derive(Debug, Clone)]
+pub enum MemoryViewerPanelMessage {
+  SpaceSelected(MemorySpace),
+  GotoAddressChanged(String),
+  GotoAddressSubmitted,
+  PreviousPage,
+  NextPage,
+  ByteSelected(usize),
+  EditValueChanged(String),
+  EditValueSubmitted,
+}
+
+#[derive(Default)]
+pub struct MemoryViewerPanel {
+  space: MemorySpace,
+  start: usize,
+  page: Option<MemoryPage>,
+  // page updates since each byte last changed
+  change_ages: Vec<u8>,
+  goto_address: String,
+  selected_addr: Option<usize>,
+  edit_value: String,
+  error: Option<String>,
+}
+
+impl MemoryViewerPanel {
+  pub fn page_request(&self) -> (MemorySpace, usize) {
+    (self.space, self.start)
+  }
+
+  pub fn page_captured(&mut self, page: MemoryPage) {
+    self.change_ages = match &self.page {
+      Some(previous) if previous.space == page.space && previous.start == page.start => page
+        .bytes
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+          if previous.bytes.get(index) != Some(value) {
+            0
+          } else {
+            self
+              .change_ages
+              .get(index)
+              .map_or(CHANGE_HIGHLIGHT_UPDATES, |age| age.saturating_add(1))
+          }
+        })
+        .collect(),
+      _ => vec![CHANGE_HIGHLIGHT_UPDATES; page.bytes.len()],
+    };
+
+    self.page = Some(page);
+  }
+
+  // the current page can still be from the previously selected space until the new one arrives
+  fn space_len(&self) -> Option<usize> {
+    self
+      .page
+      .as_ref()
+      .filter(|page| page.space == self.space)
+      .map(|page| page.space_len)
+      .or_else(|| self.space.fixed_len())
+  }
+
+  fn go_to(&mut self, addr: usize) -> Option<EmulationInboundMessage> {
+    let space_len = self.space_len().unwrap_or(usize::MAX);
+    if addr >= space_len {
+      self.error = Some(format!("Address ${:X} is out of range", addr));
+      return None;
+    }
+
+    self.start = addr - (addr % MEMORY_PAGE_SIZE);
+    self.error = None;
+    Some(EmulationInboundMessage::MemoryPageRequested(Some(
+      self.page_request(),
+    )))
+  }
+
+  pub fn update(&mut self, message: MemoryViewerPanelMessage) -> Option<EmulationInboundMessage> {
+    match message {
+      MemoryViewerPanelMessage::SpaceSelected(space) => {
+        self.space = space;
+        self.start = 0;
+        self.selected_addr = None;
+        Some(EmulationInboundMessage::MemoryPageRequested(Some(
+          self.page_request(),
+        )))
+      }
+      MemoryViewerPanelMessage::GotoAddressChanged(goto_address) => {
+        self.goto_address = goto_address;
+        None
+      }
+      MemoryViewerPanelMessage::GotoAddressSubmitted => match parse_hex(&self.goto_address) {
+        Some(addr) => {
+          let message = self.go_to(addr);
+          if message.is_some() {
+            self.selected_addr = Some(addr);
+          }
+          message
+        }
+        None => {
+          self.error = Some(format!("Invalid address '{}'", self.goto_address));
+          None
+        }
+      },
+      MemoryViewerPanelMessage::PreviousPage => {
+        self.go_to(self.start.saturating_sub(MEMORY_PAGE_SIZE))
+      }
+      MemoryViewerPanelMessage::NextPage => self.go_to(self.start + MEMORY_PAGE_SIZE),
+      MemoryViewerPanelMessage::ByteSelected(addr) => {
+        self.selected_addr = Some(addr);
+        self.edit_value.clear();
+        None
+      }
+      MemoryViewerPanelMessage::EditValueChanged(edit_value) => {
+        self.edit_value = edit_value;
+        None
+      }
+      MemoryViewerPanelMessage::EditValueSubmitted => {
+        let addr = self.selected_addr?;
+        if !self.space.can_poke(addr) {
+          self.error = Some(format!("Can't edit the I/O register at ${:04X}", addr));
+          return None;
+        }
+
+        match parse_hex(&self.edit_value).and_then(|value| u8::try_from(value).ok()) {
+          Some(value) => {
+            self.error = None;
+            self.edit_value.clear();
+            Some(EmulationInboundMessage::MemoryWriteRequested(
+              self.space, addr, value,
+            ))
+          }
+          None => {
+            self.error = Some(format!("Invalid byte value '{}'", self.edit_value));
+            None
+          }
+        }
+      }
+    }
+  }
+
+  fn byte_grid(&self, page: &MemoryPage) -> Element<'_, MemoryViewerPanelMessage> {
+    let address_digits = if page.space_len > 0x10000 { 5 } else { 4 };
+
+    let rows = page
+      .bytes
+      .chunks(BYTES_PER_ROW)
+      .enumerate()
+      .map(|(row_index, values)| {
+        let row_addr = page.start + row_index * BYTES_PER_ROW;
+        let byte_buttons = values.iter().enumerate().map(|(column_index, value)| {
+          let index = row_index * BYTES_PER_ROW + column_index;
+          let addr = page.start + index;
+          let label = value.map_or("--".to_owned(), |value| format!("{:02X}", value));
+          let mut label = text(label).font(Font::MONOSPACE);
+          if self.change_ages.get(index).copied().unwrap_or(u8::MAX) < CHANGE_HIGHLIGHT_UPDATES {
+            label = label.style(CHANGED_BYTE_COLOR);
+          }
+
+          let style = if self.selected_addr == Some(addr) {
+            theme::Button::Primary
+          } else {
+            theme::Button::Text
+          };
+
+          button(label)
+            .style(style)
+            .padding(1)
+            .on_press(MemoryViewerPanelMessage::ByteSelected(addr))
+            .into()
+        });
+
+        Row::with_children(
+          std::iter::once(
+            text(format!("{:0width$X}", row_addr, width = address_digits))
+              .font(Font::MONOSPACE)
+              .width(Length::Fixed(60.0))
+              .into(),
+          )
+          .chain(byte_buttons)
+          .collect(),
+        )
+        .spacing(2)
+        .into()
+      })
+      .collect::<Vec<_>>();
+
+    Column::with_children(rows).into()
+  }
+
+  pub fn view(&self) -> Element<'_, MemoryViewerPanelMessage> {
+    let navigation = row![
+      pick_list(
+        MemorySpace::iter().collect::<Vec<_>>(),
+        Some(self.space),
+        MemoryViewerPanelMessage::SpaceSelected
+      ),
+      text_input("Go to address", &self.goto_address)
+        .on_input(MemoryViewerPanelMessage::GotoAddressChanged)
+        .on_submit(MemoryViewerPanelMessage::GotoAddressSubmitted)
+        .width(Length::Fixed(120.0)),
+      button(text("<")).on_press(MemoryViewerPanelMessage::PreviousPage),
+      button(text(">")).on_press(MemoryViewerPanelMessage::NextPage),
+    ]
+    .spacing(5);
+
+    let mut edit_value_input = text_input("Value", &self.edit_value);
+    if self.selected_addr.is_some() {
+      edit_value_input = edit_value_input
+        .on_input(MemoryViewerPanelMessage::EditValueChanged)
+        .on_submit(MemoryViewerPanelMessage::EditValueSubmitted);
+    }
+
+    let edit_row = row![
+      text(
+        self
+          .selected_addr
+          .map_or("No byte selected".to_owned(), |addr| format!(
+            "Write ${:04X}:",
+            addr
+          ))
+      ),
+      edit_value_input.width(Length::Fixed(60.0)),
+    ]
+    .spacing(5);
+
+    let grid: Element<'_, MemoryViewerPanelMessage> = match &self.page {
+      Some(page) => self.byte_grid(page),
+      None => text("Waiting for memory contents").into(),
+    };
+
+    column![
+      navigation,
+      edit_row,
+      text(self.error.as_deref().unwrap_or_default()),
+      grid,
+    ]
+    .spacing(10)
+    .into()
+  }
+}
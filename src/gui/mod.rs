@@ -1,7 +1,27 @@
+mod breakpoints_panel;
+mod cheats_panel;
+mod code_data_logger_panel;
 mod crt_screen;
+mod debug_panel;
 mod emulator_ui;
 mod keys;
+mod memory_viewer_panel;
+mod ppu_viewer_panel;
+mod ram_search_panel;
 mod run_emulator;
+mod settings_panel;
+mod source_panel;
+mod trace_logger_panel;
 
+pub use breakpoints_panel::*;
+pub use cheats_panel::*;
+pub use code_data_logger_panel::*;
 pub use crt_screen::*;
+pub use debug_panel::*;
 pub use emulator_ui::*;
+pub use memory_viewer_panel::*;
+pub use ppu_viewer_panel::*;
+pub use ram_search_panel::*;
+pub use settings_panel::*;
+pub use source_panel::*;
+pub use trace_logger_panel::*;
@@ -0,0 +1,183 @@
+use std::fmt::Display;
+
+use iced::{
+  widget::{column, image, pick_list, row, text, Column},
+  Element, Length,
+};
+
+use crate::debugger::{PPUSnapshot, RgbaImage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PaletteChoice(u8);
+
+impl Display for PaletteChoice {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.0 < 4 {
+      write!(f, "Background {}", self.0)
+    } else {
+      write!(f, "Sprite {}", self.0 - 4)
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum PPUViewerPanelMessage {
+  PatternPaletteSelected(PaletteChoice),
+}
+
+struct PPUViewerImages {
+  pattern_tables: [image::Handle; 2],
+  name_tables: image::Handle,
+  palette_ram: image::Handle,
+  sprites: Vec<image::Handle>,
+}
+
+fn image_handle(image: RgbaImage) -> image::Handle {
+  image::Handle::from_pixels(image.width, image.height, image.data)
+}
+
+#[derive(Default)]
+pub struct PPUViewerPanel {
+  snapshot: Option<PPUSnapshot>,
+  images: Option<PPUViewerImages>,
+  pattern_palette: PaletteChoice,
+}
+
+impl PPUViewerPanel {
+  pub fn snapshot_captured(&mut self, snapshot: PPUSnapshot) {
+    self.snapshot = Some(snapshot);
+    self.render_images();
+  }
+
+  pub fn update(&mut self, message: PPUViewerPanelMessage) {
+    match message {
+      PPUViewerPanelMessage::PatternPaletteSelected(palette) => {
+        self.pattern_palette = palette;
+        self.render_images();
+      }
+    }
+  }
+
+  fn render_images(&mut self) {
+    let Some(snapshot) = &self.snapshot else {
+      return;
+    };
+
+    self.images = Some(PPUViewerImages {
+      pattern_tables: [0, 1].map(|pattern_table| {
+        image_handle(
+          snapshot
+            .render_pattern_table(pattern_table, self.pattern_palette.0)
+            .scaled(2),
+        )
+      }),
+      name_tables: image_handle(snapshot.render_name_tables()),
+      palette_ram: image_handle(snapshot.render_palette_ram()),
+      sprites: (0..snapshot.oam.len())
+        .map(|oam_index| image_handle(snapshot.render_sprite(oam_index).scaled(4)))
+        .collect(),
+    });
+  }
+
+  pub fn pattern_tables_view(&self) -> Element<'_, PPUViewerPanelMessage> {
+    let palette_picker = pick_list(
+      (0..8).map(PaletteChoice).collect::<Vec<_>>(),
+      Some(self.pattern_palette),
+      PPUViewerPanelMessage::PatternPaletteSelected,
+    );
+
+    let mut layout = column![palette_picker].spacing(10);
+    if let Some(images) = &self.images {
+      for (pattern_table, handle) in images.pattern_tables.iter().enumerate() {
+        layout = layout.push(text(format!("${:04X}", pattern_table * 0x1000)));
+        layout = layout.push(image(handle.clone()).width(Length::Fixed(256.0)));
+      }
+    }
+
+    layout.into()
+  }
+
+  pub fn name_tables_view(&self) -> Element<'_, PPUViewerPanelMessage> {
+    let (Some(snapshot), Some(images)) = (&self.snapshot, &self.images) else {
+      return text("Waiting for PPU state").into();
+    };
+
+    column![
+      text(format!(
+        "Scroll X: {} Y: {}",
+        snapshot.scroll_x, snapshot.scroll_y
+      )),
+      image(images.name_tables.clone()).width(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+  }
+
+  pub fn palettes_view(&self) -> Element<'_, PPUViewerPanelMessage> {
+    let (Some(snapshot), Some(images)) = (&self.snapshot, &self.images) else {
+      return text("Waiting for PPU state").into();
+    };
+
+    let palette_ram_rows = snapshot
+      .palette_ram
+      .chunks(16)
+      .enumerate()
+      .map(|(row_index, values)| {
+        text(format!(
+          "${:04X}: {}",
+          0x3f00 + row_index * 16,
+          values
+            .iter()
+            .map(|value| format!("{:02X}", value))
+            .collect::<Vec<_>>()
+            .join(" ")
+        ))
+        .into()
+      })
+      .collect::<Vec<_>>();
+
+    column![
+      image(images.palette_ram.clone()).width(Length::Fixed(512.0)),
+      Column::with_children(palette_ram_rows),
+    ]
+    .spacing(10)
+    .into()
+  }
+
+  pub fn oam_view(&self) -> Element<'_, PPUViewerPanelMessage> {
+    let (Some(snapshot), Some(images)) = (&self.snapshot, &self.images) else {
+      return text("Waiting for PPU state").into();
+    };
+
+    let sprite_rows = snapshot
+      .oam
+      .iter()
+      .zip(images.sprites.iter())
+      .enumerate()
+      .map(|(oam_index, (entry, handle))| {
+        row![
+          image(handle.clone()).width(Length::Fixed(32.0)),
+          text(format!(
+            "#{:02} X:{:02X} Y:{:02X} Tile:{:02X} Pal:{} {}{}{}",
+            oam_index,
+            entry.x(),
+            entry.y(),
+            entry.tile_id(),
+            entry.palette_index(),
+            if entry.flip_horizontal() { "H" } else { "-" },
+            if entry.flip_vertical() { "V" } else { "-" },
+            if entry.priority_behind_background() {
+              "B"
+            } else {
+              "-"
+            },
+          )),
+        ]
+        .spacing(10)
+        .into()
+      })
+      .collect::<Vec<_>>();
+
+    Column::with_children(sprite_rows).spacing(2).into()
+  }
+}
@@ -0,0 +1,253 @@
+use iced::{
+  widget::{button, column, pick_list, row, text, text_input, Column},
+  Element, Length,
+};
+use strum::{Display, EnumIter, IntoEnumIterator};
+
+use crate::debugger::{
+  format_value, MemorySnapshot, RamAddress, RamSearch, RamWatch, SearchFilter, ValueType,
+};
+
+// listing every candidate right after a reset would mean thousands of widgets
+const MAX_DISPLAYED_RESULTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumIter)]
+pub enum SearchFilterKind {
+  #[strum(serialize = "Equal to")]
+  EqualTo,
+  #[default]
+  Unchanged,
+  Changed,
+  Increased,
+  Decreased,
+  #[strum(serialize = "Changed by")]
+  ChangedBy,
+}
+
+impl SearchFilterKind {
+  fn needs_operand(&self) -> bool {
+    matches!(
+      self,
+      SearchFilterKind::EqualTo | SearchFilterKind::ChangedBy
+    )
+  }
+
+  fn to_filter(self, operand: &str) -> Result<SearchFilter, anyhow::Error> {
+    Ok(match self {
+      SearchFilterKind::EqualTo => SearchFilter::EqualTo(parse_operand(operand)?),
+      SearchFilterKind::Unchanged => SearchFilter::Unchanged,
+      SearchFilterKind::Changed => SearchFilter::Changed,
+      SearchFilterKind::Increased => SearchFilter::Increased,
+      SearchFilterKind::Decreased => SearchFilter::Decreased,
+      SearchFilterKind::ChangedBy => SearchFilter::ChangedBy(parse_operand(operand)?),
+    })
+  }
+}
+
+// accepts decimal ("-1", "300") or hex with a $ prefix ("$FF")
+fn parse_operand(operand: &str) -> Result<i32, anyhow::Error> {
+  let operand = operand.trim();
+  let (negative, operand) = match operand.strip_prefix('-') {
+    Some(rest) => (true, rest),
+    None => (false, operand),
+  };
+
+  let value = match operand.strip_prefix('$') {
+    Some(hex) => i32::from_str_radix(hex, 16),
+    None => operand.parse::<i32>(),
+  }
+  .map_err(|_| anyhow::Error::msg(format!("Invalid value '{}'", operand)))?;
+
+  Ok(if negative { -value } else { value })
+}
+
+#[derive(Debug, Clone)]
+pub enum RamSearchPanelMessage {
+  ValueTypeSelected(ValueType),
+  FilterKindSelected(SearchFilterKind),
+  OperandChanged(String),
+  ApplyFilter,
+  Reset,
+  AddWatch(RamAddress),
+  WatchLabelChanged(usize, String),
+  RemoveWatch(usize),
+}
+
+#[derive(Default)]
+pub struct RamSearchPanel {
+  search: RamSearch,
+  filter_kind: SearchFilterKind,
+  operand: String,
+  watches: Vec<RamWatch>,
+  latest_snapshot: MemorySnapshot,
+  error: Option<String>,
+}
+
+impl RamSearchPanel {
+  pub fn snapshot_captured(&mut self, snapshot: MemorySnapshot) {
+    self.latest_snapshot = snapshot;
+  }
+
+  pub fn update(&mut self, message: RamSearchPanelMessage) {
+    match message {
+      RamSearchPanelMessage::ValueTypeSelected(value_type) => {
+        self.search.set_value_type(value_type);
+      }
+      RamSearchPanelMessage::FilterKindSelected(filter_kind) => self.filter_kind = filter_kind,
+      RamSearchPanelMessage::OperandChanged(operand) => self.operand = operand,
+      RamSearchPanelMessage::ApplyFilter => match self.filter_kind.to_filter(&self.operand) {
+        Ok(filter) => {
+          self
+            .search
+            .apply_filter(filter, self.latest_snapshot.clone());
+          self.error = None;
+        }
+        Err(err) => self.error = Some(err.to_string()),
+      },
+      RamSearchPanelMessage::Reset => {
+        self
+          .search
+          .reset(self.latest_snapshot.clone(), self.search.value_type());
+        self.error = None;
+      }
+      RamSearchPanelMessage::AddWatch(address) => {
+        let watch = RamWatch::new(address, self.search.value_type());
+        if !self.watches.contains(&watch) {
+          self.watches.push(watch);
+        }
+      }
+      RamSearchPanelMessage::WatchLabelChanged(index, label) => {
+        if let Some(watch) = self.watches.get_mut(index) {
+          watch.label = label;
+        }
+      }
+      RamSearchPanelMessage::RemoveWatch(index) => {
+        if index < self.watches.len() {
+          self.watches.remove(index);
+        }
+      }
+    }
+  }
+
+  fn results_view(&self) -> Element<'_, RamSearchPanelMessage> {
+    let value_type = self.search.value_type();
+    let format_optional_value = |value: Option<i32>| {
+      value
+        .map(|value| format_value(value, value_type))
+        .unwrap_or_default()
+    };
+
+    let result_rows = self
+      .search
+      .candidates()
+      .iter()
+      .take(MAX_DISPLAYED_RESULTS)
+      .map(|address| {
+        row![
+          text(address.to_string()).width(Length::FillPortion(1)),
+          text(format_optional_value(self.search.previous_value(*address)))
+            .width(Length::FillPortion(2)),
+          text(format_optional_value(
+            self.latest_snapshot.read_value(*address, value_type)
+          ))
+          .width(Length::FillPortion(2)),
+          button(text("Watch")).on_press(RamSearchPanelMessage::AddWatch(*address)),
+        ]
+        .spacing(5)
+        .into()
+      })
+      .collect::<Vec<_>>();
+
+    column![
+      text(format!(
+        "{} candidates{}",
+        self.search.candidates().len(),
+        if self.search.candidates().len() > MAX_DISPLAYED_RESULTS {
+          format!(" (showing first {})", MAX_DISPLAYED_RESULTS)
+        } else {
+          String::new()
+        }
+      )),
+      row![
+        text("Address").width(Length::FillPortion(1)),
+        text("Previous").width(Length::FillPortion(2)),
+        text("Current").width(Length::FillPortion(2)),
+      ],
+      Column::with_children(result_rows).spacing(2),
+    ]
+    .spacing(5)
+    .into()
+  }
+
+  fn watches_view(&self) -> Element<'_, RamSearchPanelMessage> {
+    let watch_rows = self
+      .watches
+      .iter()
+      .enumerate()
+      .map(|(index, watch)| {
+        row![
+          text(format!("{} {}", watch.address, watch.value_type)).width(Length::FillPortion(1)),
+          text_input("Label", &watch.label)
+            .on_input(move |label| RamSearchPanelMessage::WatchLabelChanged(index, label))
+            .width(Length::FillPortion(2)),
+          text(watch.format_value(&self.latest_snapshot)).width(Length::FillPortion(1)),
+          button(text("Remove")).on_press(RamSearchPanelMessage::RemoveWatch(index)),
+        ]
+        .spacing(5)
+        .into()
+      })
+      .collect::<Vec<_>>();
+
+    column![
+      text("Watches"),
+      Column::with_children(watch_rows).spacing(2)
+    ]
+    .spacing(5)
+    .into()
+  }
+
+  pub fn view(&self) -> Element<'_, RamSearchPanelMessage> {
+    let mut operand_input = text_input("Value", &self.operand);
+    if self.filter_kind.needs_operand() {
+      operand_input = operand_input
+        .on_input(RamSearchPanelMessage::OperandChanged)
+        .on_submit(RamSearchPanelMessage::ApplyFilter);
+    }
+
+    let search_controls = row![
+      pick_list(
+        ValueType::iter().collect::<Vec<_>>(),
+        Some(self.search.value_type()),
+        RamSearchPanelMessage::ValueTypeSelected
+      ),
+      pick_list(
+        SearchFilterKind::iter().collect::<Vec<_>>(),
+        Some(self.filter_kind),
+        RamSearchPanelMessage::FilterKindSelected
+      ),
+      operand_input.width(Length::Fixed(80.0)),
+    ]
+    .spacing(5);
+
+    let mut apply_button = button(text("Filter"));
+    if self.search.is_started() {
+      apply_button = apply_button.on_press(RamSearchPanelMessage::ApplyFilter);
+    }
+
+    let search_buttons = row![
+      apply_button,
+      button(text("Reset")).on_press(RamSearchPanelMessage::Reset),
+    ]
+    .spacing(5);
+
+    column![
+      search_controls,
+      search_buttons,
+      text(self.error.as_deref().unwrap_or_default()),
+      self.watches_view(),
+      self.results_view(),
+    ]
+    .spacing(10)
+    .into()
+  }
+}
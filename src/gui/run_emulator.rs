@@ -1,23 +1,31 @@
-use std::sync::{Arc, RwLock};
+use std::thread;
 
 use smol::channel::{Receiver, Sender};
 
 use crate::{
   apu::APUSynth,
   audio::stream_setup::stream_setup_for,
+  debugger::RgbaImage,
   emulator::{EmulationInboundMessage, EmulationOutboundMessage, EmulatorBuilder},
-  ppu::Pixbuf,
+  ppu::TripleBufferWriter,
 };
 
-pub async fn run_emulator(
+/// Starts the emulator on its own thread, so frame pacing doesn't depend on the UI's executor.
+pub fn run_emulator(
   builder: Box<dyn EmulatorBuilder>,
-  pixbuf: Arc<RwLock<Pixbuf>>,
+  frame_writer: TripleBufferWriter<RgbaImage>,
   inbound_receiver: Receiver<EmulationInboundMessage>,
   outbound_sender: Sender<EmulationOutboundMessage>,
 ) {
-  let apu_synth = APUSynth::new();
-  let apu_sender = stream_setup_for(apu_synth).unwrap();
+  thread::Builder::new()
+    .name(String::from("emulation"))
+    .spawn(move || {
+      let apu_synth = APUSynth::new();
+      let playback_position = apu_synth.playback_position();
+      let apu_sender = stream_setup_for(apu_synth).unwrap();
 
-  let mut emulator = builder.build(pixbuf, apu_sender);
-  emulator.run(inbound_receiver, outbound_sender).await
+      let mut emulator = builder.build(frame_writer, apu_sender, playback_position);
+      emulator.run(inbound_receiver, outbound_sender)
+    })
+    .unwrap();
 }
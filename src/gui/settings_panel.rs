@@ -0,0 +1,415 @@
+use std::{fmt::Display, ops::RangeInclusive, path::PathBuf};
+
+use iced::{
+  widget::{button, checkbox, column, pick_list, row, slider, text, Column},
+  Element, Length,
+};
+use native_dialog::FileDialog;
+use strum::{Display, EnumIter, IntoEnumIterator};
+
+use crate::{
+  emulator::EmulationInboundMessage,
+  ppu::{FrameBlend, PaletteParameters, PalettePreset, ScreenshotOptions},
+  settings::{DisplaySettings, GameSettings, Overscan, PaletteSetting, MAX_OVERSCAN},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteChoice {
+  Preset(PalettePreset),
+  Generated,
+  File,
+}
+
+impl Display for PaletteChoice {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PaletteChoice::Preset(preset) => write!(f, "{}", preset),
+      PaletteChoice::Generated => write!(f, "Generated"),
+      PaletteChoice::File => write!(f, ".pal file"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteParameter {
+  Hue,
+  Saturation,
+  Contrast,
+  Brightness,
+  Gamma,
+}
+
+impl PaletteParameter {
+  const ALL: [PaletteParameter; 5] = [
+    PaletteParameter::Hue,
+    PaletteParameter::Saturation,
+    PaletteParameter::Contrast,
+    PaletteParameter::Brightness,
+    PaletteParameter::Gamma,
+  ];
+
+  fn value_mut(self, parameters: &mut PaletteParameters) -> &mut f32 {
+    match self {
+      PaletteParameter::Hue => &mut parameters.hue,
+      PaletteParameter::Saturation => &mut parameters.saturation,
+      PaletteParameter::Contrast => &mut parameters.contrast,
+      PaletteParameter::Brightness => &mut parameters.brightness,
+      PaletteParameter::Gamma => &mut parameters.gamma,
+    }
+  }
+
+  fn range(self) -> (RangeInclusive<f32>, f32) {
+    match self {
+      PaletteParameter::Hue => (-45.0..=45.0, 0.5),
+      PaletteParameter::Saturation => (0.0..=2.0, 0.05),
+      PaletteParameter::Contrast => (0.5..=1.5, 0.05),
+      PaletteParameter::Brightness => (-0.5..=0.5, 0.05),
+      PaletteParameter::Gamma => (1.0..=3.0, 0.05),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum FrameBlendChoice {
+  Off,
+  Mix,
+  #[strum(serialize = "Brightest")]
+  Max,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverscanEdge {
+  Top,
+  Bottom,
+  Left,
+  Right,
+}
+
+impl OverscanEdge {
+  const ALL: [OverscanEdge; 4] = [
+    OverscanEdge::Top,
+    OverscanEdge::Bottom,
+    OverscanEdge::Left,
+    OverscanEdge::Right,
+  ];
+
+  fn value_mut(self, overscan: &mut Overscan) -> &mut u32 {
+    match self {
+      OverscanEdge::Top => &mut overscan.top,
+      OverscanEdge::Bottom => &mut overscan.bottom,
+      OverscanEdge::Left => &mut overscan.left,
+      OverscanEdge::Right => &mut overscan.right,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsPanelMessage {
+  PaletteChoiceSelected(PaletteChoice),
+  PaletteParameterChanged(PaletteParameter, f32),
+  ScreenshotScaleSelected(u32),
+  ScreenshotCropToggled(bool),
+  OverscanChanged(OverscanEdge, u32),
+  IntegerScalingToggled(bool),
+  AspectCorrectionToggled(bool),
+  FullscreenToggled(bool),
+  FrameBlendSelected(FrameBlendChoice),
+  FrameBlendMixChanged(f32),
+  UnlimitedSpritesToggled(bool),
+}
+
+/// Settings for the game being played, saved next to its ROM whenever they change.
+pub struct SettingsPanel {
+  settings: GameSettings,
+  path: PathBuf,
+  error: Option<String>,
+  screenshot_scale: u32,
+  /// Crops screenshots like the picture on screen.
+  crop_screenshots: bool,
+}
+
+impl SettingsPanel {
+  pub fn new(path: PathBuf) -> Self {
+    let (settings, error) = match GameSettings::load(&path) {
+      Ok(settings) => (settings, None),
+      Err(err) => (
+        GameSettings::default(),
+        Some(format!("Couldn't load {}: {}", path.display(), err)),
+      ),
+    };
+
+    Self {
+      settings,
+      path,
+      error,
+      screenshot_scale: 1,
+      crop_screenshots: false,
+    }
+  }
+
+  pub fn screenshot_options(&self) -> ScreenshotOptions {
+    ScreenshotOptions {
+      scale: self.screenshot_scale,
+      overscan: if self.crop_screenshots {
+        self.settings.display.overscan
+      } else {
+        Overscan::default()
+      },
+    }
+  }
+
+  pub fn display(&self) -> DisplaySettings {
+    self.settings.display
+  }
+
+  /// The palette from the current settings, to send to the emulator.
+  pub fn palette_message(&mut self) -> Option<EmulationInboundMessage> {
+    match self.settings.palette.palette() {
+      Ok(palette) => Some(EmulationInboundMessage::PaletteChanged(palette)),
+      Err(err) => {
+        self.error = Some(err.to_string());
+        None
+      }
+    }
+  }
+
+  fn save(&mut self) {
+    self.error = self
+      .settings
+      .save(&self.path)
+      .err()
+      .map(|err| format!("Couldn't save {}: {}", self.path.display(), err));
+  }
+
+  fn settings_changed(&mut self) -> Option<EmulationInboundMessage> {
+    self.save();
+    self.palette_message()
+  }
+
+  pub fn frame_blend_message(&self) -> EmulationInboundMessage {
+    EmulationInboundMessage::FrameBlendChanged(self.settings.display.frame_blend)
+  }
+
+  pub fn unlimited_sprites_message(&self) -> EmulationInboundMessage {
+    EmulationInboundMessage::UnlimitedSpritesToggled(self.settings.unlimited_sprites)
+  }
+
+  fn display_changed(&mut self, change: impl FnOnce(&mut DisplaySettings)) {
+    change(&mut self.settings.display);
+    self.save();
+  }
+
+  pub fn update(&mut self, message: SettingsPanelMessage) -> Option<EmulationInboundMessage> {
+    match message {
+      SettingsPanelMessage::PaletteChoiceSelected(choice) => {
+        self.settings.palette = match choice {
+          PaletteChoice::Preset(preset) => PaletteSetting::Preset(preset),
+          PaletteChoice::Generated => PaletteSetting::Generated(PaletteParameters::default()),
+          PaletteChoice::File => {
+            let Ok(Some(path)) = FileDialog::new()
+              .add_filter("Palette", &["pal"])
+              .set_title("Load palette")
+              .show_open_single_file()
+            else {
+              return None;
+            };
+
+            PaletteSetting::File(path)
+          }
+        };
+        self.settings_changed()
+      }
+      SettingsPanelMessage::PaletteParameterChanged(parameter, value) => {
+        let PaletteSetting::Generated(parameters) = &mut self.settings.palette else {
+          return None;
+        };
+
+        *parameter.value_mut(parameters) = value;
+        self.settings_changed()
+      }
+      SettingsPanelMessage::ScreenshotScaleSelected(scale) => {
+        self.screenshot_scale = scale;
+        None
+      }
+      SettingsPanelMessage::ScreenshotCropToggled(crop_screenshots) => {
+        self.crop_screenshots = crop_screenshots;
+        None
+      }
+      SettingsPanelMessage::OverscanChanged(edge, value) => {
+        self.display_changed(|display| *edge.value_mut(&mut display.overscan) = value);
+        None
+      }
+      SettingsPanelMessage::IntegerScalingToggled(integer_scaling) => {
+        self.display_changed(|display| display.integer_scaling = integer_scaling);
+        None
+      }
+      SettingsPanelMessage::AspectCorrectionToggled(aspect_correction) => {
+        self.display_changed(|display| display.aspect_correction = aspect_correction);
+        None
+      }
+      SettingsPanelMessage::FullscreenToggled(fullscreen) => {
+        self.display_changed(|display| display.fullscreen = fullscreen);
+        None
+      }
+      SettingsPanelMessage::FrameBlendSelected(choice) => {
+        let frame_blend = match choice {
+          FrameBlendChoice::Off => None,
+          FrameBlendChoice::Mix => Some(FrameBlend::Mix(0.5)),
+          FrameBlendChoice::Max => Some(FrameBlend::Max),
+        };
+        self.display_changed(|display| display.frame_blend = frame_blend);
+        Some(self.frame_blend_message())
+      }
+      SettingsPanelMessage::FrameBlendMixChanged(amount) => {
+        self.display_changed(|display| display.frame_blend = Some(FrameBlend::Mix(amount)));
+        Some(self.frame_blend_message())
+      }
+      SettingsPanelMessage::UnlimitedSpritesToggled(unlimited_sprites) => {
+        self.settings.unlimited_sprites = unlimited_sprites;
+        self.save();
+        Some(self.unlimited_sprites_message())
+      }
+    }
+  }
+
+  pub fn view(&self) -> Element<'_, SettingsPanelMessage> {
+    let choices = PalettePreset::iter()
+      .map(PaletteChoice::Preset)
+      .chain([PaletteChoice::Generated, PaletteChoice::File])
+      .collect::<Vec<_>>();
+    let selected = match &self.settings.palette {
+      PaletteSetting::Preset(preset) => PaletteChoice::Preset(*preset),
+      PaletteSetting::Generated(_) => PaletteChoice::Generated,
+      PaletteSetting::File(_) => PaletteChoice::File,
+    };
+
+    let mut content = column![row![
+      text("Palette"),
+      pick_list(
+        choices,
+        Some(selected),
+        SettingsPanelMessage::PaletteChoiceSelected
+      ),
+    ]
+    .spacing(5)]
+    .spacing(10);
+
+    match &self.settings.palette {
+      PaletteSetting::Generated(parameters) => {
+        let sliders = PaletteParameter::ALL.map(|parameter| {
+          let mut parameters = *parameters;
+          let value = *parameter.value_mut(&mut parameters);
+          let (range, step) = parameter.range();
+          row![
+            text(format!("{:?} {:.2}", parameter, value)).width(Length::FillPortion(1)),
+            slider(range, value, move |value| {
+              SettingsPanelMessage::PaletteParameterChanged(parameter, value)
+            })
+            .step(step)
+            .width(Length::FillPortion(2)),
+          ]
+          .spacing(5)
+          .into()
+        });
+        content = content.push(Column::with_children(sliders.into()).spacing(5));
+      }
+      PaletteSetting::File(path) => {
+        content = content.push(row![
+          text(path.display().to_string()).width(Length::Fill),
+          button(text("Change")).on_press(SettingsPanelMessage::PaletteChoiceSelected(
+            PaletteChoice::File
+          )),
+        ]);
+      }
+      PaletteSetting::Preset(_) => {}
+    }
+
+    let screenshot_row = row![
+      text("Screenshot scale"),
+      pick_list(
+        [1, 2, 3, 4].as_slice(),
+        Some(self.screenshot_scale),
+        SettingsPanelMessage::ScreenshotScaleSelected
+      ),
+      checkbox(
+        "Crop overscan",
+        self.crop_screenshots,
+        SettingsPanelMessage::ScreenshotCropToggled
+      ),
+    ]
+    .spacing(5);
+
+    let display = self.settings.display;
+    let overscan_sliders = OverscanEdge::ALL.map(|edge| {
+      let mut overscan = display.overscan;
+      let value = *edge.value_mut(&mut overscan);
+      row![
+        text(format!("Crop {:?} {}", edge, value)).width(Length::FillPortion(1)),
+        slider(0..=MAX_OVERSCAN, value, move |value| {
+          SettingsPanelMessage::OverscanChanged(edge, value)
+        })
+        .width(Length::FillPortion(2)),
+      ]
+      .spacing(5)
+      .into()
+    });
+    let display_column = column![
+      checkbox(
+        "Integer scaling",
+        display.integer_scaling,
+        SettingsPanelMessage::IntegerScalingToggled
+      ),
+      checkbox(
+        "8:7 pixel aspect ratio",
+        display.aspect_correction,
+        SettingsPanelMessage::AspectCorrectionToggled
+      ),
+      checkbox(
+        "Fullscreen (F11)",
+        display.fullscreen,
+        SettingsPanelMessage::FullscreenToggled
+      ),
+      Column::with_children(overscan_sliders.into()).spacing(5),
+    ]
+    .spacing(5);
+
+    let frame_blend_choice = match display.frame_blend {
+      None => FrameBlendChoice::Off,
+      Some(FrameBlend::Mix(_)) => FrameBlendChoice::Mix,
+      Some(FrameBlend::Max) => FrameBlendChoice::Max,
+    };
+    let mut frame_blend_row = row![
+      text("Frame blending"),
+      pick_list(
+        FrameBlendChoice::iter().collect::<Vec<_>>(),
+        Some(frame_blend_choice),
+        SettingsPanelMessage::FrameBlendSelected
+      ),
+    ]
+    .spacing(5);
+    if let Some(FrameBlend::Mix(amount)) = display.frame_blend {
+      frame_blend_row = frame_blend_row
+        .push(text(format!("{:.0}%", amount * 100.0)))
+        .push(
+          slider(
+            0.0..=1.0,
+            amount,
+            SettingsPanelMessage::FrameBlendMixChanged,
+          )
+          .step(0.05),
+        );
+    }
+
+    content
+      .push(text(self.error.as_deref().unwrap_or_default()))
+      .push(display_column)
+      .push(frame_blend_row)
+      .push(checkbox(
+        "More than 8 sprites per line",
+        self.settings.unlimited_sprites,
+        SettingsPanelMessage::UnlimitedSpritesToggled,
+      ))
+      .push(screenshot_row)
+      .into()
+  }
+}
@@ -0,0 +1,152 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use iced::{
+  widget::{button, column, row, text, Column},
+  Color, Element, Font,
+};
+use native_dialog::FileDialog;
+
+use crate::{
+  debugger::{SourceLocation, SourceMap},
+  emulator::{EmulationInboundMessage, EmulatorState},
+};
+
+// lines shown either side of the current one
+const CONTEXT_LINES: usize = 20;
+
+#[derive(Debug, Clone)]
+pub enum SourcePanelMessage {
+  LoadDebugInfoRequested,
+  StepLine,
+}
+
+#[derive(Default)]
+pub struct SourcePanel {
+  source_map: SourceMap,
+  debug_info_path: Option<PathBuf>,
+  location: Option<SourceLocation>,
+  // source file contents by file index, or the error from reading them
+  sources: HashMap<usize, Result<Vec<String>, String>>,
+  error: Option<String>,
+}
+
+impl SourcePanel {
+  pub fn location_changed(&mut self, location: Option<SourceLocation>) {
+    self.location = location;
+
+    let Some(location) = location else {
+      return;
+    };
+    let Some(file) = self.source_map.files().get(location.file) else {
+      return;
+    };
+    self.sources.entry(location.file).or_insert_with(|| {
+      fs::read_to_string(&file.path)
+        .map(|contents| contents.lines().map(str::to_owned).collect())
+        .map_err(|err| format!("{}: {}", file.path.display(), err))
+    });
+  }
+
+  pub fn update(&mut self, message: SourcePanelMessage) -> Option<EmulationInboundMessage> {
+    match message {
+      SourcePanelMessage::LoadDebugInfoRequested => {
+        let Ok(Some(path)) = FileDialog::new()
+          .add_filter("ld65 debug info", &["dbg"])
+          .set_title("Load debug info")
+          .show_open_single_file()
+        else {
+          return None;
+        };
+
+        match SourceMap::load(&path) {
+          Ok(source_map) => {
+            self.source_map = source_map;
+            self.sources.clear();
+            self.debug_info_path = Some(path);
+            self.error = None;
+            Some(EmulationInboundMessage::SourceMapChanged(
+              self.source_map.clone(),
+            ))
+          }
+          Err(err) => {
+            self.error = Some(err.to_string());
+            None
+          }
+        }
+      }
+      SourcePanelMessage::StepLine => Some(EmulationInboundMessage::EmulatorStateChangeRequested(
+        EmulatorState::RunUntilNextSourceLine,
+      )),
+    }
+  }
+
+  fn source_view(&self) -> Element<'_, SourcePanelMessage> {
+    let Some(location) = self.location else {
+      return text("No source line for the current instruction").into();
+    };
+    let file = &self.source_map.files()[location.file];
+    let header = text(format!("{}:{}", file.name, location.line));
+
+    let lines = match self.sources.get(&location.file) {
+      Some(Ok(lines)) => lines,
+      Some(Err(err)) => return column![header, text(err)].spacing(10).into(),
+      None => return header.into(),
+    };
+
+    let current = location.line.saturating_sub(1);
+    let first = current.saturating_sub(CONTEXT_LINES);
+    let source_lines = Column::with_children(
+      lines
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(CONTEXT_LINES * 2 + 1)
+        .map(|(index, line)| {
+          let is_current = index == current;
+          let line = text(format!(
+            "{} {:>5}  {}",
+            if is_current { ">" } else { " " },
+            index + 1,
+            line
+          ))
+          .font(Font::MONOSPACE)
+          .size(12);
+
+          if is_current {
+            line.style(Color::from_rgb(1.0, 1.0, 0.0)).into()
+          } else {
+            line.into()
+          }
+        })
+        .collect(),
+    );
+
+    column![header, source_lines].spacing(10).into()
+  }
+
+  pub fn view(&self) -> Element<'_, SourcePanelMessage> {
+    let debug_info_status = match &self.debug_info_path {
+      Some(path) => format!(
+        "{} source files from {}",
+        self.source_map.files().len(),
+        path.display()
+      ),
+      None => String::from("Load an ld65 .dbg file to see source lines"),
+    };
+
+    let controls = row![
+      button(text("Load debug info")).on_press(SourcePanelMessage::LoadDebugInfoRequested),
+      button(text("Step line")).on_press(SourcePanelMessage::StepLine),
+    ]
+    .spacing(5);
+
+    column![
+      text(debug_info_status),
+      controls,
+      text(self.error.as_deref().unwrap_or_default()),
+      self.source_view(),
+    ]
+    .spacing(10)
+    .into()
+  }
+}
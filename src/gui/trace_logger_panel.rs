@@ -0,0 +1,268 @@
+use std::{
+  fs::File,
+  io::BufWriter,
+  path::PathBuf,
+  sync::{Arc, RwLock},
+};
+
+use iced::{
+  widget::{button, column, pick_list, row, text, text_input, Column},
+  Element, Font, Length,
+};
+use native_dialog::FileDialog;
+use strum::IntoEnumIterator;
+
+use super::parse_hex;
+use crate::{
+  debugger::{InterruptFilter, TraceFilter, TraceFormat, DEFAULT_TRACE_CAPACITY},
+  emulator::EmulationInboundMessage,
+};
+
+const DISPLAYED_TRACE_LINES: usize = 200;
+
+#[derive(Debug, Clone)]
+pub enum TraceLoggerPanelMessage {
+  LoggingToggled,
+  Clear,
+  FormatSelected(TraceFormat),
+  PCStartChanged(String),
+  PCEndChanged(String),
+  BankChanged(String),
+  InterruptFilterSelected(InterruptFilter),
+  ApplyFilter,
+  CapacityChanged(String),
+  CapacitySubmitted,
+  LogToFileRequested,
+  StopLoggingToFile,
+}
+
+pub struct TraceLoggerPanel {
+  logging: bool,
+  format: TraceFormat,
+  pc_start: String,
+  pc_end: String,
+  bank: String,
+  interrupt_filter: InterruptFilter,
+  capacity: String,
+  log_file: Option<PathBuf>,
+  lines: Vec<String>,
+  error: Option<String>,
+}
+
+impl Default for TraceLoggerPanel {
+  fn default() -> Self {
+    Self {
+      logging: false,
+      format: TraceFormat::default(),
+      pc_start: String::new(),
+      pc_end: String::new(),
+      bank: String::new(),
+      interrupt_filter: InterruptFilter::default(),
+      capacity: DEFAULT_TRACE_CAPACITY.to_string(),
+      log_file: None,
+      lines: Vec::new(),
+      error: None,
+    }
+  }
+}
+
+impl TraceLoggerPanel {
+  pub fn trace_request(&self) -> usize {
+    DISPLAYED_TRACE_LINES
+  }
+
+  pub fn trace_captured(&mut self, lines: Vec<String>) {
+    self.lines = lines;
+  }
+
+  fn parse_filter(&self) -> Result<TraceFilter, String> {
+    let parse_addr = |value: &str| -> Result<Option<u16>, String> {
+      if value.trim().is_empty() {
+        return Ok(None);
+      }
+
+      parse_hex(value)
+        .and_then(|addr| u16::try_from(addr).ok())
+        .map(Some)
+        .ok_or_else(|| format!("Invalid address '{}'", value))
+    };
+
+    let pc_range = match (parse_addr(&self.pc_start)?, parse_addr(&self.pc_end)?) {
+      (None, None) => None,
+      (start, end) => Some(start.unwrap_or(0)..=end.unwrap_or(0xffff)),
+    };
+
+    let bank = if self.bank.trim().is_empty() {
+      None
+    } else {
+      Some(
+        self
+          .bank
+          .trim()
+          .parse::<usize>()
+          .map_err(|_| format!("Invalid bank '{}'", self.bank))?,
+      )
+    };
+
+    Ok(TraceFilter {
+      pc_range,
+      bank,
+      interrupt: self.interrupt_filter,
+    })
+  }
+
+  pub fn update(&mut self, message: TraceLoggerPanelMessage) -> Option<EmulationInboundMessage> {
+    match message {
+      TraceLoggerPanelMessage::LoggingToggled => {
+        self.logging = !self.logging;
+        Some(EmulationInboundMessage::TraceLoggingToggled(self.logging))
+      }
+      TraceLoggerPanelMessage::Clear => Some(EmulationInboundMessage::TraceCleared),
+      TraceLoggerPanelMessage::FormatSelected(format) => {
+        self.format = format;
+        Some(EmulationInboundMessage::TraceFormatChanged(format))
+      }
+      TraceLoggerPanelMessage::PCStartChanged(pc_start) => {
+        self.pc_start = pc_start;
+        None
+      }
+      TraceLoggerPanelMessage::PCEndChanged(pc_end) => {
+        self.pc_end = pc_end;
+        None
+      }
+      TraceLoggerPanelMessage::BankChanged(bank) => {
+        self.bank = bank;
+        None
+      }
+      TraceLoggerPanelMessage::InterruptFilterSelected(interrupt_filter) => {
+        self.interrupt_filter = interrupt_filter;
+        None
+      }
+      TraceLoggerPanelMessage::ApplyFilter => match self.parse_filter() {
+        Ok(filter) => {
+          self.error = None;
+          Some(EmulationInboundMessage::TraceFilterChanged(filter))
+        }
+        Err(err) => {
+          self.error = Some(err);
+          None
+        }
+      },
+      TraceLoggerPanelMessage::CapacityChanged(capacity) => {
+        self.capacity = capacity;
+        None
+      }
+      TraceLoggerPanelMessage::CapacitySubmitted => match self.capacity.trim().parse::<usize>() {
+        Ok(capacity) => {
+          self.error = None;
+          Some(EmulationInboundMessage::TraceCapacityChanged(capacity))
+        }
+        Err(_) => {
+          self.error = Some(format!("Invalid buffer size '{}'", self.capacity));
+          None
+        }
+      },
+      TraceLoggerPanelMessage::LogToFileRequested => {
+        let Ok(Some(path)) = FileDialog::new()
+          .add_filter("Trace log", &["log", "txt"])
+          .set_title("Log trace to file")
+          .show_save_single_file()
+        else {
+          return None;
+        };
+
+        match File::create(&path) {
+          Ok(file) => {
+            self.log_file = Some(path);
+            self.error = None;
+            Some(EmulationInboundMessage::TraceWriterChanged(Some(Arc::new(
+              RwLock::new(BufWriter::new(file)),
+            ))))
+          }
+          Err(err) => {
+            self.error = Some(err.to_string());
+            None
+          }
+        }
+      }
+      TraceLoggerPanelMessage::StopLoggingToFile => {
+        self.log_file = None;
+        Some(EmulationInboundMessage::TraceWriterChanged(None))
+      }
+    }
+  }
+
+  pub fn view(&self) -> Element<'_, TraceLoggerPanelMessage> {
+    let controls = row![
+      button(text(if self.logging { "Stop" } else { "Start" }))
+        .on_press(TraceLoggerPanelMessage::LoggingToggled),
+      button(text("Clear")).on_press(TraceLoggerPanelMessage::Clear),
+      pick_list(
+        TraceFormat::iter().collect::<Vec<_>>(),
+        Some(self.format),
+        TraceLoggerPanelMessage::FormatSelected
+      ),
+    ]
+    .spacing(5);
+
+    let filter_controls = row![
+      text_input("PC from", &self.pc_start)
+        .on_input(TraceLoggerPanelMessage::PCStartChanged)
+        .on_submit(TraceLoggerPanelMessage::ApplyFilter)
+        .width(Length::Fixed(70.0)),
+      text_input("PC to", &self.pc_end)
+        .on_input(TraceLoggerPanelMessage::PCEndChanged)
+        .on_submit(TraceLoggerPanelMessage::ApplyFilter)
+        .width(Length::Fixed(70.0)),
+      text_input("Bank", &self.bank)
+        .on_input(TraceLoggerPanelMessage::BankChanged)
+        .on_submit(TraceLoggerPanelMessage::ApplyFilter)
+        .width(Length::Fixed(50.0)),
+      pick_list(
+        InterruptFilter::iter().collect::<Vec<_>>(),
+        Some(self.interrupt_filter),
+        TraceLoggerPanelMessage::InterruptFilterSelected
+      ),
+      button(text("Apply")).on_press(TraceLoggerPanelMessage::ApplyFilter),
+    ]
+    .spacing(5);
+
+    let file_button = match &self.log_file {
+      Some(path) => button(text(format!("Stop logging to {}", path.display())))
+        .on_press(TraceLoggerPanelMessage::StopLoggingToFile),
+      None => button(text("Log to file")).on_press(TraceLoggerPanelMessage::LogToFileRequested),
+    };
+
+    let output_controls = row![
+      text("Keep"),
+      text_input("Instructions", &self.capacity)
+        .on_input(TraceLoggerPanelMessage::CapacityChanged)
+        .on_submit(TraceLoggerPanelMessage::CapacitySubmitted)
+        .width(Length::Fixed(80.0)),
+      file_button,
+    ]
+    .spacing(5);
+
+    let trace_lines = if self.lines.is_empty() {
+      column![text("Pause emulation to see the most recent instructions")]
+    } else {
+      Column::with_children(
+        self
+          .lines
+          .iter()
+          .map(|line| text(line).font(Font::MONOSPACE).size(12).into())
+          .collect(),
+      )
+    };
+
+    column![
+      controls,
+      filter_controls,
+      output_controls,
+      text(self.error.as_deref().unwrap_or_default()),
+      trace_lines,
+    ]
+    .spacing(10)
+    .into()
+  }
+}
@@ -2,32 +2,188 @@ mod apu;
 mod audio;
 mod bus;
 mod cartridge;
+mod cheats;
 mod cpu;
+mod debugger;
 mod emulator;
 mod gui;
 mod nes;
 mod ppu;
+mod recording;
+mod settings;
 
-use std::{env, path::PathBuf, str::FromStr};
+use std::{env, fs, path::PathBuf, process, str::FromStr};
 
+use anyhow::{anyhow, Context};
 use iced::{Application, Settings};
 
 use crate::{
-  emulator::NESEmulatorBuilder,
+  debugger::PRGDisassembler,
+  emulator::{NESEmulatorBuilder, MAX_RUN_AHEAD_FRAMES},
   gui::{EmulatorUI, EmulatorUIFlags},
-  nes::INESRom,
+  nes::{INESRom, NES},
+  ppu::{screenshot_path, Pixbuf, ScreenshotOptions},
+  settings::GameSettings,
 };
 
+const USAGE: &str = "usage: family-computer [<rom.nes>] [--rewind-mb <megabytes>] \
+   [--run-ahead <0-4 frames>] [--record]";
+const DEFAULT_REWIND_MB: usize = 256;
+
+const SCREENSHOT_USAGE: &str = "usage: family-computer screenshot <rom.nes> [--frames <count>] \
+   [--scale <factor>] [--crop-overscan] [-o <out.png>]";
+const DEFAULT_SCREENSHOT_FRAMES: u64 = 60;
+
+const DISASSEMBLE_USAGE: &str =
+  "usage: family-computer disassemble <rom.nes> [--cdl <file.cdl>] [-o <out.s>]";
+
+/// `disassemble` subcommand: writes a ca65 source file for a ROM without starting the GUI.
+fn disassemble_command(args: &[String]) -> Result<(), anyhow::Error> {
+  let mut rom_path = None;
+  let mut cdl_path = None;
+  let mut output_path = None;
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--cdl" => cdl_path = Some(args.next().ok_or_else(|| anyhow!(DISASSEMBLE_USAGE))?),
+      "-o" => output_path = Some(args.next().ok_or_else(|| anyhow!(DISASSEMBLE_USAGE))?),
+      _ if rom_path.is_none() => rom_path = Some(arg),
+      _ => return Err(anyhow!(DISASSEMBLE_USAGE)),
+    }
+  }
+
+  let rom_path = PathBuf::from(rom_path.ok_or_else(|| anyhow!(DISASSEMBLE_USAGE))?);
+  let rom = INESRom::from_file(&rom_path)
+    .with_context(|| format!("Couldn't load {}", rom_path.display()))?;
+  let cdl = cdl_path
+    .map(|cdl_path| fs::read(cdl_path).with_context(|| format!("Couldn't load {}", cdl_path)))
+    .transpose()?;
+
+  let source = PRGDisassembler::new(&rom).disassemble(cdl.as_deref());
+  match output_path {
+    Some(output_path) => fs::write(output_path, source)?,
+    None => print!("{}", source),
+  }
+
+  Ok(())
+}
+
+/// `screenshot` subcommand: runs a ROM without a window for a number of frames, then saves a PNG
+/// of the last one. The picture comes out as the game's settings make it look on screen.
+fn screenshot_command(args: &[String]) -> Result<(), anyhow::Error> {
+  let mut rom_path = None;
+  let mut frames = DEFAULT_SCREENSHOT_FRAMES;
+  let mut options = ScreenshotOptions::default();
+  let mut crop_overscan = false;
+  let mut output_path = None;
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--frames" => {
+        frames = args
+          .next()
+          .and_then(|frames| frames.parse().ok())
+          .ok_or_else(|| anyhow!(SCREENSHOT_USAGE))?
+      }
+      "--scale" => {
+        options.scale = args
+          .next()
+          .and_then(|scale| scale.parse().ok())
+          .filter(|scale| *scale > 0)
+          .ok_or_else(|| anyhow!(SCREENSHOT_USAGE))?
+      }
+      "--crop-overscan" => crop_overscan = true,
+      "-o" => output_path = Some(args.next().ok_or_else(|| anyhow!(SCREENSHOT_USAGE))?),
+      _ if rom_path.is_none() => rom_path = Some(arg),
+      _ => return Err(anyhow!(SCREENSHOT_USAGE)),
+    }
+  }
+
+  let rom_path = PathBuf::from(rom_path.ok_or_else(|| anyhow!(SCREENSHOT_USAGE))?);
+  let rom = INESRom::from_file(&rom_path)
+    .with_context(|| format!("Couldn't load {}", rom_path.display()))?;
+  let settings_path = GameSettings::path_for_rom(&rom_path);
+  let settings = GameSettings::load(&settings_path)
+    .with_context(|| format!("Couldn't load {}", settings_path.display()))?;
+  let palette = settings.palette.palette()?;
+  if crop_overscan {
+    options.overscan = settings.display.overscan;
+  }
+
+  // nothing plays the audio, but the channel has to stay open
+  let (apu_sender, _apu_receiver) = smol::channel::unbounded();
+  let mut nes = NES::from_rom(rom, apu_sender);
+  nes.state.ppu.unlimited_sprites = settings.unlimited_sprites;
+  let mut pixbuf = Pixbuf::new();
+  pixbuf.set_frame_blend(settings.display.frame_blend);
+  while nes.state.ppu.frame_count < frames {
+    nes.execute_frame(&mut pixbuf);
+  }
+  pixbuf.render(&palette);
+
+  let output_path = output_path
+    .map(PathBuf::from)
+    .unwrap_or_else(|| screenshot_path(&rom_path, nes.state.ppu.frame_count));
+  pixbuf.save_png(&output_path, options)?;
+  println!("Saved {}", output_path.display());
+
+  Ok(())
+}
+
 pub fn main() -> Result<(), iced::Error> {
   if env::var("SMOL_THREADS").is_err() {
     env::set_var("SMOL_THREADS", "4");
   }
 
   let args = env::args().collect::<Vec<_>>();
-  let rom_path = match args
-    .get(1)
-    .map(|arg| PathBuf::from_str(arg.as_str()).unwrap())
-  {
+  let command_result = match args.get(1).map(String::as_str) {
+    Some("disassemble") => Some(disassemble_command(&args[2..])),
+    Some("screenshot") => Some(screenshot_command(&args[2..])),
+    _ => None,
+  };
+  if let Some(result) = command_result {
+    if let Err(err) = result {
+      eprintln!("{:#}", err);
+      process::exit(1);
+    }
+    return Ok(());
+  }
+
+  let mut rom_path = None;
+  let mut rewind_mb = DEFAULT_REWIND_MB;
+  let mut run_ahead_frames = 0;
+  let mut record = false;
+  let mut args = args[1..].iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--rewind-mb" => match args.next().and_then(|mb| mb.parse().ok()) {
+        Some(mb) => rewind_mb = mb,
+        None => {
+          eprintln!("{}", USAGE);
+          process::exit(1);
+        }
+      },
+      "--run-ahead" => match args
+        .next()
+        .and_then(|frames| frames.parse().ok())
+        .filter(|frames| *frames <= MAX_RUN_AHEAD_FRAMES)
+      {
+        Some(frames) => run_ahead_frames = frames,
+        None => {
+          eprintln!("{}", USAGE);
+          process::exit(1);
+        }
+      },
+      "--record" => record = true,
+      _ if rom_path.is_none() => rom_path = Some(PathBuf::from_str(arg.as_str()).unwrap()),
+      _ => {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+      }
+    }
+  }
+
+  let rom_path = match rom_path {
     Some(rom_path) => rom_path,
     None => native_dialog::FileDialog::new()
       .add_filter("iNES ROM", &["nes"])
@@ -42,7 +198,13 @@ pub fn main() -> Result<(), iced::Error> {
   let rom = INESRom::from_file(&rom_path).unwrap();
   println!("Using mapper ID {}", rom.mapper_id);
 
-  EmulatorUI::run(Settings::with_flags(EmulatorUIFlags::new(Box::new(
-    NESEmulatorBuilder::new(rom),
-  ))))
+  EmulatorUI::run(Settings::with_flags(EmulatorUIFlags::new(
+    Box::new(NESEmulatorBuilder::new(
+      rom,
+      rewind_mb * 1024 * 1024,
+      run_ahead_frames,
+    )),
+    rom_path,
+    record,
+  )))
 }
@@ -78,6 +78,22 @@ impl INESRom {
     })
   }
 
+  /// Rebuilds an iNES 1.0 header describing this ROM.
+  pub fn header(&self) -> [u8; 16] {
+    let mut header = [0; 16];
+    header[0..4].copy_from_slice(b"NES\x1a");
+    header[4] = (self.prg_data.len() / (16 * 1024)) as u8;
+    header[5] = (self.chr_data.len() / (8 * 1024)) as u8;
+    header[6] = ((self.mapper_id as u8 & 0x0f) << 4)
+      | (u8::from(self.trainer_data.is_some()) << 2)
+      | (u8::from(self.has_battery_ram) << 1)
+      | u8::from(self.vertical_mirroring);
+    header[7] = (self.mapper_id as u8 & 0xf0)
+      | (u8::from(self.playchoice_10) << 1)
+      | u8::from(self.vs_unisystem);
+    header
+  }
+
   pub fn initial_mirroring(&self) -> CartridgeMirroring {
     if self.vertical_mirroring {
       CartridgeMirroring::Vertical
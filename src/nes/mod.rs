@@ -2,8 +2,199 @@ mod controller;
 mod dma;
 mod ines_rom;
 mod nes;
+mod rewind;
+mod shared_memory;
 
 pub use controller::*;
 pub use dma::*;
 pub use ines_rom::*;
 pub use nes::*;
+pub use rewind::*;
+pub use shared_memory::*;
+
+#[cfg(test)]
+mod tests {
+  use std::{io::BufReader, time::Instant};
+
+  use smol::channel::Receiver;
+
+  use super::{INESRom, RewindBuffer, NES};
+  use crate::{apu::APUSynthChannel, audio::synth::SynthCommand, ppu::Pixbuf};
+
+  const BENCHMARK_FRAMES: u32 = 600;
+
+  // the receiver has to outlive the machine, or sending audio fails
+  fn frame_basics() -> (NES, Receiver<SynthCommand<APUSynthChannel>>) {
+    let rom_data = include_bytes!("../../smoketest/1.frame_basics.nes");
+    let rom = INESRom::from_reader(&mut BufReader::new(&rom_data[..])).unwrap();
+    let (sender, receiver) = smol::channel::unbounded();
+    (NES::from_rom(rom, sender), receiver)
+  }
+
+  fn frames_per_second(tracing: bool) -> f64 {
+    let (mut machine, _receiver) = frame_basics();
+    if tracing {
+      machine.trace_logger.start();
+    }
+
+    let mut pixbuf = Pixbuf::new();
+    let start = Instant::now();
+    for _ in 0..BENCHMARK_FRAMES {
+      machine.execute_frame(&mut pixbuf);
+    }
+
+    f64::from(BENCHMARK_FRAMES) / start.elapsed().as_secs_f64()
+  }
+
+  // run with `cargo test --release -- --ignored --nocapture benchmark`
+  #[test]
+  #[ignore]
+  fn benchmark_frames_per_second() {
+    println!("tracing disabled: {:.1} FPS", frames_per_second(false));
+    println!("tracing enabled: {:.1} FPS", frames_per_second(true));
+  }
+
+  #[test]
+  fn test_untraced_cpu_doesnt_disassemble() {
+    let (mut machine, _receiver) = frame_basics();
+    for tracing in [false, true] {
+      let executed = (0..100)
+        .find_map(|_| {
+          machine
+            .state
+            .cpu
+            .tick(machine.state.cartridge.cpu_bus_mut(), tracing)
+        })
+        .unwrap();
+      assert_eq!(executed.disassembled_instruction.is_some(), tracing);
+    }
+  }
+
+  #[test]
+  fn test_rewind() {
+    let (mut machine, _receiver) = frame_basics();
+    let mut pixbuf = Pixbuf::new();
+    // a keyframe takes up the ROM and RAM, and the other frames only what changed since it
+    let mut rewind_buffer = RewindBuffer::new(128 * 1024);
+    machine.state.cartridge.set_code_data_logging(true);
+
+    let mut expected = Vec::new();
+    for _ in 0..60 {
+      let cpu_bus = machine.state.cartridge.cpu_bus();
+      expected.push((machine.state.cpu_cycle_count, cpu_bus.work_ram().to_vec()));
+      rewind_buffer.push(machine.snapshot());
+      machine.execute_frame(&mut pixbuf);
+    }
+    let final_work_ram = machine.state.cartridge.cpu_bus().work_ram().to_vec();
+    let code_data_log = machine.code_data_log();
+
+    for (cpu_cycle_count, work_ram) in expected.into_iter().rev() {
+      let state = rewind_buffer.pop().unwrap();
+      assert_eq!(state.cpu_cycle_count, cpu_cycle_count);
+      assert_eq!(state.cartridge.cpu_bus().work_ram(), work_ram);
+      machine.restore(state);
+    }
+    assert!(rewind_buffer.pop().is_none());
+
+    // the code/data log isn't rolled back
+    assert_eq!(machine.code_data_log().to_bytes(), code_data_log.to_bytes());
+
+    for _ in 0..60 {
+      machine.execute_frame(&mut pixbuf);
+    }
+    assert_eq!(machine.state.cartridge.cpu_bus().work_ram(), final_work_ram);
+
+    let mut rewind_buffer = RewindBuffer::new(0);
+    rewind_buffer.push(machine.snapshot());
+    assert!(rewind_buffer.pop().is_none());
+  }
+
+  #[test]
+  fn test_run_ahead() {
+    let (mut ahead, receiver) = frame_basics();
+    let (mut reference, _reference_receiver) = frame_basics();
+    let mut pixbuf = Pixbuf::new();
+    let mut reference_pixbuf = Pixbuf::new();
+
+    for _ in 0..30 {
+      ahead.execute_frame(&mut pixbuf);
+      reference.execute_frame(&mut reference_pixbuf);
+    }
+    let cpu_cycle_count = ahead.state.cpu_cycle_count;
+    let work_ram = ahead.state.cartridge.cpu_bus().work_ram().to_vec();
+    let queued_audio = receiver.len();
+
+    ahead.run_ahead(2, &mut pixbuf);
+    reference.execute_frame(&mut reference_pixbuf);
+    reference.execute_frame(&mut reference_pixbuf);
+
+    // the picture is from two frames on, but the machine and audio are where they were
+    assert_eq!(pixbuf.frame, reference_pixbuf.frame);
+    assert_eq!(ahead.state.cpu_cycle_count, cpu_cycle_count);
+    assert_eq!(ahead.state.cartridge.cpu_bus().work_ram(), work_ram);
+    assert_eq!(receiver.len(), queued_audio);
+  }
+
+  #[test]
+  fn test_rewind_with_run_ahead() {
+    let mut snapshots_kept = Vec::new();
+    for run_ahead_frames in [0, 2] {
+      let (mut machine, _receiver) = frame_basics();
+      let mut pixbuf = Pixbuf::new();
+      // enough for a second or so, so older frames get dropped
+      let mut rewind_buffer = RewindBuffer::new(96 * 1024);
+
+      for _ in 0..150 {
+        rewind_buffer.push(machine.snapshot());
+        machine.execute_frame(&mut pixbuf);
+        if run_ahead_frames > 0 {
+          machine.run_ahead(run_ahead_frames, &mut pixbuf);
+        }
+      }
+
+      let mut count = 0;
+      while rewind_buffer.pop().is_some() {
+        count += 1;
+      }
+      snapshots_kept.push(count);
+    }
+
+    // copies made while running ahead aren't charged to the rewind snapshots
+    assert!(snapshots_kept[0] > 0);
+    assert_eq!(snapshots_kept[0], snapshots_kept[1]);
+  }
+
+  #[test]
+  fn test_lag_frames() {
+    let (mut machine, _receiver) = frame_basics();
+    let mut pixbuf = Pixbuf::new();
+
+    // the test ROM never reads the controllers
+    for _ in 0..10 {
+      machine.execute_frame(&mut pixbuf);
+    }
+    assert!(machine.is_lag_frame());
+    let lag_frame_count = machine.state.lag_frame_count;
+    assert!(lag_frame_count >= 9);
+
+    // reading without strobing first doesn't get any new input
+    let cpu_bus = machine.state.cartridge.cpu_bus_mut();
+    for _ in 0..8 {
+      cpu_bus.read(0x4016);
+    }
+    machine.execute_frame(&mut pixbuf);
+    assert!(machine.is_lag_frame());
+    let lag_frame_count = machine.state.lag_frame_count;
+
+    let cpu_bus = machine.state.cartridge.cpu_bus_mut();
+    cpu_bus.write(0x4016, 1);
+    cpu_bus.write(0x4016, 0);
+    for _ in 0..8 {
+      cpu_bus.read(0x4016);
+    }
+    machine.execute_frame(&mut pixbuf);
+    assert!(!machine.is_lag_frame());
+    assert_eq!(machine.state.last_frame_input_polls, 8);
+    assert_eq!(machine.state.lag_frame_count, lag_frame_count);
+  }
+}
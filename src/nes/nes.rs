@@ -1,20 +1,24 @@
-use std::{
-  any::Any,
-  fmt::Debug,
-  io::Write,
-  sync::{Arc, RwLock},
-};
+use std::{any::Any, fmt::Debug, io::Write, mem};
 
 use crate::{
-  apu::APUSynth,
+  apu::{APUSynth, AudioClock},
   audio::stream_setup::StreamSpawner,
   cartridge::Cartridge,
-  cpu::{DisassemblyMachineState, ExecutedInstruction, CPU},
+  cheats::CheatEngine,
+  cpu::{CPUInterrupt, DisassemblyMachineState, Instruction, CPU},
+  debugger::{
+    Breakpoint, CDLFile, CHRDataLogger, CodeDataLogger, SourceLocation, SourceMap, SymbolTable,
+    TraceEntry, TraceLogger,
+  },
   ppu::{Pixbuf, PPU},
 };
 
 use super::INESRom;
 
+pub const PRG_BANK_SIZE: usize = 16 * 1024;
+// an NMI can interrupt an IRQ handler, but games don't nest deeper than that
+const MAX_INTERRUPT_NESTING: usize = 4;
+
 pub trait DisassemblyWriter: Write + Debug + Any {
   fn as_any(&self) -> &dyn Any
   where
@@ -33,12 +37,20 @@ pub trait DisassemblyWriter: Write + Debug + Any {
 
 impl<T: Write + Debug + Any> DisassemblyWriter for T {}
 
+#[derive(Clone)]
 pub struct NESState {
   pub cartridge: Cartridge,
   pub cpu: CPU,
   pub ppu: PPU,
   pub cpu_cycle_count: u64,
   pub ppu_cycle_count: u64,
+  /// Frames in which the game didn't read the controllers.
+  pub lag_frame_count: u64,
+  /// Controller reads during the last complete frame; 0 makes it a lag frame.
+  pub last_frame_input_polls: u32,
+  /// Interrupts being serviced, innermost last. The oldest are forgotten past
+  /// `MAX_INTERRUPT_NESTING`, for handlers that leave without an RTI.
+  pub interrupt_stack: Vec<CPUInterrupt>,
 }
 
 impl NESState {
@@ -49,30 +61,95 @@ impl NESState {
       ppu: PPU::new(),
       cpu_cycle_count: 0,
       ppu_cycle_count: 0,
+      lag_frame_count: 0,
+      last_frame_input_polls: 0,
+      interrupt_stack: Vec::new(),
+    }
+  }
+}
+
+// state that lives in the cartridge but isn't rolled back by rewinding
+struct PersistentState {
+  code_data_logger: CodeDataLogger,
+  chr_data_logger: CHRDataLogger,
+  cheats: CheatEngine,
+}
+
+impl PersistentState {
+  fn take(cartridge: &mut Cartridge) -> Self {
+    let cpu_bus = cartridge.cpu_bus_mut();
+    let code_data_logger = mem::take(cpu_bus.code_data_logger_mut());
+    let cheats = mem::take(cpu_bus.cheats_mut());
+    let chr_data_logger = mem::take(
+      cpu_bus
+        .ppu_cpu_bus_mut()
+        .ppu_memory_mut()
+        .chr_data_logger_mut(),
+    );
+
+    Self {
+      code_data_logger,
+      chr_data_logger,
+      cheats,
     }
   }
+
+  fn put(self, cartridge: &mut Cartridge) {
+    let cpu_bus = cartridge.cpu_bus_mut();
+    *cpu_bus.code_data_logger_mut() = self.code_data_logger;
+    *cpu_bus.cheats_mut() = self.cheats;
+    *cpu_bus
+      .ppu_cpu_bus_mut()
+      .ppu_memory_mut()
+      .chr_data_logger_mut() = self.chr_data_logger;
+  }
 }
 
 #[allow(clippy::upper_case_acronyms)]
 pub struct NES {
   pub state: NESState,
   pub apu_sender: <APUSynth as StreamSpawner>::OutputType,
-  pub last_executed_instruction: Option<ExecutedInstruction>,
-  pub last_disassembly_machine_state: Option<DisassemblyMachineState>,
-  pub disassembly_writer: Option<Arc<RwLock<dyn DisassemblyWriter + Send + Sync>>>,
+  pub audio_clock: AudioClock,
+  /// Set while recording, to get the sound in emulated time.
+  pub recording_sender: Option<<APUSynth as StreamSpawner>::OutputType>,
+  audio_muted: bool,
+  pub trace_logger: TraceLogger,
+  pub symbols: SymbolTable,
+  pub breakpoints: Vec<Breakpoint>,
+  pub source_map: SourceMap,
+  // while stepping by source line, the line being stepped from
+  source_step: Option<Option<SourceLocation>>,
+  debug_stop: bool,
+  // sizes from the iNES header, since mappers may mirror small ROMs to fill their address space
+  pub prg_rom_size: usize,
+  pub chr_rom_size: usize,
 }
 
 impl NES {
   pub fn from_rom(rom: INESRom, apu_sender: <APUSynth as StreamSpawner>::OutputType) -> Self {
+    let prg_rom_size = rom.prg_data.len();
+    let chr_rom_size = if rom.uses_chr_ram {
+      0
+    } else {
+      rom.chr_data.len()
+    };
     let cartridge = Cartridge::from_ines_rom(rom);
     let state = NESState::new(cartridge);
 
     let mut machine = Self {
       state,
       apu_sender,
-      last_executed_instruction: None,
-      last_disassembly_machine_state: None,
-      disassembly_writer: None,
+      audio_clock: AudioClock::default(),
+      recording_sender: None,
+      audio_muted: false,
+      trace_logger: TraceLogger::default(),
+      symbols: SymbolTable::default(),
+      breakpoints: Vec::new(),
+      source_map: SourceMap::default(),
+      source_step: None,
+      debug_stop: false,
+      prg_rom_size,
+      chr_rom_size,
     };
 
     machine.reset();
@@ -80,30 +157,200 @@ impl NES {
     machine
   }
 
-  pub fn execute_frame(&mut self, pixbuf: &mut Pixbuf) {
+  pub fn code_data_log(&self) -> CDLFile {
+    CDLFile::build(
+      self.state.cartridge.cpu_bus().code_data_logger(),
+      self
+        .state
+        .cartridge
+        .ppu_cpu_bus()
+        .ppu_memory()
+        .chr_data_logger(),
+      self.prg_rom_size,
+      self.chr_rom_size,
+    )
+  }
+
+  /// A copy of the machine state for rewinding. RAM and ROM buffers are shared copy-on-write, and
+  /// debugger logs and cheats are left out.
+  pub fn snapshot(&mut self) -> NESState {
+    let persistent_state = PersistentState::take(&mut self.state.cartridge);
+    let snapshot = self.state.clone();
+    persistent_state.put(&mut self.state.cartridge);
+
+    snapshot
+  }
+
+  /// Replaces the machine state with a snapshot, keeping the current debugger logs, cheats and
+  /// enhancements.
+  pub fn restore(&mut self, mut state: NESState) {
+    PersistentState::take(&mut self.state.cartridge).put(&mut state.cartridge);
+    state.ppu.unlimited_sprites = self.state.ppu.unlimited_sprites;
+    self.state = state;
+  }
+
+  /// Runs `frames` frames ahead with the current input and leaves the last one's picture in
+  /// `pixbuf`, then goes back to where it started. Showing the future like this hides lag frames
+  /// built into games.
+  pub fn run_ahead(&mut self, frames: usize, pixbuf: &mut Pixbuf) {
+    let state = self.snapshot();
+    for _ in 0..frames {
+      self.execute_hidden_frame(pixbuf);
+    }
+    self.restore(state);
+  }
+
+  /// Runs a frame that's only there to be looked at, like one run ahead or replayed while
+  /// rewinding: it's silent, untraced and ignores breakpoints.
+  pub fn execute_hidden_frame(&mut self, pixbuf: &mut Pixbuf) {
+    let trace_logger = mem::take(&mut self.trace_logger);
+    let breakpoints = mem::take(&mut self.breakpoints);
+    self.audio_muted = true;
+
+    self.execute_frame(pixbuf);
+
+    self.audio_muted = false;
+    self.breakpoints = breakpoints;
+    self.trace_logger = trace_logger;
+  }
+
+  /// The offset into PRG ROM mapped at a CPU address, folding mirrors of small ROMs.
+  pub fn prg_rom_offset(&self, addr: u16) -> Option<usize> {
+    let offset = self.state.cartridge.prg_rom_offset(addr)?;
+    offset.checked_rem(self.prg_rom_size)
+  }
+
+  /// The 16KiB PRG ROM bank mapped at a CPU address.
+  pub fn prg_bank(&self, addr: u16) -> Option<usize> {
+    Some(self.prg_rom_offset(addr)? / PRG_BANK_SIZE)
+  }
+
+  /// The source line of the instruction at a CPU address, if debug info is loaded.
+  pub fn source_location(&self, addr: u16) -> Option<SourceLocation> {
+    self.source_map.location(addr, self.prg_rom_offset(addr))
+  }
+
+  /// Makes `execute_frame` stop once an instruction from a different source line is about to run.
+  pub fn start_source_step(&mut self) {
+    self.source_step = Some(self.source_location(self.state.cpu.pc));
+  }
+
+  pub fn cancel_source_step(&mut self) {
+    self.source_step = None;
+  }
+
+  /// Runs until the end of the frame, or until the next instruction is at a breakpoint or ends a
+  /// source line step. Returns whether it stopped early; calling it again resumes from there.
+  pub fn execute_frame(&mut self, pixbuf: &mut Pixbuf) -> bool {
+    self.debug_stop = false;
+
     loop {
       self.tick(pixbuf);
 
+      if self.debug_stop {
+        return true;
+      }
+
       if self.state.ppu.cycle == 1 && self.state.ppu.scanline == -1 {
-        break;
+        return false;
+      }
+    }
+  }
+
+  fn check_debug_stops(&mut self) {
+    let cpu = &self.state.cpu;
+    if (self.breakpoints.is_empty() && self.source_step.is_none())
+      || cpu.wait_cycles > 0
+      || cpu.pending_interrupt().is_some()
+    {
+      return;
+    }
+
+    let pc = cpu.pc;
+    let prg_rom_offset = self.prg_rom_offset(pc);
+    if self
+      .breakpoints
+      .iter()
+      .any(|breakpoint| breakpoint.matches(pc, prg_rom_offset))
+    {
+      self.debug_stop = true;
+    }
+
+    if let Some(from) = self.source_step {
+      // code without debug info (e.g. the runtime library) is stepped through
+      let location = self.source_map.location(pc, prg_rom_offset);
+      if location.is_some() && location != from {
+        self.source_step = None;
+        self.debug_stop = true;
       }
     }
   }
 
   pub fn tick_cpu(&mut self) {
-    let captured_state = DisassemblyMachineState::capture(
-      &self.state.cpu,
-      &self.state.ppu,
-      self.state.cpu_cycle_count,
-      self.state.cartridge.cpu_bus(),
-    );
+    let pending_interrupt = self.state.cpu.pending_interrupt();
+    // only pay for capturing and disassembling when an instruction is about to start while tracing
+    let tracing = self.trace_logger.is_enabled()
+      && pending_interrupt.is_none()
+      && self.state.cpu.wait_cycles == 0;
+    let captured_state = tracing.then(|| {
+      DisassemblyMachineState::capture(
+        &self.state.cpu,
+        &self.state.ppu,
+        self.state.cpu_cycle_count,
+        self.state.cartridge.cpu_bus(),
+      )
+    });
 
-    let executed_instruction = self.state.cpu.tick(self.state.cartridge.cpu_bus_mut());
+    let executed_instruction = self
+      .state
+      .cpu
+      .tick(self.state.cartridge.cpu_bus_mut(), tracing);
     self.state.cpu_cycle_count += 1;
+    // the next instruction starts on the following CPU cycle, so stopping here leaves it unexecuted
+    self.check_debug_stops();
 
-    if let Some(instruction) = executed_instruction {
-      self.last_disassembly_machine_state = Some(captured_state);
-      self.last_executed_instruction = Some(instruction);
+    if let Some(interrupt) = pending_interrupt {
+      let interrupt_stack = &mut self.state.interrupt_stack;
+      if interrupt_stack.len() == MAX_INTERRUPT_NESTING {
+        interrupt_stack.remove(0);
+      }
+      interrupt_stack.push(interrupt);
+    }
+
+    let Some(mut instruction) = executed_instruction else {
+      return;
+    };
+
+    let returned_from_interrupt = matches!(instruction.instruction, Instruction::RTI);
+    if let Some(captured_state) = captured_state {
+      if let Some(disassembled_instruction) = &mut instruction.disassembled_instruction {
+        if !self.symbols.is_empty() {
+          disassembled_instruction.resolve_label(|addr| {
+            self
+              .symbols
+              .label(addr, self.prg_rom_offset(addr))
+              .map(str::to_owned)
+          });
+        }
+      }
+
+      let bank = self
+        .trace_logger
+        .filter()
+        .bank
+        .and_then(|_| self.prg_bank(captured_state.cpu.pc));
+      self.trace_logger.log(
+        TraceEntry {
+          instruction,
+          state: captured_state,
+        },
+        bank,
+        self.state.interrupt_stack.last().copied(),
+      );
+    }
+
+    if returned_from_interrupt {
+      self.state.interrupt_stack.pop();
     }
   }
 
@@ -114,17 +361,54 @@ impl NES {
       .tick(pixbuf, self.state.cartridge.ppu_cpu_bus_mut());
     self.state.ppu_cycle_count += 1;
 
+    if self.state.ppu.scanline == -1 && self.state.ppu.cycle == 0 {
+      self.count_input_polls();
+      self.apply_frozen_ram();
+    }
+
     if nmi_set {
       self.nmi();
     }
   }
 
+  fn count_input_polls(&mut self) {
+    let input_polls = self.state.cartridge.cpu_bus_mut().take_input_polls();
+    self.state.last_frame_input_polls = input_polls;
+    if input_polls == 0 {
+      self.state.lag_frame_count += 1;
+    }
+  }
+
+  pub fn is_lag_frame(&self) -> bool {
+    self.state.last_frame_input_polls == 0
+  }
+
+  /// Whether the PPU is partway through the visible scanlines, e.g. after stopping at a
+  /// breakpoint, so the frame buffer holds parts of two frames.
+  pub fn is_drawing_frame(&self) -> bool {
+    (0..240).contains(&self.state.ppu.scanline)
+  }
+
+  fn apply_frozen_ram(&mut self) {
+    let cartridge = &mut self.state.cartridge;
+    if cartridge.cpu_bus().cheats().frozen_ram().is_empty() {
+      return;
+    }
+
+    // poked rather than written, so the game doesn't see extra writes to mapper registers
+    let frozen_ram = cartridge.cpu_bus().cheats().frozen_ram().to_vec();
+    for code in frozen_ram {
+      cartridge.poke_cpu(code.addr, code.value);
+    }
+  }
+
   pub fn tick_apu(&mut self) {
-    let irq_set = self
-      .state
-      .cartridge
-      .cpu_bus_mut()
-      .tick_apu(&self.apu_sender, self.state.cpu_cycle_count);
+    let irq_set = self.state.cartridge.cpu_bus_mut().tick_apu(
+      (!self.audio_muted).then_some(&self.apu_sender),
+      &self.audio_clock,
+      self.recording_sender.as_ref().filter(|_| !self.audio_muted),
+      self.state.cpu_cycle_count,
+    );
 
     if irq_set {
       self.state.cpu.irq_set = true;
@@ -140,7 +424,6 @@ impl NES {
         .maybe_tick_dma(self.state.ppu_cycle_count);
 
       if !dma_ticked {
-        self.log_last_executed_instruction();
         self.tick_cpu();
       }
     }
@@ -149,25 +432,6 @@ impl NES {
     self.tick_apu();
   }
 
-  fn log_last_executed_instruction(&mut self) {
-    if let Some(disassembly_writer) = &mut self.disassembly_writer {
-      if let Some(executed_instruction) = &self.last_executed_instruction {
-        if let Some(prev_state) = &self.last_disassembly_machine_state {
-          if self.state.cpu.wait_cycles == 0 {
-            disassembly_writer
-              .write()
-              .unwrap()
-              .write_fmt(format_args!(
-                "{}\n",
-                executed_instruction.disassemble(prev_state)
-              ))
-              .unwrap();
-          }
-        }
-      }
-    }
-  }
-
   pub fn nmi(&mut self) {
     self.state.cpu.nmi_set = true;
   }
@@ -0,0 +1,237 @@
+use std::{collections::VecDeque, mem};
+
+use super::{NESState, SharedBuffer};
+
+/// How often to take a full snapshot for the ones after it to be encoded against.
+const KEYFRAME_INTERVAL: usize = 60;
+
+// deltas mark where something changed, so short unchanged stretches are cheaper left in
+const MIN_UNCHANGED_RUN: usize = 4;
+
+enum Snapshot {
+  Keyframe(NESState),
+  /// A snapshot whose buffers are the last keyframe's, with the bytes that differ from them
+  /// XORed in and run-length encoded.
+  Delta(NESState, Vec<u8>),
+}
+
+/// A rolling buffer of per-frame snapshots for playing emulation backwards.
+///
+/// Every `KEYFRAME_INTERVAL` frames it keeps a full snapshot, whose RAM and ROM buffers are shared
+/// copy-on-write with the live machine. The snapshots in between only keep the changes to those
+/// buffers since the keyframe. The oldest snapshots are dropped once they take up more than the
+/// memory limit.
+#[derive(Default)]
+pub struct RewindBuffer {
+  // each snapshot with the bytes it takes up
+  snapshots: VecDeque<(Snapshot, usize)>,
+  memory_limit: usize,
+  memory_used: usize,
+}
+
+impl RewindBuffer {
+  pub fn new(memory_limit: usize) -> Self {
+    Self {
+      memory_limit,
+      ..Default::default()
+    }
+  }
+
+  pub fn push(&mut self, mut state: NESState) {
+    let keyframe = self
+      .snapshots
+      .iter_mut()
+      .rev()
+      .take(KEYFRAME_INTERVAL - 1)
+      .find_map(|(snapshot, _)| match snapshot {
+        Snapshot::Keyframe(keyframe) => Some(keyframe),
+        Snapshot::Delta(..) => None,
+      });
+
+    let (snapshot, bytes) = match keyframe {
+      Some(keyframe) => {
+        let mut keyframe_buffers = shared_buffers(keyframe).into_iter();
+        let mut delta = Vec::new();
+        state.cartridge.visit_shared_buffers(&mut |buffer| {
+          let keyframe_buffer = keyframe_buffers.next().unwrap();
+          encode_delta(keyframe_buffer.bytes(), buffer.bytes(), &mut delta);
+          buffer.share(&*keyframe_buffer);
+        });
+        delta.shrink_to_fit();
+
+        let bytes = state_size(&state) + delta.len();
+        (Snapshot::Delta(state, delta), bytes)
+      }
+      None => {
+        // buffers still shared with the previous snapshot are already counted
+        let previous_buffers = match self.snapshots.back_mut() {
+          Some((Snapshot::Keyframe(previous) | Snapshot::Delta(previous, _), _)) => {
+            shared_buffers(previous)
+          }
+          None => Vec::new(),
+        };
+        let mut bytes = state_size(&state);
+        let mut index = 0;
+        state.cartridge.visit_shared_buffers(&mut |buffer| {
+          let shared = previous_buffers
+            .get(index)
+            .is_some_and(|previous| previous.bytes().as_ptr() == buffer.bytes().as_ptr());
+          if !shared {
+            bytes += buffer.bytes().len();
+          }
+          index += 1;
+        });
+
+        (Snapshot::Keyframe(state), bytes)
+      }
+    };
+
+    self.snapshots.push_back((snapshot, bytes));
+    self.memory_used += bytes;
+    self.evict();
+  }
+
+  /// The most recent snapshot, removing it from the buffer.
+  pub fn pop(&mut self) -> Option<NESState> {
+    let (snapshot, bytes) = self.snapshots.pop_back()?;
+    self.memory_used -= bytes;
+
+    Some(match snapshot {
+      Snapshot::Keyframe(state) => state,
+      Snapshot::Delta(mut state, delta) => {
+        let mut delta = &delta[..];
+        state
+          .cartridge
+          .visit_shared_buffers(&mut |buffer| decode_delta(&mut delta, buffer));
+        state
+      }
+    })
+  }
+
+  fn pop_front(&mut self) {
+    if let Some((_, bytes)) = self.snapshots.pop_front() {
+      self.memory_used -= bytes;
+    }
+  }
+
+  fn evict(&mut self) {
+    while self.memory_used > self.memory_limit && !self.snapshots.is_empty() {
+      self.pop_front();
+      // the keyframe's buffers live on in its deltas, so they go together
+      while matches!(self.snapshots.front(), Some((Snapshot::Delta(..), _))) {
+        self.pop_front();
+      }
+    }
+  }
+}
+
+fn shared_buffers(state: &mut NESState) -> Vec<Box<dyn SharedBuffer>> {
+  let mut buffers = Vec::new();
+  state
+    .cartridge
+    .visit_shared_buffers(&mut |buffer| buffers.push(buffer.clone_shared()));
+  buffers
+}
+
+/// The memory a snapshot takes up besides its RAM and ROM buffers.
+fn state_size(state: &NESState) -> usize {
+  mem::size_of::<NESState>() + state.cartridge.state_size()
+}
+
+/// Appends the changes from `base` to `current`, as alternating runs of unchanged bytes and runs
+/// of changed bytes XORed with `base`, each starting with its length.
+fn encode_delta(base: &[u8], current: &[u8], output: &mut Vec<u8>) {
+  debug_assert_eq!(base.len(), current.len());
+
+  if base.as_ptr() == current.as_ptr() {
+    // it's the same memory, so nothing's been written since the keyframe
+    if !current.is_empty() {
+      write_length(output, current.len());
+    }
+    return;
+  }
+
+  let mut position = 0;
+  while position < current.len() {
+    let unchanged = (position..current.len())
+      .take_while(|&index| base[index] == current[index])
+      .count();
+    write_length(output, unchanged);
+    position += unchanged;
+    if position == current.len() {
+      break;
+    }
+
+    let mut end = position;
+    let mut unchanged = 0;
+    while end < current.len() && unchanged < MIN_UNCHANGED_RUN {
+      if base[end] == current[end] {
+        unchanged += 1;
+      } else {
+        unchanged = 0;
+      }
+      end += 1;
+    }
+    let end = end - unchanged;
+
+    write_length(output, end - position);
+    output.extend((position..end).map(|index| base[index] ^ current[index]));
+    position = end;
+  }
+}
+
+/// Applies a buffer's changes written by `encode_delta`, moving `delta` past them. `buffer` has to
+/// hold the base they were encoded against.
+fn decode_delta(delta: &mut &[u8], buffer: &mut dyn SharedBuffer) {
+  let len = buffer.bytes().len();
+  if len == 0 {
+    return;
+  }
+
+  let mut position = read_length(delta);
+  if position == len {
+    // unchanged, so there's no need to copy it
+    return;
+  }
+
+  let bytes = buffer.bytes_mut();
+  loop {
+    let changed = read_length(delta);
+    for (byte, change) in bytes[position..position + changed].iter_mut().zip(*delta) {
+      *byte ^= change;
+    }
+    *delta = &delta[changed..];
+    position += changed;
+    if position == len {
+      break;
+    }
+
+    position += read_length(delta);
+    if position == len {
+      break;
+    }
+  }
+}
+
+// LEB128, so short runs only take a byte
+fn write_length(output: &mut Vec<u8>, mut length: usize) {
+  while length >= 0x80 {
+    output.push((length as u8) | 0x80);
+    length >>= 7;
+  }
+  output.push(length as u8);
+}
+
+fn read_length(input: &mut &[u8]) -> usize {
+  let mut length = 0;
+  let mut shift = 0;
+  loop {
+    let byte = input[0];
+    *input = &input[1..];
+    length |= usize::from(byte & 0x7f) << shift;
+    if byte & 0x80 == 0 {
+      return length;
+    }
+    shift += 7;
+  }
+}
@@ -0,0 +1,96 @@
+use std::{
+  any::Any,
+  ops::{Deref, DerefMut},
+  sync::Arc,
+};
+
+use bytemuck::Pod;
+
+/// Memory that can be viewed as plain bytes, for encoding snapshots.
+pub trait AsBytes {
+  fn as_bytes(&self) -> &[u8];
+  fn as_bytes_mut(&mut self) -> &mut [u8];
+}
+
+impl<T: Pod, const N: usize> AsBytes for [T; N] {
+  fn as_bytes(&self) -> &[u8] {
+    bytemuck::cast_slice(self)
+  }
+
+  fn as_bytes_mut(&mut self) -> &mut [u8] {
+    bytemuck::cast_slice_mut(self)
+  }
+}
+
+impl<T: Pod> AsBytes for Vec<T> {
+  fn as_bytes(&self) -> &[u8] {
+    bytemuck::cast_slice(self)
+  }
+
+  fn as_bytes_mut(&mut self) -> &mut [u8] {
+    bytemuck::cast_slice_mut(self)
+  }
+}
+
+/// Copy-on-write storage for RAM and ROM buffers. Cloning machine state (e.g. for rewind) shares
+/// them, and a buffer is only copied the first time it's written afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct SharedMemory<T: Clone + AsBytes>(Arc<T>);
+
+impl<T: Clone + AsBytes> SharedMemory<T> {
+  pub fn new(value: T) -> Self {
+    Self(Arc::new(value))
+  }
+}
+
+impl<T: Clone + AsBytes> Deref for SharedMemory<T> {
+  type Target = T;
+
+  fn deref(&self) -> &T {
+    &self.0
+  }
+}
+
+impl<T: Clone + AsBytes> DerefMut for SharedMemory<T> {
+  fn deref_mut(&mut self) -> &mut T {
+    Arc::make_mut(&mut self.0)
+  }
+}
+
+/// A `SharedMemory` of any type, so a machine's buffers can be walked through without knowing
+/// which mapper it has.
+pub trait SharedBuffer {
+  fn bytes(&self) -> &[u8];
+  /// Copies the buffer first if it's shared.
+  fn bytes_mut(&mut self) -> &mut [u8];
+  fn clone_shared(&self) -> Box<dyn SharedBuffer>;
+  /// Points this buffer at the same memory as `other`, which has to be the same type.
+  fn share(&mut self, other: &dyn SharedBuffer);
+  fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Clone + AsBytes + 'static> SharedBuffer for SharedMemory<T> {
+  fn bytes(&self) -> &[u8] {
+    self.0.as_bytes()
+  }
+
+  fn bytes_mut(&mut self) -> &mut [u8] {
+    Arc::make_mut(&mut self.0).as_bytes_mut()
+  }
+
+  fn clone_shared(&self) -> Box<dyn SharedBuffer> {
+    Box::new(self.clone())
+  }
+
+  fn share(&mut self, other: &dyn SharedBuffer) {
+    let other = other
+      .as_any()
+      .downcast_ref::<Self>()
+      .expect("Shared buffers should be the same type");
+    self.0 = other.0.clone();
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+}
@@ -1,6 +1,6 @@
 use super::{
-  palette::PALETTE, PPUCPUBusTrait, PPUMemoryTrait, Pixbuf, SpritePriority, PIXEL_BUFFER_HEIGHT,
-  PIXEL_BUFFER_WIDTH, PPU,
+  PPUCPUBusTrait, PPUMemoryTrait, Pixbuf, SpritePriority, PIXEL_BUFFER_HEIGHT, PIXEL_BUFFER_WIDTH,
+  PPU,
 };
 
 impl PPU {
@@ -68,14 +68,25 @@ impl PPU {
       }
     };
 
-    let palette_index =
-      PPU::get_palette_color(palette as u16, pixel as u16, ppu_cpu_bus.ppu_memory_mut()) as usize;
-    let color = PALETTE[palette_index % PALETTE.len()];
+    let mut palette_index = u16::from(PPU::get_palette_color(
+      palette as u16,
+      pixel as u16,
+      ppu_cpu_bus.ppu_memory_mut(),
+    )) & 0x3f;
+    if mask.grayscale() {
+      palette_index &= 0x30;
+    }
+    // the emphasis bits sit above the palette index, in PPUMASK order (red, green, blue)
+    let pixel = palette_index | (u16::from(u8::from(mask) >> 5) << 6);
 
     let x = self.cycle - 1;
     let y = self.scanline;
     if x >= 0 && y >= 0 && x < PIXEL_BUFFER_WIDTH as i32 && y < PIXEL_BUFFER_HEIGHT as i32 {
-      pixbuf.set_pixel(color, u32::try_from(x).unwrap(), u32::try_from(y).unwrap());
+      let (x, y) = (u32::try_from(x).unwrap(), u32::try_from(y).unwrap());
+      pixbuf.frame.set_pixel(pixel, x, y);
+      if x == 0 {
+        pixbuf.frame.ntsc_phases[y as usize] = self.ntsc_phase() as u8;
+      }
     }
   }
 }
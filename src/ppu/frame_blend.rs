@@ -0,0 +1,32 @@
+use super::BYTES_PER_PIXEL;
+
+/// Post-processing that mixes each frame with the one before it, so sprites that games flicker on
+/// alternate frames (to get around the 8 per scanline limit) show up steadily instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameBlend {
+  /// How much of the previous frame to mix in, from 0 (none) to 1 (all of it).
+  Mix(f32),
+  /// Keeps the brighter of the two frames in each channel, so flickering sprites stay solid.
+  Max,
+}
+
+impl FrameBlend {
+  /// Blends RGBA pixels from the previous frame into the current frame's.
+  pub fn blend(self, current: &mut [u8], previous: &[u8]) {
+    for (current, previous) in current
+      .chunks_exact_mut(BYTES_PER_PIXEL as usize)
+      .zip(previous.chunks_exact(BYTES_PER_PIXEL as usize))
+    {
+      for channel in 0..3 {
+        current[channel] = match self {
+          FrameBlend::Mix(amount) => {
+            let amount = amount.clamp(0.0, 1.0);
+            (f32::from(current[channel]) * (1.0 - amount) + f32::from(previous[channel]) * amount)
+              .round() as u8
+          }
+          FrameBlend::Max => current[channel].max(previous[channel]),
+        };
+      }
+    }
+  }
+}
@@ -0,0 +1,34 @@
+use super::{PIXEL_BUFFER_HEIGHT, PIXEL_BUFFER_WIDTH};
+
+/// The picture as the PPU outputs it: a palette index in the low 6 bits of each pixel and the
+/// PPUMASK emphasis bits in bits 6-8. Turning it into colors is left to `Pixbuf::render`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedFrameBuffer {
+  pub pixels: Vec<u16>,
+  // the color subcarrier phase each scanline starts at, for the NTSC filter
+  pub ntsc_phases: [u8; PIXEL_BUFFER_HEIGHT as usize],
+}
+
+impl Default for IndexedFrameBuffer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl IndexedFrameBuffer {
+  pub fn new() -> Self {
+    Self {
+      pixels: vec![0; (PIXEL_BUFFER_WIDTH * PIXEL_BUFFER_HEIGHT) as usize],
+      ntsc_phases: [0; PIXEL_BUFFER_HEIGHT as usize],
+    }
+  }
+
+  pub fn set_pixel(&mut self, pixel: u16, x: u32, y: u32) {
+    self.pixels[(x + y * PIXEL_BUFFER_WIDTH) as usize] = pixel;
+  }
+
+  pub fn scanline(&self, y: u32) -> &[u16] {
+    let start = (y * PIXEL_BUFFER_WIDTH) as usize;
+    &self.pixels[start..start + PIXEL_BUFFER_WIDTH as usize]
+  }
+}
@@ -1,27 +1,46 @@
 mod drawing;
+mod frame_blend;
+mod frame_buffer;
+mod ntsc_filter;
 mod palette;
+mod palette_generator;
 mod pixbuf;
 mod ppu;
 mod ppu_cpu_bus;
 mod ppu_memory;
 mod registers;
+mod screenshot;
 mod scrolling;
 mod sprites;
+mod triple_buffer;
 
+pub use frame_blend::*;
+pub use frame_buffer::*;
+pub use ntsc_filter::*;
+pub use palette::*;
+pub use palette_generator::*;
 pub use pixbuf::*;
 pub use ppu::*;
 pub use ppu_cpu_bus::*;
 pub use ppu_memory::*;
 pub use registers::*;
+pub use screenshot::*;
 pub use sprites::*;
+pub use triple_buffer::*;
 
 #[cfg(test)]
 mod tests {
-  use std::io::BufReader;
+  use std::{io::BufReader, path::Path};
 
-  use crate::nes::{INESRom, NES};
+  use crate::{
+    nes::{INESRom, NES},
+    settings::Overscan,
+  };
 
-  use super::Pixbuf;
+  use super::{
+    screenshot_path, triple_buffer, FrameBlend, NTSCFilter, Palette, PaletteParameters,
+    PalettePreset, Pixbuf, ScreenshotOptions, NTSC_OUTPUT_WIDTH, PALETTE,
+  };
 
   fn run_blargg_ppu_test(rom_data: &[u8]) -> u8 {
     let rom = INESRom::from_reader(&mut BufReader::new(rom_data)).unwrap();
@@ -211,4 +230,282 @@ mod tests {
       error_message
     );
   }
+
+  #[test]
+  fn test_ntsc_filter_solid_colors() {
+    let filter = NTSCFilter::default();
+    let mut output = vec![0; NTSC_OUTPUT_WIDTH as usize * 4];
+    // away from the edges of the line, where the filter window runs out of signal
+    let middle = |output: &[u8]| {
+      let offset = NTSC_OUTPUT_WIDTH as usize * 2;
+      [output[offset], output[offset + 1], output[offset + 2]]
+    };
+
+    filter.filter_scanline(&[0x0f; 256], 0, &mut output);
+    assert_eq!(middle(&output), [0, 0, 0]);
+
+    filter.filter_scanline(&[0x30; 256], 0, &mut output);
+    assert!(middle(&output).iter().all(|channel| *channel > 240));
+
+    for phase in [0, 4, 8] {
+      filter.filter_scanline(&[0x16; 256], phase, &mut output);
+      let [r, g, b] = middle(&output);
+      assert!(r > 2 * g && r > 2 * b, "{:?} isn't red", [r, g, b]);
+    }
+
+    // blue emphasis darkens red
+    filter.filter_scanline(&[0x16 | 0x100; 256], 0, &mut output);
+    assert!(middle(&output)[0] < 180);
+  }
+
+  #[test]
+  fn test_emphasis_palette() {
+    let palette = Palette::default();
+    assert_eq!(palette.color(0x16), PALETTE[0x16]);
+
+    // red emphasis on white dims green and blue
+    let [r, g, b] = palette.color(0x30 | (0b001 << 6));
+    assert_eq!(r, 0xff);
+    assert!(g < 0xc0 && b < 0xc0);
+
+    // red and green together tint yellow
+    let [r, g, b] = palette.color(0x30 | (0b011 << 6));
+    assert_eq!([r, g], [0xff, 0xff]);
+    assert!(b < 0xc0);
+
+    // all three dim everything
+    assert!(palette
+      .color(0x30 | (0b111 << 6))
+      .iter()
+      .all(|value| *value < 0xc0));
+  }
+
+  #[test]
+  fn test_render_indexed_frame() {
+    let mut pixbuf = Pixbuf::new();
+    pixbuf.frame.set_pixel(0x16, 1, 0);
+    pixbuf.render(&Palette::default());
+    let [r, g, b] = PALETTE[0x16];
+    assert_eq!(pixbuf.image.data[4..8], [r, g, b, 255]);
+
+    // the same frame comes out differently through another palette
+    let mut base_colors = PALETTE;
+    base_colors[0x16] = [1, 2, 3];
+    pixbuf.render(&Palette::from_base_colors(&base_colors));
+    assert_eq!(pixbuf.image.data[4..8], [1, 2, 3, 255]);
+  }
+
+  #[test]
+  fn test_load_pal_files() {
+    let base_colors = (0..192).map(|index| index as u8).collect::<Vec<_>>();
+    let palette = Palette::from_pal_bytes(&base_colors).unwrap();
+    assert_eq!(palette.color(0x01), [3, 4, 5]);
+    assert!(palette.color(0x01 | (0b010 << 6))[0] < 3);
+
+    let all_colors = (0..1536).map(|index| (index / 3) as u8).collect::<Vec<_>>();
+    let palette = Palette::from_pal_bytes(&all_colors).unwrap();
+    assert_eq!(palette.color(0x01 | (0b010 << 6)), [129, 129, 129]);
+
+    assert!(Palette::from_pal_bytes(&[0; 100]).is_err());
+  }
+
+  #[test]
+  fn test_generate_palette() {
+    let palette = Palette::generate(&PaletteParameters::default());
+    assert_eq!(palette.color(0x0f), [0, 0, 0]);
+    assert!(palette.color(0x30).iter().all(|channel| *channel > 240));
+    let [r, g, b] = palette.color(0x16);
+    assert!(r > 2 * g && r > 2 * b, "{:?} isn't red", [r, g, b]);
+
+    // turning the hue a third of the way around makes red into blue
+    let palette = Palette::generate(&PaletteParameters {
+      hue: 120.0,
+      ..Default::default()
+    });
+    let [r, g, b] = palette.color(0x16);
+    assert!(b > 2 * r && b > 2 * g, "{:?} isn't blue", [r, g, b]);
+  }
+
+  #[test]
+  fn test_palette_presets() {
+    // the RGB PPUs turn emphasized channels all the way up
+    let rgb = PalettePreset::RGB.palette();
+    assert_eq!(rgb.color(0x0f), [0, 0, 0]);
+    assert_eq!(rgb.color(0x20), [0xff, 0xff, 0xff]);
+    assert_eq!(rgb.color(0x0f | (0b100 << 6)), [0, 0, 0xff]);
+
+    // the 2C04s have the same colors in a different order
+    let rp2c04 = PalettePreset::RP2C04_0001.palette();
+    assert_eq!(rp2c04.color(0x08), rgb.color(0x20));
+    assert_eq!(rp2c04.color(0x09), rgb.color(0x00));
+    assert_eq!(PalettePreset::RC2C05.palette(), rgb);
+
+    // PAL swaps red and green emphasis
+    let pal_emphasis = PalettePreset::PALEmphasis.palette();
+    assert_eq!(
+      pal_emphasis.color(0x30 | (0b001 << 6)),
+      Palette::default().color(0x30 | (0b010 << 6))
+    );
+
+    // and the 2C07 has its own hues too
+    let pal = "2C07".parse::<PalettePreset>().unwrap().palette();
+    let ntsc = Palette::generate(&PaletteParameters::default());
+    assert_ne!(pal.color(0x16), ntsc.color(0x16));
+    assert_eq!(pal.color(0x0f), [0, 0, 0]);
+    let shifted = Palette::generate(&PaletteParameters {
+      hue: -15.0,
+      ..Default::default()
+    });
+    assert_eq!(
+      pal.color(0x16 | (0b001 << 6)),
+      shifted.color(0x16 | (0b010 << 6))
+    );
+  }
+
+  #[test]
+  fn test_screenshot_png() {
+    let png_size = |png: &[u8]| {
+      let size = |offset: usize| u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap());
+      (size(16), size(20))
+    };
+
+    let mut pixbuf = Pixbuf::new();
+    let png = pixbuf.encode_png(ScreenshotOptions::default()).unwrap();
+    assert_eq!(&png[1..4], b"PNG");
+    assert_eq!(png_size(&png), (256, 240));
+
+    let options = ScreenshotOptions {
+      scale: 3,
+      overscan: Overscan {
+        top: 8,
+        bottom: 8,
+        left: 0,
+        right: 4,
+      },
+    };
+    assert_eq!(png_size(&pixbuf.encode_png(options).unwrap()), (756, 672));
+
+    pixbuf.set_ntsc_filter(Some(NTSCFilter::default()));
+    assert_eq!(png_size(&pixbuf.encode_png(options).unwrap()), (1512, 1344));
+
+    assert_eq!(
+      screenshot_path(Path::new("/roms/Some Game (U).nes"), 1234),
+      Path::new("/roms/Some Game (U)-001234.png")
+    );
+  }
+
+  #[test]
+  fn test_frame_blend() {
+    let mut base_colors = PALETTE;
+    base_colors[0x01] = [200, 0, 100];
+    base_colors[0x02] = [0, 100, 50];
+    let palette = Palette::from_base_colors(&base_colors);
+
+    let mut pixbuf = Pixbuf::new();
+    pixbuf.set_frame_blend(Some(FrameBlend::Mix(0.5)));
+    pixbuf.frame.set_pixel(0x01, 0, 0);
+    pixbuf.start_frame();
+    pixbuf.frame.set_pixel(0x02, 0, 0);
+
+    pixbuf.render(&palette);
+    assert_eq!(pixbuf.image.data[0..4], [100, 50, 75, 255]);
+
+    pixbuf.set_frame_blend(Some(FrameBlend::Max));
+    pixbuf.render(&palette);
+    assert_eq!(pixbuf.image.data[0..4], [200, 100, 100, 255]);
+
+    pixbuf.set_frame_blend(None);
+    pixbuf.render(&palette);
+    assert_eq!(pixbuf.image.data[0..4], [0, 100, 50, 255]);
+  }
+
+  #[test]
+  fn test_ntsc_phase_advances_each_frame() {
+    let rom = INESRom::from_reader(&mut BufReader::new(
+      include_bytes!("../../smoketest/nestest.nes").as_slice(),
+    ))
+    .unwrap();
+    let (sender, _receiver) = smol::channel::unbounded();
+    let mut machine = NES::from_rom(rom, sender);
+    let mut pixbuf = Pixbuf::new();
+
+    machine.execute_frame(&mut pixbuf);
+    let mut phase = pixbuf.frame.ntsc_phases[0];
+    let mut skipped_dots = 0;
+    for _ in 0..10 {
+      let mask = machine
+        .state
+        .cartridge
+        .ppu_cpu_bus_mut()
+        .ppu_memory_mut()
+        .mask();
+      let skips_dot = machine.state.ppu.frame_count % 2 == 1
+        && (mask.render_background() || mask.render_sprites());
+      machine.execute_frame(&mut pixbuf);
+
+      // 262 scanlines of 341 dots is 4 samples past a whole number of periods, and skipping a dot
+      // takes 8 back off
+      let next_phase = pixbuf.frame.ntsc_phases[0];
+      assert_eq!(next_phase, (phase + if skips_dot { 8 } else { 4 }) % 12);
+      phase = next_phase;
+      skipped_dots += usize::from(skips_dot);
+    }
+    assert!(skipped_dots > 0);
+  }
+
+  #[test]
+  fn test_unlimited_sprites() {
+    let rom = INESRom::from_reader(&mut BufReader::new(
+      include_bytes!("../../smoketest/nestest.nes").as_slice(),
+    ))
+    .unwrap();
+    let (sender, _receiver) = smol::channel::unbounded();
+    let mut machine = NES::from_rom(rom, sender);
+    let state = &mut machine.state;
+
+    for (index, entry) in state
+      .cartridge
+      .ppu_cpu_bus_mut()
+      .oam_mut()
+      .iter_mut()
+      .take(10)
+      .enumerate()
+    {
+      entry.set_y(20);
+      entry.set_x(index as u8 * 8);
+    }
+    state.ppu.scanline = 25;
+
+    for (unlimited_sprites, sprite_count) in [(false, 8), (true, 10)] {
+      let ppu_cpu_bus = state.cartridge.ppu_cpu_bus_mut();
+      ppu_cpu_bus.status_mut().set_sprite_overflow(false);
+      state.ppu.unlimited_sprites = unlimited_sprites;
+      state.ppu.evaluate_scanline_sprites(ppu_cpu_bus);
+
+      assert_eq!(state.ppu.sprite_scanline.len(), sprite_count);
+      // the game sees the overflow either way
+      assert!(ppu_cpu_bus.status_mut().sprite_overflow());
+    }
+  }
+
+  #[test]
+  fn test_triple_buffer() {
+    let (mut writer, mut reader) = triple_buffer(0);
+    assert!(!reader.has_new_frame());
+
+    *writer.back_buffer() = 1;
+    writer.publish();
+    assert!(reader.has_new_frame());
+    assert_eq!(*reader.read(), 1);
+    assert!(!reader.has_new_frame());
+
+    // the reader only sees the latest of the frames published since it last looked
+    for frame in 2..=4 {
+      *writer.back_buffer() = frame;
+      writer.publish();
+    }
+    assert_eq!(*reader.read(), 4);
+    assert_eq!(*reader.read(), 4);
+    assert_ne!(*writer.back_buffer(), 4);
+  }
 }
@@ -0,0 +1,164 @@
+use std::f32::consts::PI;
+
+use super::PIXEL_BUFFER_WIDTH;
+
+/// Output pixels per NES pixel. The signal has 8 samples per pixel, decoded every 4 samples.
+const OUTPUT_PIXELS_PER_PIXEL: u32 = 2;
+pub const NTSC_OUTPUT_WIDTH: u32 = PIXEL_BUFFER_WIDTH * OUTPUT_PIXELS_PER_PIXEL;
+
+const SAMPLES_PER_PIXEL: usize = 8;
+const SAMPLES_PER_LINE: usize = PIXEL_BUFFER_WIDTH as usize * SAMPLES_PER_PIXEL;
+const SAMPLES_PER_OUTPUT_PIXEL: usize = SAMPLES_PER_PIXEL / OUTPUT_PIXELS_PER_PIXEL as usize;
+// one period of the color subcarrier
+const CHROMA_WINDOW: usize = 12;
+// samples between the start of the subcarrier period and the colorburst the TV locks onto
+const COLORBURST_PHASE: usize = 4;
+// demodulating a square wave rather than a sine only recovers half its amplitude
+const CHROMA_GAIN: f32 = 2.0;
+
+// composite voltages for the four luma levels, normalized so black is 0 and white is 1
+const BLACK: f32 = 0.518;
+const WHITE: f32 = 1.962;
+const LOW_LEVELS: [f32; 4] = [0.350, 0.518, 0.962, 1.550];
+const HIGH_LEVELS: [f32; 4] = [1.094, 1.506, 1.962, 1.962];
+const EMPHASIS_ATTENUATION: f32 = 0.746;
+
+/// Recreates the look of the NES's composite video output, after blargg's nes_ntsc: each scanline
+/// of palette indices and emphasis bits is encoded to the signal the PPU would generate, then
+/// decoded back to RGB like a TV would. That brings back artifact colors, fringing on sharp edges
+/// and the dot crawl between frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NTSCFilter {
+  /// From -1 (softest) to 1. Sharper pictures let more chroma leak into luma, with more artifacts.
+  pub sharpness: f32,
+}
+
+impl Default for NTSCFilter {
+  fn default() -> Self {
+    Self { sharpness: 0.0 }
+  }
+}
+
+fn in_color_phase(color: u16, phase: usize) -> bool {
+  (usize::from(color) + phase) % 12 < 6
+}
+
+/// The normalized signal level for a pixel (palette index plus emphasis bits from PPUMASK, in bits
+/// 6-8) at a subcarrier phase.
+fn signal_level(pixel: u16, phase: usize) -> f32 {
+  let color = pixel & 0x0f;
+  let level = if color > 0x0d {
+    1
+  } else {
+    usize::from((pixel >> 4) & 0x03)
+  };
+  let emphasis = pixel >> 6;
+
+  let mut low = LOW_LEVELS[level];
+  let mut high = HIGH_LEVELS[level];
+  if color == 0x00 {
+    low = high;
+  } else if color > 0x0c {
+    high = low;
+  }
+
+  let mut signal = if in_color_phase(color, phase) {
+    high
+  } else {
+    low
+  };
+
+  // each emphasis bit darkens the part of the subcarrier period where its color is
+  if (emphasis & 0x01 != 0 && in_color_phase(0x0c, phase))
+    || (emphasis & 0x02 != 0 && in_color_phase(0x04, phase))
+    || (emphasis & 0x04 != 0 && in_color_phase(0x08, phase))
+  {
+    signal *= EMPHASIS_ATTENUATION;
+  }
+
+  (signal - BLACK) / (WHITE - BLACK)
+}
+
+// the gamma TVs of the time were built for, converted to the ~2.2 of modern displays
+pub const NTSC_GAMMA: f32 = 1.8;
+
+fn gamma_fix(value: f32, gamma: f32) -> u8 {
+  if value <= 0.0 {
+    0
+  } else {
+    (value.powf(2.2 / gamma) * 255.0).min(255.0) as u8
+  }
+}
+
+/// Converts a decoded composite color to RGB.
+pub fn yiq_to_rgb(y: f32, i: f32, q: f32, gamma: f32) -> [u8; 3] {
+  [
+    gamma_fix(y + 0.946_882 * i + 0.623_557 * q, gamma),
+    gamma_fix(y - 0.274_788 * i - 0.635_691 * q, gamma),
+    gamma_fix(y - 1.108_545 * i + 1.709_007 * q, gamma),
+  ]
+}
+
+/// Decodes one subcarrier period of a pixel's signal to YIQ, which is what a TV shows for a large
+/// area of a single color.
+pub fn composite_yiq(pixel: u16) -> [f32; 3] {
+  let (mut y, mut i, mut q) = (0.0, 0.0, 0.0);
+  for phase in 0..CHROMA_WINDOW {
+    let level = signal_level(pixel, phase);
+    let angle = PI * ((phase + COLORBURST_PHASE) % 12) as f32 / 6.0;
+    y += level;
+    i += level * angle.cos();
+    q += level * angle.sin();
+  }
+
+  let chroma_scale = CHROMA_GAIN / CHROMA_WINDOW as f32;
+  [y / CHROMA_WINDOW as f32, i * chroma_scale, q * chroma_scale]
+}
+
+impl NTSCFilter {
+  /// Filters a scanline into `NTSC_OUTPUT_WIDTH` RGBA pixels. `phase` is the subcarrier phase the
+  /// line starts at (0-11), which shifts from line to line and frame to frame.
+  pub fn filter_scanline(&self, pixels: &[u16], phase: usize, output: &mut [u8]) {
+    let mut signal = [0.0; SAMPLES_PER_LINE];
+    for (x, pixel) in pixels.iter().enumerate() {
+      for sample in 0..SAMPLES_PER_PIXEL {
+        let index = x * SAMPLES_PER_PIXEL + sample;
+        signal[index] = signal_level(*pixel, (phase + index) % 12);
+      }
+    }
+
+    let cos: [f32; 12] = std::array::from_fn(|phase| (PI * phase as f32 / 6.0).cos());
+    let sin: [f32; 12] = std::array::from_fn(|phase| (PI * phase as f32 / 6.0).sin());
+
+    // sharper pictures average luma over fewer samples than a full subcarrier period
+    let luma_window = (CHROMA_WINDOW as f32 - 4.0 * (self.sharpness.clamp(-1.0, 1.0) + 1.0))
+      .round()
+      .max(4.0) as usize;
+
+    for (x, rgba) in output
+      .chunks_exact_mut(4)
+      .take(NTSC_OUTPUT_WIDTH as usize)
+      .enumerate()
+    {
+      let center = x * SAMPLES_PER_OUTPUT_PIXEL + SAMPLES_PER_OUTPUT_PIXEL / 2;
+
+      let luma_start = center.saturating_sub(luma_window / 2);
+      let luma_end = (luma_start + luma_window).min(SAMPLES_PER_LINE);
+      let y = signal[luma_start..luma_end].iter().sum::<f32>() / luma_window as f32;
+
+      let chroma_start = center.saturating_sub(CHROMA_WINDOW / 2);
+      let chroma_end = (chroma_start + CHROMA_WINDOW).min(SAMPLES_PER_LINE);
+      let (mut i, mut q) = (0.0, 0.0);
+      for (index, level) in signal[chroma_start..chroma_end].iter().enumerate() {
+        let sample_phase = (phase + chroma_start + index + COLORBURST_PHASE) % 12;
+        i += level * cos[sample_phase];
+        q += level * sin[sample_phase];
+      }
+      i *= CHROMA_GAIN / CHROMA_WINDOW as f32;
+      q *= CHROMA_GAIN / CHROMA_WINDOW as f32;
+
+      let [r, g, b] = yiq_to_rgb(y, i, q, NTSC_GAMMA);
+      rgba.copy_from_slice(&[r, g, b, 255]);
+    }
+  }
+}
@@ -1,3 +1,10 @@
+use std::{fs, path::Path};
+
+use anyhow::anyhow;
+use strum::{Display, EnumIter, EnumString};
+
+use super::PaletteParameters;
+
 pub const PALETTE: [[u8; 3]; 64] = [
   [0x62, 0x62, 0x62],
   [0x00, 0x1f, 0xb2],
@@ -64,3 +71,213 @@ pub const PALETTE: [[u8; 3]; 64] = [
   [0x00, 0x00, 0x00],
   [0x00, 0x00, 0x00],
 ];
+
+// The RGB PPUs' master palette, 3 bits per channel. The 2C05 uses it as is; each 2C04 variant
+// draws from the same colors in its own order.
+const RGB_PPU_LEVELS: [&str; 64] = [
+  "333", "014", "006", "326", "403", "503", "510", "420", "320", "120", "031", "040", "022", "000",
+  "000", "000", "555", "036", "027", "407", "507", "704", "700", "630", "430", "140", "040", "053",
+  "044", "000", "000", "000", "777", "357", "447", "637", "707", "737", "740", "750", "660", "360",
+  "070", "276", "077", "000", "000", "000", "777", "567", "657", "757", "747", "755", "764", "772",
+  "773", "572", "473", "276", "467", "000", "000", "000",
+];
+
+// Where each 2C04 variant's colors are in the 2C03's palette. Vs. System games were made for a
+// particular variant, so their colors come out scrambled on any other.
+const RP2C04_0001: [u8; 64] = [
+  0x35, 0x23, 0x16, 0x22, 0x1c, 0x09, 0x1d, 0x15, 0x20, 0x00, 0x27, 0x05, 0x04, 0x28, 0x08, 0x20,
+  0x21, 0x3e, 0x1f, 0x29, 0x3c, 0x32, 0x36, 0x12, 0x3f, 0x2b, 0x2e, 0x1e, 0x3d, 0x2d, 0x24, 0x01,
+  0x0e, 0x31, 0x33, 0x2a, 0x2c, 0x0c, 0x1b, 0x14, 0x2e, 0x07, 0x34, 0x06, 0x13, 0x02, 0x26, 0x2e,
+  0x2e, 0x19, 0x10, 0x0a, 0x39, 0x03, 0x37, 0x17, 0x0f, 0x11, 0x0b, 0x0d, 0x38, 0x25, 0x18, 0x3a,
+];
+
+const RP2C04_0002: [u8; 64] = [
+  0x2e, 0x27, 0x18, 0x39, 0x3a, 0x25, 0x1c, 0x31, 0x16, 0x13, 0x38, 0x34, 0x20, 0x23, 0x3c, 0x0b,
+  0x0f, 0x21, 0x06, 0x3d, 0x1b, 0x29, 0x1e, 0x22, 0x1d, 0x24, 0x0e, 0x2b, 0x32, 0x08, 0x2e, 0x03,
+  0x04, 0x36, 0x26, 0x33, 0x11, 0x1f, 0x10, 0x02, 0x14, 0x3f, 0x00, 0x09, 0x12, 0x2e, 0x28, 0x20,
+  0x3e, 0x0d, 0x2a, 0x17, 0x0c, 0x01, 0x15, 0x19, 0x2e, 0x2c, 0x07, 0x37, 0x35, 0x05, 0x0a, 0x2f,
+];
+
+const RP2C04_0003: [u8; 64] = [
+  0x14, 0x25, 0x3a, 0x10, 0x0b, 0x20, 0x31, 0x09, 0x01, 0x2e, 0x36, 0x08, 0x15, 0x3d, 0x3e, 0x3c,
+  0x22, 0x1c, 0x05, 0x12, 0x19, 0x18, 0x17, 0x1b, 0x00, 0x03, 0x2e, 0x02, 0x16, 0x06, 0x34, 0x35,
+  0x23, 0x0f, 0x0e, 0x37, 0x0d, 0x27, 0x26, 0x20, 0x29, 0x04, 0x21, 0x24, 0x11, 0x2d, 0x2e, 0x1f,
+  0x2c, 0x1e, 0x39, 0x33, 0x07, 0x2a, 0x28, 0x1d, 0x0a, 0x2e, 0x32, 0x38, 0x13, 0x2b, 0x3f, 0x0c,
+];
+
+const RP2C04_0004: [u8; 64] = [
+  0x18, 0x03, 0x1c, 0x28, 0x2e, 0x35, 0x01, 0x17, 0x10, 0x1f, 0x2a, 0x0e, 0x36, 0x37, 0x0b, 0x39,
+  0x25, 0x1e, 0x12, 0x34, 0x2e, 0x1d, 0x06, 0x26, 0x3e, 0x1b, 0x22, 0x19, 0x04, 0x2e, 0x3a, 0x21,
+  0x05, 0x0a, 0x07, 0x02, 0x13, 0x14, 0x00, 0x15, 0x0c, 0x3d, 0x11, 0x0f, 0x0d, 0x38, 0x2d, 0x24,
+  0x33, 0x20, 0x08, 0x16, 0x3f, 0x2b, 0x20, 0x3c, 0x2e, 0x27, 0x23, 0x31, 0x29, 0x32, 0x2c, 0x09,
+];
+
+const PALETTE_SIZE: usize = 64 * 8;
+
+// the 2C07 generates each color at a subcarrier phase 15 degrees off the 2C02's
+const PAL_HUE_SHIFT: f32 = -15.0;
+
+// how much emphasis darkens the colors it doesn't emphasize
+const EMPHASIS_ATTENUATION: f32 = 0.746;
+
+/// A color for each of the 64 palette indices under each of the 8 combinations of PPUMASK's
+/// emphasis bits. Colors are looked up by palette index, with the emphasis bits above it in bits
+/// 6-8 (red, green, blue).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+  colors: Vec<[u8; 3]>,
+}
+
+impl Default for Palette {
+  fn default() -> Self {
+    Self::from_base_colors(&PALETTE)
+  }
+}
+
+impl Palette {
+  /// Derives the emphasized colors from the 64 unemphasized ones. Each emphasis bit dims the
+  /// channels it doesn't emphasize, and setting all three dims everything.
+  pub fn from_base_colors(base_colors: &[[u8; 3]; 64]) -> Self {
+    let colors = (0..8)
+      .flat_map(|emphasis: u8| {
+        base_colors.iter().map(move |color| {
+          let mut color = *color;
+          for (channel, value) in color.iter_mut().enumerate() {
+            if emphasis != 0 && (emphasis == 0b111 || emphasis & (1 << channel) == 0) {
+              *value = (f32::from(*value) * EMPHASIS_ATTENUATION) as u8;
+            }
+          }
+          color
+        })
+      })
+      .collect();
+
+    Self { colors }
+  }
+
+  /// Takes all 512 colors, in the order `color` looks them up.
+  pub fn from_colors(colors: Vec<[u8; 3]>) -> Result<Self, anyhow::Error> {
+    if colors.len() != PALETTE_SIZE {
+      return Err(anyhow!(
+        "Expected {} colors, got {}",
+        PALETTE_SIZE,
+        colors.len()
+      ));
+    }
+
+    Ok(Self { colors })
+  }
+
+  /// Reads a `.pal` file's contents: either 64 RGB colors, with the emphasized ones derived from
+  /// them, or all 512.
+  pub fn from_pal_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+    let colors = bytes
+      .chunks_exact(3)
+      .map(|color| [color[0], color[1], color[2]])
+      .collect::<Vec<_>>();
+
+    match bytes.len() {
+      192 => Ok(Self::from_base_colors(&colors.try_into().unwrap())),
+      1536 => Self::from_colors(colors),
+      len => Err(anyhow!(
+        "Expected a palette of 192 or 1536 bytes, got {} bytes",
+        len
+      )),
+    }
+  }
+
+  pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+    Self::from_pal_bytes(&fs::read(path)?)
+  }
+
+  pub fn color(&self, pixel: u16) -> [u8; 3] {
+    self.colors[usize::from(pixel) % self.colors.len()]
+  }
+
+  /// Swaps the colors for PPUMASK's red and green emphasis bits, which the PAL PPU has the other
+  /// way around.
+  fn with_pal_emphasis(&self) -> Self {
+    let colors = (0..PALETTE_SIZE)
+      .map(|pixel| {
+        let emphasis = pixel >> 6;
+        let swapped = (emphasis & 0b100) | ((emphasis & 0b01) << 1) | ((emphasis & 0b10) >> 1);
+        self.colors[(swapped << 6) | (pixel & 0x3f)]
+      })
+      .collect();
+    Self { colors }
+  }
+}
+
+/// The palettes of the different PPU models.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumIter, EnumString)]
+pub enum PalettePreset {
+  /// The NTSC PPU in the Famicom and NES.
+  #[default]
+  #[allow(clippy::upper_case_acronyms)]
+  #[strum(serialize = "2C02")]
+  NTSC,
+  /// The PAL PPU, decoded by a PAL TV.
+  #[allow(clippy::upper_case_acronyms)]
+  #[strum(serialize = "2C07")]
+  PAL,
+  /// The NTSC colors with the red and green emphasis bits swapped, like on the PAL PPU.
+  #[strum(serialize = "2C02, PAL emphasis")]
+  PALEmphasis,
+  /// The PPUs with RGB output in the PlayChoice-10 and Famicom Titler.
+  #[allow(clippy::upper_case_acronyms)]
+  #[strum(serialize = "2C03")]
+  RGB,
+  #[strum(serialize = "2C04-0001")]
+  RP2C04_0001,
+  #[strum(serialize = "2C04-0002")]
+  RP2C04_0002,
+  #[strum(serialize = "2C04-0003")]
+  RP2C04_0003,
+  #[strum(serialize = "2C04-0004")]
+  RP2C04_0004,
+  /// The Vs. System PPU that has the 2C03's palette but swaps PPUCTRL and PPUMASK.
+  #[strum(serialize = "2C05")]
+  RC2C05,
+}
+
+impl PalettePreset {
+  pub fn palette(self) -> Palette {
+    match self {
+      PalettePreset::NTSC => Palette::default(),
+      PalettePreset::RGB | PalettePreset::RC2C05 => {
+        Self::rgb_ppu_palette(&std::array::from_fn(|index| index as u8))
+      }
+      PalettePreset::RP2C04_0001 => Self::rgb_ppu_palette(&RP2C04_0001),
+      PalettePreset::RP2C04_0002 => Self::rgb_ppu_palette(&RP2C04_0002),
+      PalettePreset::RP2C04_0003 => Self::rgb_ppu_palette(&RP2C04_0003),
+      PalettePreset::RP2C04_0004 => Self::rgb_ppu_palette(&RP2C04_0004),
+      PalettePreset::PAL => Palette::generate(&PaletteParameters {
+        hue: PAL_HUE_SHIFT,
+        ..Default::default()
+      })
+      .with_pal_emphasis(),
+      PalettePreset::PALEmphasis => Palette::default().with_pal_emphasis(),
+    }
+  }
+
+  /// `order` gives where each palette index's color is in the 2C03's palette.
+  fn rgb_ppu_palette(order: &[u8; 64]) -> Palette {
+    // emphasis on the RGB PPUs turns its channel all the way up instead of dimming the others
+    let colors = (0..8)
+      .flat_map(|emphasis: u8| {
+        order.iter().map(move |&index| {
+          let mut color = [0; 3];
+          for (channel, level) in RGB_PPU_LEVELS[usize::from(index)].bytes().enumerate() {
+            color[channel] = if emphasis & (1 << channel) != 0 {
+              0xff
+            } else {
+              ((u16::from(level - b'0') * 0xff) / 7) as u8
+            };
+          }
+          color
+        })
+      })
+      .collect();
+    Palette { colors }
+  }
+}
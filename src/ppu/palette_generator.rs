@@ -0,0 +1,48 @@
+use super::{composite_yiq, yiq_to_rgb, Palette, NTSC_GAMMA};
+
+/// Knobs for `Palette::generate`, like the picture controls on a TV.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaletteParameters {
+  /// Rotates every color's hue, in degrees.
+  pub hue: f32,
+  pub saturation: f32,
+  pub contrast: f32,
+  pub brightness: f32,
+  pub gamma: f32,
+}
+
+impl Default for PaletteParameters {
+  fn default() -> Self {
+    Self {
+      hue: 0.0,
+      saturation: 1.0,
+      contrast: 1.0,
+      brightness: 0.0,
+      gamma: NTSC_GAMMA,
+    }
+  }
+}
+
+impl Palette {
+  /// Computes every color from the composite signal the PPU generates for it, emphasis included,
+  /// decoded the way a TV with these settings would.
+  pub fn generate(parameters: &PaletteParameters) -> Self {
+    let (hue_sin, hue_cos) = parameters.hue.to_radians().sin_cos();
+    let colors = (0..512)
+      .map(|pixel| {
+        let [y, i, q] = composite_yiq(pixel);
+        let (i, q) = (i * hue_cos - q * hue_sin, i * hue_sin + q * hue_cos);
+        let chroma = parameters.saturation * parameters.contrast;
+
+        yiq_to_rgb(
+          y * parameters.contrast + parameters.brightness,
+          i * chroma,
+          q * chroma,
+          parameters.gamma,
+        )
+      })
+      .collect();
+
+    Self::from_colors(colors).unwrap()
+  }
+}
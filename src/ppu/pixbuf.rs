@@ -1,31 +1,117 @@
+use crate::debugger::RgbaImage;
+
+use super::{FrameBlend, IndexedFrameBuffer, NTSCFilter, Palette, NTSC_OUTPUT_WIDTH};
+
 pub const PIXEL_BUFFER_WIDTH: u32 = 256;
 pub const PIXEL_BUFFER_HEIGHT: u32 = 240;
 pub const BYTES_PER_PIXEL: u32 = 4;
 pub const PIXEL_BUFFER_SIZE: usize = 256 * 240 * 4;
 
+// each filtered scanline is drawn twice so the picture keeps its shape
+pub const NTSC_OUTPUT_HEIGHT: u32 = PIXEL_BUFFER_HEIGHT * 2;
+
 pub struct Pixbuf {
-  pub data: [u8; PIXEL_BUFFER_SIZE],
+  /// What the PPU draws into.
+  pub frame: IndexedFrameBuffer,
+  /// The last frame the PPU finished before `frame`, kept while frame blending is on.
+  pub previous_frame: IndexedFrameBuffer,
+  /// `frame` in RGBA, through the NTSC filter if it's on, as of the last `render`.
+  pub image: RgbaImage,
+  pub ntsc: Option<NTSCFilter>,
+  pub blend: Option<FrameBlend>,
 }
 
 impl Default for Pixbuf {
-    fn default() -> Self {
-        Self::new()
-    }
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl Pixbuf {
   pub fn new() -> Self {
     Self {
-      data: [0; PIXEL_BUFFER_SIZE],
+      frame: IndexedFrameBuffer::new(),
+      previous_frame: IndexedFrameBuffer::new(),
+      image: RgbaImage::new(PIXEL_BUFFER_WIDTH, PIXEL_BUFFER_HEIGHT),
+      ntsc: None,
+      blend: None,
+    }
+  }
+
+  pub fn set_frame_blend(&mut self, blend: Option<FrameBlend>) {
+    self.blend = blend;
+  }
+
+  /// Called by the PPU as it starts drawing a frame, while `frame` still holds the last one.
+  pub fn start_frame(&mut self) {
+    if self.blend.is_some() {
+      self.previous_frame.clone_from(&self.frame);
     }
   }
 
-  pub fn set_pixel(&mut self, color: [u8; 3], x: u32, y: u32) {
-    let offset = (x + (y * PIXEL_BUFFER_WIDTH)) * BYTES_PER_PIXEL;
-    let pixel = self
-      .data
-      .get_mut((offset as usize)..((offset + BYTES_PER_PIXEL) as usize))
-      .unwrap();
-    pixel.copy_from_slice(&[color[0], color[1], color[2], 255]);
+  pub fn set_ntsc_filter(&mut self, filter: Option<NTSCFilter>) {
+    self.ntsc = filter;
+    self.resize_image();
+  }
+
+  // the image can be swapped out for one that was rendered with the filter toggled
+  fn resize_image(&mut self) {
+    let (width, height) = match self.ntsc {
+      Some(_) => (NTSC_OUTPUT_WIDTH, NTSC_OUTPUT_HEIGHT),
+      None => (PIXEL_BUFFER_WIDTH, PIXEL_BUFFER_HEIGHT),
+    };
+    if (self.image.width, self.image.height) != (width, height) {
+      self.image = RgbaImage::new(width, height);
+    }
+  }
+
+  /// Converts the indexed frame to RGBA: through the NTSC filter if one is set, otherwise by
+  /// looking each pixel up in `palette`, then blends in the previous frame if blending is on. This
+  /// can be rerun on the same frame, e.g. after changing the palette while paused.
+  pub fn render(&mut self, palette: &Palette) {
+    self.resize_image();
+    match &self.ntsc {
+      Some(filter) => {
+        let row_size = (NTSC_OUTPUT_WIDTH * BYTES_PER_PIXEL) as usize;
+        let mut previous_row = vec![0; row_size];
+        for (y, rows) in self.image.data.chunks_exact_mut(row_size * 2).enumerate() {
+          let (first_row, second_row) = rows.split_at_mut(row_size);
+          let y = y as u32;
+          filter.filter_scanline(
+            self.frame.scanline(y),
+            usize::from(self.frame.ntsc_phases[y as usize]),
+            first_row,
+          );
+          if let Some(blend) = self.blend {
+            filter.filter_scanline(
+              self.previous_frame.scanline(y),
+              usize::from(self.previous_frame.ntsc_phases[y as usize]),
+              &mut previous_row,
+            );
+            blend.blend(first_row, &previous_row);
+          }
+          second_row.copy_from_slice(first_row);
+        }
+      }
+      None => {
+        render_with_palette(&self.frame, palette, &mut self.image.data);
+        if let Some(blend) = self.blend {
+          let mut previous_data = vec![0; PIXEL_BUFFER_SIZE];
+          render_with_palette(&self.previous_frame, palette, &mut previous_data);
+          blend.blend(&mut self.image.data, &previous_data);
+        }
+      }
+    }
+  }
+}
+
+fn render_with_palette(frame: &IndexedFrameBuffer, palette: &Palette, data: &mut [u8]) {
+  for (pixel, rgba) in frame
+    .pixels
+    .iter()
+    .zip(data.chunks_exact_mut(BYTES_PER_PIXEL as usize))
+  {
+    let [r, g, b] = palette.color(*pixel);
+    rgba.copy_from_slice(&[r, g, b, 255]);
   }
 }
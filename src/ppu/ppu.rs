@@ -1,4 +1,9 @@
-use super::{ActiveSprite, PPUCPUBusTrait, Pixbuf};
+use super::{ActiveSprite, PPUCPUBusTrait, Pixbuf, MAX_SPRITES_PER_SCANLINE, OAM_SPRITES};
+
+// the color subcarrier repeats every 12 samples, and each dot is 8 of them
+const NTSC_PHASES: usize = 12;
+const NTSC_SAMPLES_PER_DOT: usize = 8;
+const DOTS_PER_SCANLINE: usize = 341;
 
 #[derive(Debug, Clone, Copy)]
 pub enum PPUAddressLatch {
@@ -20,10 +25,16 @@ pub struct PPU {
   pub bg_shifter_pattern_high: u16,
   pub bg_shifter_attrib_low: u16,
   pub bg_shifter_attrib_high: u16,
-  pub sprite_shifter_pattern_low: [u8; 8],
-  pub sprite_shifter_pattern_high: [u8; 8],
+  pub sprite_shifter_pattern_low: [u8; OAM_SPRITES],
+  pub sprite_shifter_pattern_high: [u8; OAM_SPRITES],
   pub frame_count: u64,
+  /// See `ntsc_phase`.
+  pub scanline_ntsc_phase: u8,
   pub status_register_read_last_tick: bool,
+  /// Draws every sprite on a scanline instead of only the first 8, to get rid of flicker. The
+  /// game still sees the sprite overflow flag the real PPU would set. Off by default, as it's not
+  /// how the hardware behaves.
+  pub unlimited_sprites: bool,
 }
 
 impl Default for PPU {
@@ -37,7 +48,7 @@ impl PPU {
     Self {
       cycle: 0,
       scanline: -1,
-      sprite_scanline: Vec::with_capacity(8),
+      sprite_scanline: Vec::with_capacity(MAX_SPRITES_PER_SCANLINE),
       bg_next_tile_attrib: 0,
       bg_next_tile_id: 0,
       bg_next_tile_low: 0,
@@ -46,10 +57,12 @@ impl PPU {
       bg_shifter_attrib_low: 0,
       bg_shifter_pattern_high: 0,
       bg_shifter_pattern_low: 0,
-      sprite_shifter_pattern_low: [0; 8],
-      sprite_shifter_pattern_high: [0; 8],
+      sprite_shifter_pattern_low: [0; OAM_SPRITES],
+      sprite_shifter_pattern_high: [0; OAM_SPRITES],
       frame_count: 0,
+      scanline_ntsc_phase: 0,
       status_register_read_last_tick: false,
+      unlimited_sprites: false,
     }
   }
 
@@ -59,8 +72,8 @@ impl PPU {
     status.set_sprite_zero_hit(false);
     status.set_sprite_overflow(false);
 
-    self.sprite_shifter_pattern_low = [0; 8];
-    self.sprite_shifter_pattern_high = [0; 8];
+    self.sprite_shifter_pattern_low = [0; OAM_SPRITES];
+    self.sprite_shifter_pattern_high = [0; OAM_SPRITES];
   }
 
   fn update_registers_on_renderable_scanline(&mut self, ppu_cpu_bus: &mut dyn PPUCPUBusTrait) {
@@ -101,10 +114,23 @@ impl PPU {
     }
   }
 
+  /// The color subcarrier's phase (0-11) at the start of the current scanline. It carries on from
+  /// every dot the PPU has output, so it moves 4 samples on with each 341 dot scanline, and the dot
+  /// skipped on odd frames while rendering sets it 8 samples back.
+  pub fn ntsc_phase(&self) -> usize {
+    usize::from(self.scanline_ntsc_phase)
+  }
+
+  fn advance_ntsc_phase(&mut self, dots: usize) {
+    let phase = usize::from(self.scanline_ntsc_phase) + dots * NTSC_SAMPLES_PER_DOT;
+    self.scanline_ntsc_phase = (phase % NTSC_PHASES) as u8;
+  }
+
   fn increment_cycle_and_scanline(&mut self) {
     self.cycle += 1;
 
     if self.cycle >= 341 {
+      self.advance_ntsc_phase(DOTS_PER_SCANLINE);
       self.cycle = 0;
       self.scanline += 1;
 
@@ -126,11 +152,14 @@ impl PPU {
         let mask = ppu_cpu_bus.ppu_memory_mut().mask();
         if mask.render_background() || mask.render_sprites() {
           self.cycle = 1;
+          // 12 dots are a whole number of subcarrier periods, so 11 more is the same as one fewer
+          self.advance_ntsc_phase(NTSC_PHASES - 1);
         }
       }
 
       if self.scanline == -1 && self.cycle == 1 {
         self.start_frame(ppu_cpu_bus);
+        pixbuf.start_frame();
       }
 
       self.update_registers_on_renderable_scanline(ppu_cpu_bus);
@@ -30,7 +30,10 @@ pub trait PPUCPUBusTrait {
   fn vram_addr(&self) -> &PPULoopyRegister;
   fn tram_addr(&self) -> &PPULoopyRegister;
   fn address_latch(&self) -> PPUAddressLatch;
+  fn control(&self) -> PPUControlRegister;
+  fn oam(&self) -> &[PPUOAMEntry; 64];
 
+  fn ppu_memory<'a>(&'a self) -> &'a (dyn PPUMemoryTrait + 'a);
   fn ppu_memory_mut<'a>(&'a mut self) -> &'a mut (dyn PPUMemoryTrait + 'a);
   fn status_mut(&mut self) -> &mut PPUStatusRegister;
   fn control_mut(&mut self) -> &mut PPUControlRegister;
@@ -107,6 +110,18 @@ impl<I: BusInterceptor<u16, BusType = PPUMemory> + PPUMemoryTrait> PPUCPUBusTrai
     self.address_latch
   }
 
+  fn control(&self) -> PPUControlRegister {
+    self.control
+  }
+
+  fn oam(&self) -> &[PPUOAMEntry; 64] {
+    &self.oam
+  }
+
+  fn ppu_memory(&self) -> &dyn PPUMemoryTrait {
+    self.ppu_memory.as_ref()
+  }
+
   fn ppu_memory_mut(&mut self) -> &mut dyn PPUMemoryTrait {
     self.ppu_memory.as_mut()
   }
@@ -176,7 +191,9 @@ impl<I: BusInterceptor<u16, BusType = PPUMemory> + PPUMemoryTrait> Bus<PPURegist
       }
       PPURegister::PPUDATA => {
         let addr: u16 = (self.vram_addr).into();
+        self.ppu_memory.chr_data_logger_mut().set_cpu_reading(true);
         self.data_buffer = self.ppu_memory.read(addr);
+        self.ppu_memory.chr_data_logger_mut().set_cpu_reading(false);
         self.vram_addr = PPULoopyRegister::from(
           u16::from(self.vram_addr) + if self.control.increment_mode() { 32 } else { 1 },
         );
@@ -5,6 +5,8 @@ use dyn_clone::DynClone;
 use crate::{
   bus::Bus,
   cartridge::{bus_interceptor::BusInterceptor, CartridgeMirroring},
+  debugger::CHRDataLogger,
+  nes::{SharedBuffer, SharedMemory},
 };
 
 use super::PPUMaskRegister;
@@ -25,6 +27,9 @@ where
 pub trait PPUMemoryTrait: Bus<u16> {
   fn mask(&self) -> PPUMaskRegister;
   fn name_tables(&self) -> &[[u8; 1024]; 4];
+  fn palette_ram(&self) -> &[u8; 32];
+  fn chr_data_logger(&self) -> &CHRDataLogger;
+  fn chr_data_logger_mut(&mut self) -> &mut CHRDataLogger;
 }
 
 impl<I: BusInterceptor<u16, BusType = PPUMemory>> PPUMemoryTrait for I {
@@ -35,15 +40,28 @@ impl<I: BusInterceptor<u16, BusType = PPUMemory>> PPUMemoryTrait for I {
   fn name_tables(&self) -> &[[u8; 1024]; 4] {
     self.get_inner().name_tables()
   }
+
+  fn palette_ram(&self) -> &[u8; 32] {
+    self.get_inner().palette_ram()
+  }
+
+  fn chr_data_logger(&self) -> &CHRDataLogger {
+    self.get_inner().chr_data_logger()
+  }
+
+  fn chr_data_logger_mut(&mut self) -> &mut CHRDataLogger {
+    self.get_inner_mut().chr_data_logger_mut()
+  }
 }
 
 #[derive(Debug, Clone)]
 pub struct PPUMemory {
   pub mask: PPUMaskRegister,
   pub palette_ram: [u8; 32],
-  pub name_tables: [[u8; 1024]; 4],
-  pub pattern_tables: [[u8; 4096]; 2],
+  pub name_tables: SharedMemory<[[u8; 1024]; 4]>,
+  pub pattern_tables: SharedMemory<[[u8; 4096]; 2]>,
   pub mirroring: CartridgeMirroring,
+  pub chr_data_logger: CHRDataLogger,
 }
 
 impl PPUMemory {
@@ -51,11 +69,17 @@ impl PPUMemory {
     Self {
       mask: PPUMaskRegister::from(0),
       mirroring,
-      name_tables: [[0; 1024]; 4],
-      pattern_tables: [[0; 4096]; 2],
+      name_tables: SharedMemory::new([[0; 1024]; 4]),
+      pattern_tables: SharedMemory::new([[0; 4096]; 2]),
       palette_ram: [0; 32],
+      chr_data_logger: CHRDataLogger::new(),
     }
   }
+
+  pub fn visit_shared_buffers(&mut self, visit: &mut dyn FnMut(&mut dyn SharedBuffer)) {
+    visit(&mut self.name_tables);
+    visit(&mut self.pattern_tables);
+  }
 }
 
 impl PPUMemoryTrait for PPUMemory {
@@ -66,6 +90,18 @@ impl PPUMemoryTrait for PPUMemory {
   fn name_tables(&self) -> &[[u8; 1024]; 4] {
     &self.name_tables
   }
+
+  fn palette_ram(&self) -> &[u8; 32] {
+    &self.palette_ram
+  }
+
+  fn chr_data_logger(&self) -> &CHRDataLogger {
+    &self.chr_data_logger
+  }
+
+  fn chr_data_logger_mut(&mut self) -> &mut CHRDataLogger {
+    &mut self.chr_data_logger
+  }
 }
 
 impl Bus<u16> for PPUMemory {
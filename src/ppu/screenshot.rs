@@ -0,0 +1,66 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::settings::Overscan;
+
+use super::{Pixbuf, BYTES_PER_PIXEL};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenshotOptions {
+  /// Each pixel becomes a `scale` × `scale` square.
+  pub scale: u32,
+  /// What to crop off, usually the game's overscan setting so it matches the screen.
+  pub overscan: Overscan,
+}
+
+impl Default for ScreenshotOptions {
+  fn default() -> Self {
+    Self {
+      scale: 1,
+      overscan: Overscan::default(),
+    }
+  }
+}
+
+/// Screenshots of `game.nes` are saved next to it as `game-<frame>.png`.
+pub fn screenshot_path(rom_path: &Path, frame: u64) -> PathBuf {
+  let rom_name = rom_path
+    .file_stem()
+    .map(|stem| stem.to_string_lossy())
+    .unwrap_or_default();
+  rom_path.with_file_name(format!("{}-{:06}.png", rom_name, frame))
+}
+
+impl Pixbuf {
+  pub fn encode_png(&self, options: ScreenshotOptions) -> Result<Vec<u8>, anyhow::Error> {
+    let image = options.overscan.crop(&self.image);
+    let row_size = (image.width * BYTES_PER_PIXEL) as usize;
+    let scale = options.scale.max(1);
+
+    let mut rgb = Vec::new();
+    for row in image.data.chunks_exact(row_size) {
+      let scaled_row = row
+        .chunks_exact(BYTES_PER_PIXEL as usize)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]].repeat(scale as usize))
+        .collect::<Vec<_>>();
+      for _ in 0..scale {
+        rgb.extend_from_slice(&scaled_row);
+      }
+    }
+
+    let mut png_data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_data, image.width * scale, image.height * scale);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&rgb)?;
+
+    Ok(png_data)
+  }
+
+  pub fn save_png(&self, path: &Path, options: ScreenshotOptions) -> Result<(), anyhow::Error> {
+    fs::write(path, self.encode_png(options)?)?;
+    Ok(())
+  }
+}
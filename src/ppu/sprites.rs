@@ -3,6 +3,9 @@ use bytemuck::{Pod, Zeroable};
 
 use super::{PPUCPUBusTrait, PPU};
 
+pub const OAM_SPRITES: usize = 64;
+pub const MAX_SPRITES_PER_SCANLINE: usize = 8;
+
 #[bitfield(u32)]
 #[derive(Pod, Zeroable)]
 pub struct PPUOAMEntry {
@@ -93,15 +96,17 @@ impl PPU {
         });
       }
 
-      if self.sprite_scanline.len() == 9 {
+      if self.sprite_scanline.len() > MAX_SPRITES_PER_SCANLINE && !self.unlimited_sprites {
         break;
       }
     }
 
     ppu_cpu_bus
       .status_mut()
-      .set_sprite_overflow(self.sprite_scanline.len() > 8);
-    self.sprite_scanline.truncate(8);
+      .set_sprite_overflow(self.sprite_scanline.len() > MAX_SPRITES_PER_SCANLINE);
+    if !self.unlimited_sprites {
+      self.sprite_scanline.truncate(MAX_SPRITES_PER_SCANLINE);
+    }
   }
 
   pub fn load_sprite_data_for_next_scanline(
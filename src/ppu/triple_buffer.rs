@@ -0,0 +1,75 @@
+use std::sync::{
+  atomic::{AtomicUsize, Ordering},
+  Arc, Mutex, MutexGuard,
+};
+
+// set on the shared slot's index when it holds something the reader hasn't seen yet
+const FRESH: usize = 0b100;
+const INDEX_MASK: usize = 0b011;
+
+struct Slots<T> {
+  buffers: [Mutex<T>; 3],
+  shared: AtomicUsize,
+}
+
+/// Hands frames from the emulation thread to the UI without either waiting on the other. The
+/// writer fills its back buffer and publishes it by swapping it with the shared one; the reader
+/// picks up whatever was published last, skipping any frames it missed. Each buffer only ever
+/// belongs to one side at a time, so the locks around them are never contended.
+pub fn triple_buffer<T: Clone>(initial: T) -> (TripleBufferWriter<T>, TripleBufferReader<T>) {
+  let slots = Arc::new(Slots {
+    buffers: [
+      Mutex::new(initial.clone()),
+      Mutex::new(initial.clone()),
+      Mutex::new(initial),
+    ],
+    shared: AtomicUsize::new(1),
+  });
+
+  (
+    TripleBufferWriter {
+      slots: slots.clone(),
+      back: 0,
+    },
+    TripleBufferReader { slots, front: 2 },
+  )
+}
+
+pub struct TripleBufferWriter<T> {
+  slots: Arc<Slots<T>>,
+  back: usize,
+}
+
+impl<T> TripleBufferWriter<T> {
+  /// The buffer to draw the next frame into. It holds an older frame until it's overwritten.
+  pub fn back_buffer(&mut self) -> MutexGuard<'_, T> {
+    self.slots.buffers[self.back].lock().unwrap()
+  }
+
+  pub fn publish(&mut self) {
+    let previous = self.slots.shared.swap(self.back | FRESH, Ordering::AcqRel);
+    self.back = previous & INDEX_MASK;
+  }
+}
+
+pub struct TripleBufferReader<T> {
+  slots: Arc<Slots<T>>,
+  front: usize,
+}
+
+impl<T> TripleBufferReader<T> {
+  /// Whether a frame has been published since the last `read`.
+  pub fn has_new_frame(&self) -> bool {
+    self.slots.shared.load(Ordering::Acquire) & FRESH != 0
+  }
+
+  /// The most recently published frame.
+  pub fn read(&mut self) -> MutexGuard<'_, T> {
+    if self.has_new_frame() {
+      let previous = self.slots.shared.swap(self.front, Ordering::AcqRel);
+      self.front = previous & INDEX_MASK;
+    }
+
+    self.slots.buffers[self.front].lock().unwrap()
+  }
+}
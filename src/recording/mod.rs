@@ -0,0 +1,64 @@
+mod recorder;
+mod wav_writer;
+mod y4m_writer;
+
+pub use recorder::*;
+pub use wav_writer::*;
+pub use y4m_writer::*;
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, io::BufReader};
+
+  use crate::{
+    apu::emulated_time,
+    nes::{INESRom, NES},
+    ppu::{Palette, Pixbuf},
+  };
+
+  use super::*;
+
+  #[test]
+  fn test_record_frames() {
+    let rom = INESRom::from_reader(&mut BufReader::new(
+      include_bytes!("../../smoketest/nestest.nes").as_slice(),
+    ))
+    .unwrap();
+    let (sender, _receiver) = smol::channel::unbounded();
+    let mut machine = NES::from_rom(rom, sender);
+    let mut pixbuf = Pixbuf::new();
+    let palette = Palette::default();
+
+    let directory = std::env::temp_dir().join(format!("recording-test-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let (video_path, audio_path) = recording_paths(&directory.join("nestest.nes"), 0);
+    let mut recorder = Recorder::create(&video_path, &audio_path).unwrap();
+    machine.recording_sender = Some(recorder.command_sender());
+
+    // the sound lasts exactly as long as the frames took to emulate
+    let frames = 60;
+    let recording_start = emulated_time(machine.state.cpu_cycle_count);
+    for _ in 0..frames {
+      let start = emulated_time(machine.state.cpu_cycle_count);
+      machine.execute_frame(&mut pixbuf);
+      let end = emulated_time(machine.state.cpu_cycle_count);
+      recorder.record_frame(&pixbuf.frame, &palette, start, end);
+    }
+    assert_eq!(recorder.finish().unwrap(), video_path);
+
+    let video = fs::read(&video_path).unwrap();
+    let header = b"YUV4MPEG2 W256 H240 F39375000:655171 Ip A8:7 C444\n";
+    assert_eq!(&video[..header.len()], header);
+    assert_eq!(video.len(), header.len() + frames * (6 + 256 * 240 * 3));
+
+    let audio = fs::read(&audio_path).unwrap();
+    let data_size = u32::from_le_bytes(audio[40..44].try_into().unwrap()) as usize;
+    assert_eq!(data_size, audio.len() - 44);
+    let seconds = data_size as f64 / 2.0 / f64::from(RECORDING_SAMPLE_RATE);
+    let emulated_seconds =
+      (emulated_time(machine.state.cpu_cycle_count) - recording_start).as_secs_f64();
+    assert!((seconds - emulated_seconds).abs() < 0.001, "{}", seconds);
+
+    fs::remove_dir_all(&directory).unwrap();
+  }
+}
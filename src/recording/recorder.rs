@@ -0,0 +1,138 @@
+use std::{
+  fs::File,
+  io::BufWriter,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use anyhow::Context;
+use smol::channel::{Receiver, Sender};
+
+use crate::{
+  apu::{APUSynth, APUSynthChannel},
+  audio::synth::{OfflineSynth, SynthCommand},
+  ppu::{IndexedFrameBuffer, Palette, PIXEL_BUFFER_HEIGHT, PIXEL_BUFFER_WIDTH},
+};
+
+use super::{WavWriter, Y4MWriter};
+
+pub const RECORDING_SAMPLE_RATE: u32 = 48_000;
+
+/// A recording of `game.nes` is saved next to it as `game-<frame>.y4m` and `game-<frame>.wav`.
+pub fn recording_paths(rom_path: &Path, frame: u64) -> (PathBuf, PathBuf) {
+  let rom_name = rom_path
+    .file_stem()
+    .map(|stem| stem.to_string_lossy())
+    .unwrap_or_default();
+  let base_name = format!("{}-{:06}", rom_name, frame);
+  (
+    rom_path.with_file_name(format!("{}.y4m", base_name)),
+    rom_path.with_file_name(format!("{}.wav", base_name)),
+  )
+}
+
+/// Records every emulated frame, and the sound made during it, so the video and audio line up
+/// however fast emulation actually ran. The picture is the palette's colors, without the NTSC
+/// filter.
+pub struct Recorder {
+  video: Y4MWriter<BufWriter<File>>,
+  audio: WavWriter<BufWriter<File>>,
+  video_path: PathBuf,
+  synth: OfflineSynth<APUSynthChannel>,
+  command_sender: Sender<SynthCommand<APUSynthChannel>>,
+  command_receiver: Receiver<SynthCommand<APUSynthChannel>>,
+  // emulated time recorded so far
+  recorded_time: Duration,
+  pixels: Vec<[u8; 3]>,
+  samples: Vec<i16>,
+  error: Option<anyhow::Error>,
+}
+
+impl Recorder {
+  pub fn create(video_path: &Path, audio_path: &Path) -> Result<Self, anyhow::Error> {
+    let video_file = File::create(video_path)
+      .with_context(|| format!("Couldn't create {}", video_path.display()))?;
+    let audio_file = File::create(audio_path)
+      .with_context(|| format!("Couldn't create {}", audio_path.display()))?;
+    let (command_sender, command_receiver) = smol::channel::unbounded();
+
+    Ok(Self {
+      video: Y4MWriter::new(
+        BufWriter::new(video_file),
+        PIXEL_BUFFER_WIDTH,
+        PIXEL_BUFFER_HEIGHT,
+      )?,
+      audio: WavWriter::new(BufWriter::new(audio_file), RECORDING_SAMPLE_RATE)?,
+      video_path: video_path.to_path_buf(),
+      synth: APUSynth::new().offline_synth(RECORDING_SAMPLE_RATE),
+      command_sender,
+      command_receiver,
+      recorded_time: Duration::ZERO,
+      pixels: Vec::new(),
+      samples: Vec::new(),
+      error: None,
+    })
+  }
+
+  /// Where the APU should send its commands, timed in emulated time (see `NES::recording_sender`).
+  pub fn command_sender(&self) -> Sender<SynthCommand<APUSynthChannel>> {
+    self.command_sender.clone()
+  }
+
+  /// Adds a frame, with the sound from the APU commands sent between the emulated times `start`
+  /// and `end`. After a write fails, this does nothing and `take_error` returns why.
+  pub fn record_frame(
+    &mut self,
+    frame: &IndexedFrameBuffer,
+    palette: &Palette,
+    start: Duration,
+    end: Duration,
+  ) {
+    if self.error.is_none() {
+      self.error = self.write_frame(frame, palette, start, end).err();
+    }
+  }
+
+  fn write_frame(
+    &mut self,
+    frame: &IndexedFrameBuffer,
+    palette: &Palette,
+    start: Duration,
+    end: Duration,
+  ) -> Result<(), anyhow::Error> {
+    while let Ok(command) = self.command_receiver.try_recv() {
+      let offset = command.time().saturating_sub(start);
+      self
+        .synth
+        .render_until(self.recorded_time + offset, &mut self.samples);
+      self.synth.handle_command(command);
+    }
+    self.recorded_time += end.saturating_sub(start);
+    self
+      .synth
+      .render_until(self.recorded_time, &mut self.samples);
+    self.audio.write_samples(&self.samples)?;
+    self.samples.clear();
+
+    self.pixels.clear();
+    self
+      .pixels
+      .extend(frame.pixels.iter().map(|pixel| palette.color(*pixel)));
+    self.video.write_frame(&self.pixels)
+  }
+
+  pub fn has_error(&self) -> bool {
+    self.error.is_some()
+  }
+
+  pub fn take_error(&mut self) -> Option<anyhow::Error> {
+    self.error.take()
+  }
+
+  /// Finishes both files, returning the video's path.
+  pub fn finish(self) -> Result<PathBuf, anyhow::Error> {
+    self.video.finish()?;
+    self.audio.finish()?;
+    Ok(self.video_path)
+  }
+}
@@ -0,0 +1,57 @@
+use std::io::{Seek, SeekFrom, Write};
+
+const HEADER_SIZE: u32 = 44;
+
+/// Writes 16-bit mono PCM WAV. The sizes in the header are filled in by `finish`.
+pub struct WavWriter<W: Write + Seek> {
+  writer: W,
+  data_size: u32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+  pub fn new(mut writer: W, sample_rate: u32) -> Result<Self, anyhow::Error> {
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&0u32.to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // PCM, 1 channel
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    // bytes per second, bytes per sample and bits per sample
+    writer.write_all(&(sample_rate * 2).to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&0u32.to_le_bytes())?;
+
+    Ok(Self {
+      writer,
+      data_size: 0,
+    })
+  }
+
+  pub fn write_samples(&mut self, samples: &[i16]) -> Result<(), anyhow::Error> {
+    let bytes = samples
+      .iter()
+      .flat_map(|sample| sample.to_le_bytes())
+      .collect::<Vec<_>>();
+    self.writer.write_all(&bytes)?;
+    self.data_size += bytes.len() as u32;
+    Ok(())
+  }
+
+  pub fn finish(mut self) -> Result<W, anyhow::Error> {
+    self.writer.seek(SeekFrom::Start(4))?;
+    self
+      .writer
+      .write_all(&(HEADER_SIZE - 8 + self.data_size).to_le_bytes())?;
+    self
+      .writer
+      .seek(SeekFrom::Start(u64::from(HEADER_SIZE) - 4))?;
+    self.writer.write_all(&self.data_size.to_le_bytes())?;
+    self.writer.seek(SeekFrom::End(0))?;
+    self.writer.flush()?;
+    Ok(self.writer)
+  }
+}
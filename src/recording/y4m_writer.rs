@@ -0,0 +1,55 @@
+use std::io::Write;
+
+// NTSC frames are 357,366 PPU cycles at 21.477272MHz / 4
+const FRAME_RATE_NUMERATOR: u32 = 39_375_000;
+const FRAME_RATE_DENOMINATOR: u32 = 655_171;
+
+/// Writes uncompressed YUV 4:4:4 video in the YUV4MPEG2 format that ffmpeg and most players read,
+/// at the NES's exact frame rate and 8:7 pixel aspect ratio.
+pub struct Y4MWriter<W: Write> {
+  writer: W,
+  width: u32,
+  height: u32,
+  planes: Vec<u8>,
+}
+
+impl<W: Write> Y4MWriter<W> {
+  pub fn new(mut writer: W, width: u32, height: u32) -> Result<Self, anyhow::Error> {
+    writeln!(
+      writer,
+      "YUV4MPEG2 W{} H{} F{}:{} Ip A8:7 C444",
+      width, height, FRAME_RATE_NUMERATOR, FRAME_RATE_DENOMINATOR
+    )?;
+
+    Ok(Self {
+      writer,
+      width,
+      height,
+      planes: Vec::new(),
+    })
+  }
+
+  pub fn write_frame(&mut self, pixels: &[[u8; 3]]) -> Result<(), anyhow::Error> {
+    let plane_size = (self.width * self.height) as usize;
+    self.planes.resize(plane_size * 3, 0);
+    let (y_plane, chroma_planes) = self.planes.split_at_mut(plane_size);
+    let (u_plane, v_plane) = chroma_planes.split_at_mut(plane_size);
+
+    // BT.601 limited range, what players assume for standard definition video
+    for (index, [r, g, b]) in pixels.iter().take(plane_size).enumerate() {
+      let (r, g, b) = (f32::from(*r), f32::from(*g), f32::from(*b));
+      y_plane[index] = (16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0).round() as u8;
+      u_plane[index] = (128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0).round() as u8;
+      v_plane[index] = (128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0).round() as u8;
+    }
+
+    self.writer.write_all(b"FRAME\n")?;
+    self.writer.write_all(&self.planes)?;
+    Ok(())
+  }
+
+  pub fn finish(mut self) -> Result<W, anyhow::Error> {
+    self.writer.flush()?;
+    Ok(self.writer)
+  }
+}
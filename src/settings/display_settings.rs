@@ -0,0 +1,174 @@
+use anyhow::anyhow;
+
+use crate::{
+  debugger::RgbaImage,
+  ppu::{FrameBlend, BYTES_PER_PIXEL, PIXEL_BUFFER_HEIGHT, PIXEL_BUFFER_WIDTH},
+};
+
+use super::parse_bool;
+
+/// NES pixels are a little wider than they are tall on a TV.
+pub const PIXEL_ASPECT_RATIO: f32 = 8.0 / 7.0;
+
+/// The most that can be cropped from each edge of the picture, in NES pixels.
+pub const MAX_OVERSCAN: u32 = 32;
+
+/// How many NES pixels to crop from each edge of the picture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Overscan {
+  pub top: u32,
+  pub bottom: u32,
+  pub left: u32,
+  pub right: u32,
+}
+
+impl Overscan {
+  /// The size of the picture left after cropping, in NES pixels.
+  pub fn cropped_size(&self) -> (u32, u32) {
+    (
+      PIXEL_BUFFER_WIDTH - self.left - self.right,
+      PIXEL_BUFFER_HEIGHT - self.top - self.bottom,
+    )
+  }
+
+  /// Crops `image`, which can be a whole multiple of the NES's resolution, e.g. from the NTSC
+  /// filter.
+  pub fn crop(&self, image: &RgbaImage) -> RgbaImage {
+    let x_scale = image.width / PIXEL_BUFFER_WIDTH;
+    let y_scale = image.height / PIXEL_BUFFER_HEIGHT;
+    let (width, height) = self.cropped_size();
+    let row_size = (image.width * BYTES_PER_PIXEL) as usize;
+    let row_start = (self.left * x_scale * BYTES_PER_PIXEL) as usize;
+    let row_end = row_size - (self.right * x_scale * BYTES_PER_PIXEL) as usize;
+
+    let data = image
+      .data
+      .chunks_exact(row_size)
+      .skip((self.top * y_scale) as usize)
+      .take((height * y_scale) as usize)
+      .flat_map(|row| &row[row_start..row_end])
+      .copied()
+      .collect();
+
+    RgbaImage {
+      width: width * x_scale,
+      height: height * y_scale,
+      data,
+    }
+  }
+
+  fn parse(value: &str) -> Result<Self, anyhow::Error> {
+    let edges = value
+      .split_whitespace()
+      .map(|edge| {
+        edge
+          .parse::<u32>()
+          .ok()
+          .filter(|edge| *edge <= MAX_OVERSCAN)
+          .ok_or_else(|| anyhow!("Invalid overscan {}", edge))
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let [top, bottom, left, right] = edges[..] else {
+      return Err(anyhow!("Expected overscan = top bottom left right"));
+    };
+
+    Ok(Self {
+      top,
+      bottom,
+      left,
+      right,
+    })
+  }
+
+  fn format(&self) -> String {
+    format!("{} {} {} {}", self.top, self.bottom, self.left, self.right)
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DisplaySettings {
+  pub overscan: Overscan,
+  /// Only scale the picture by whole multiples, so every NES pixel is the same height.
+  pub integer_scaling: bool,
+  pub aspect_correction: bool,
+  pub fullscreen: bool,
+  pub frame_blend: Option<FrameBlend>,
+}
+
+impl DisplaySettings {
+  /// The size to draw the picture at to fit it in `available_width` × `available_height`.
+  pub fn screen_size(&self, available_width: f32, available_height: f32) -> (f32, f32) {
+    let (width, height) = self.overscan.cropped_size();
+    let pixel_aspect_ratio = if self.aspect_correction {
+      PIXEL_ASPECT_RATIO
+    } else {
+      1.0
+    };
+    let width = width as f32 * pixel_aspect_ratio;
+    let height = height as f32;
+
+    let scale = (available_width / width).min(available_height / height);
+    // there's no whole multiple that fits below 1x, so it just gets scaled down
+    let scale = if self.integer_scaling && scale >= 1.0 {
+      scale.floor()
+    } else {
+      scale
+    };
+
+    (width * scale, height * scale)
+  }
+
+  /// Handles a line from the settings file, returning false if it's not a display setting.
+  pub fn parse_line(&mut self, key: &str, value: &str) -> Result<bool, anyhow::Error> {
+    match key {
+      "overscan" => self.overscan = Overscan::parse(value)?,
+      "integer_scaling" => self.integer_scaling = parse_bool(key, value)?,
+      "aspect_correction" => self.aspect_correction = parse_bool(key, value)?,
+      "fullscreen" => self.fullscreen = parse_bool(key, value)?,
+      "frame_blend" => self.frame_blend = parse_frame_blend(value)?,
+      _ => return Ok(false),
+    }
+
+    Ok(true)
+  }
+
+  pub fn format(&self) -> String {
+    format!(
+      "overscan = {}\n\
+       integer_scaling = {}\n\
+       aspect_correction = {}\n\
+       fullscreen = {}\n\
+       frame_blend = {}\n",
+      self.overscan.format(),
+      self.integer_scaling,
+      self.aspect_correction,
+      self.fullscreen,
+      match self.frame_blend {
+        None => String::from("off"),
+        Some(FrameBlend::Mix(amount)) => format!("mix {}", amount),
+        Some(FrameBlend::Max) => String::from("max"),
+      }
+    )
+  }
+}
+
+fn parse_frame_blend(value: &str) -> Result<Option<FrameBlend>, anyhow::Error> {
+  match value.split_once(char::is_whitespace) {
+    Some(("mix", amount)) => {
+      let amount = amount
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|amount| (0.0..=1.0).contains(amount))
+        .ok_or_else(|| anyhow!("Expected a frame blend mix from 0 to 1, got {}", amount))?;
+      Ok(Some(FrameBlend::Mix(amount)))
+    }
+    None if value == "off" => Ok(None),
+    None if value == "max" => Ok(Some(FrameBlend::Max)),
+    _ => Err(anyhow!(
+      "Expected frame_blend to be off, mix or max, got {}",
+      value
+    )),
+  }
+}
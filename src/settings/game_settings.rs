@@ -0,0 +1,179 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+use anyhow::anyhow;
+
+use crate::ppu::{Palette, PaletteParameters, PalettePreset};
+
+use super::DisplaySettings;
+
+// Game settings are plain text, one setting per line, and live next to the ROM:
+//
+//   palette = preset 2C03
+//   palette = generated hue=0 saturation=1.2 contrast=1 brightness=0 gamma=1.8
+//   palette = file /home/me/palettes/smooth.pal
+//   overscan = 8 8 0 0
+//   integer_scaling = true
+//   frame_blend = mix 0.5
+//   unlimited_sprites = true
+//
+// Blank lines and lines starting with # are ignored.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteSetting {
+  Preset(PalettePreset),
+  Generated(PaletteParameters),
+  File(PathBuf),
+}
+
+impl Default for PaletteSetting {
+  fn default() -> Self {
+    PaletteSetting::Preset(PalettePreset::default())
+  }
+}
+
+impl PaletteSetting {
+  pub fn palette(&self) -> Result<Palette, anyhow::Error> {
+    match self {
+      PaletteSetting::Preset(preset) => Ok(preset.palette()),
+      PaletteSetting::Generated(parameters) => Ok(Palette::generate(parameters)),
+      PaletteSetting::File(path) => Palette::load(path)
+        .map_err(|err| anyhow!("Couldn't load palette {}: {}", path.display(), err)),
+    }
+  }
+
+  fn parse(value: &str) -> Result<Self, anyhow::Error> {
+    let (kind, rest) = value.split_once(char::is_whitespace).unwrap_or((value, ""));
+    let rest = rest.trim();
+
+    match kind {
+      "preset" => Ok(PaletteSetting::Preset(
+        PalettePreset::from_str(rest).map_err(|_| anyhow!("Unknown palette preset {}", rest))?,
+      )),
+      "generated" => {
+        let mut parameters = PaletteParameters::default();
+        for parameter in rest.split_whitespace() {
+          let (name, value) = parameter
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected name=value, got {}", parameter))?;
+          let value = value
+            .parse()
+            .map_err(|_| anyhow!("Invalid value for {}: {}", name, value))?;
+          match name {
+            "hue" => parameters.hue = value,
+            "saturation" => parameters.saturation = value,
+            "contrast" => parameters.contrast = value,
+            "brightness" => parameters.brightness = value,
+            "gamma" => parameters.gamma = value,
+            _ => return Err(anyhow!("Unknown palette parameter {}", name)),
+          }
+        }
+        Ok(PaletteSetting::Generated(parameters))
+      }
+      "file" if !rest.is_empty() => Ok(PaletteSetting::File(PathBuf::from(rest))),
+      _ => Err(anyhow!(
+        "Expected palette to be preset, generated or file, got {}",
+        value
+      )),
+    }
+  }
+
+  fn format(&self) -> String {
+    match self {
+      PaletteSetting::Preset(preset) => format!("preset {}", preset),
+      PaletteSetting::Generated(parameters) => format!(
+        "generated hue={} saturation={} contrast={} brightness={} gamma={}",
+        parameters.hue,
+        parameters.saturation,
+        parameters.contrast,
+        parameters.brightness,
+        parameters.gamma
+      ),
+      PaletteSetting::File(path) => format!("file {}", path.display()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameSettings {
+  pub palette: PaletteSetting,
+  pub display: DisplaySettings,
+  /// Draws more than 8 sprites per scanline. See `PPU::unlimited_sprites`.
+  pub unlimited_sprites: bool,
+}
+
+impl GameSettings {
+  /// Settings for `game.nes` are kept in `game.settings`.
+  pub fn path_for_rom(rom_path: &Path) -> PathBuf {
+    rom_path.with_extension("settings")
+  }
+
+  pub fn parse(input: &str) -> Result<Self, anyhow::Error> {
+    let mut settings = Self::default();
+
+    for (line_index, line) in input.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      settings
+        .parse_line(line)
+        .map_err(|err| anyhow!("Line {}: {}", line_index + 1, err))?;
+    }
+
+    Ok(settings)
+  }
+
+  fn parse_line(&mut self, line: &str) -> Result<(), anyhow::Error> {
+    let (key, value) = line
+      .split_once('=')
+      .ok_or_else(|| anyhow!("Expected key = value"))?;
+
+    match key.trim() {
+      "palette" => self.palette = PaletteSetting::parse(value.trim())?,
+      "unlimited_sprites" => {
+        self.unlimited_sprites = parse_bool("unlimited_sprites", value.trim())?
+      }
+      key => {
+        if !self.display.parse_line(key, value.trim())? {
+          return Err(anyhow!("Unknown setting {}", key));
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn format(&self) -> String {
+    format!(
+      "palette = {}\n{}unlimited_sprites = {}\n",
+      self.palette.format(),
+      self.display.format(),
+      self.unlimited_sprites
+    )
+  }
+
+  /// Loads the settings at `path`, or the defaults if there aren't any yet.
+  pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+
+    Self::parse(&fs::read_to_string(path)?)
+  }
+
+  pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+    fs::write(path, self.format())?;
+    Ok(())
+  }
+}
+
+pub fn parse_bool(key: &str, value: &str) -> Result<bool, anyhow::Error> {
+  value
+    .parse()
+    .map_err(|_| anyhow!("Expected {} to be true or false, got {}", key, value))
+}
@@ -0,0 +1,88 @@
+mod display_settings;
+mod game_settings;
+
+pub use display_settings::*;
+pub use game_settings::*;
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use crate::ppu::{FrameBlend, PaletteParameters, PalettePreset};
+
+  use super::*;
+
+  #[test]
+  fn test_game_settings_round_trip() {
+    for palette in [
+      PaletteSetting::Preset(PalettePreset::PAL),
+      PaletteSetting::Generated(PaletteParameters {
+        hue: -7.5,
+        saturation: 1.25,
+        ..Default::default()
+      }),
+      PaletteSetting::File(PathBuf::from("/tmp/my palette.pal")),
+    ] {
+      let settings = GameSettings {
+        palette,
+        display: DisplaySettings {
+          overscan: Overscan {
+            top: 8,
+            bottom: 8,
+            left: 0,
+            right: 4,
+          },
+          aspect_correction: true,
+          frame_blend: Some(FrameBlend::Mix(0.25)),
+          ..Default::default()
+        },
+        unlimited_sprites: true,
+      };
+      assert_eq!(GameSettings::parse(&settings.format()).unwrap(), settings);
+    }
+  }
+
+  #[test]
+  fn test_parse_game_settings() {
+    let settings = GameSettings::parse("# comment\n\npalette = generated gamma=2.2\n").unwrap();
+    assert_eq!(
+      settings.palette,
+      PaletteSetting::Generated(PaletteParameters {
+        gamma: 2.2,
+        ..Default::default()
+      })
+    );
+
+    assert_eq!(GameSettings::parse("").unwrap(), GameSettings::default());
+    assert!(GameSettings::parse("palette = preset 2C09").is_err());
+    assert!(GameSettings::parse("palette = generated tint=3").is_err());
+    assert!(GameSettings::parse("volume = 11").is_err());
+    assert!(GameSettings::parse("overscan = 8 8").is_err());
+    assert!(GameSettings::parse("fullscreen = yes").is_err());
+    assert!(GameSettings::parse("frame_blend = mix 2").is_err());
+    assert_eq!(
+      GameSettings::parse("frame_blend = max")
+        .unwrap()
+        .display
+        .frame_blend,
+      Some(FrameBlend::Max)
+    );
+  }
+
+  #[test]
+  fn test_screen_size() {
+    let mut display = DisplaySettings::default();
+    assert_eq!(display.screen_size(1024.0, 1000.0), (1024.0, 960.0));
+
+    display.integer_scaling = true;
+    assert_eq!(display.screen_size(1000.0, 1000.0), (768.0, 720.0));
+    assert_eq!(display.screen_size(128.0, 240.0), (128.0, 120.0));
+
+    display.overscan.top = 8;
+    display.overscan.bottom = 8;
+    display.aspect_correction = true;
+    let (width, height) = display.screen_size(2000.0, 1000.0);
+    assert_eq!(height, 896.0);
+    assert!((width - 256.0 * 8.0 / 7.0 * 4.0).abs() < 0.01);
+  }
+}